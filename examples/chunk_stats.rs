@@ -1,7 +1,5 @@
-use cuet::ChunkReader;
+use cuet::{tag, ChunkReader};
 use std::env::args;
-use std::fs::File;
-use std::io;
 
 fn main() {
     let mut arguments = args();
@@ -12,40 +10,39 @@ fn main() {
         panic!("No argument for path");
     };
 
-    let file = File::open(wav_path).unwrap();
-    let reader = io::BufReader::new(file);
-    let mut wave_cursor = ChunkReader::new(reader).unwrap();
-
-    while let Some((tag, chunk)) = wave_cursor.read_next_chunk(None).unwrap() {
-        let tag_s = tag.iter().map(|&b| b as char).collect::<String>();
-        println!(
-            "Found \"{}\" chunk that's {} bytes long",
-            tag_s,
-            chunk.len()
-        );
-
-        if tag == *b"LIST" {
-            let ltype = &chunk[..4];
-            let ltype_s = ltype.iter().map(|&b| b as char).collect::<String>();
-            println!("\tLIST type = \"{}\"", ltype_s);
-
-            if ltype == *b"adtl" {
-                let sctype = &chunk[4..8];
-                let sctype_s =
-                    sctype.iter().map(|&b| b as char).collect::<String>();
-                println!("\tSub-chunk type = \"{}\"", sctype_s);
-
-                let sclen = u32::from_le_bytes(
-                    *chunk[8..12]
-                        .chunks_exact(4)
-                        .next()
-                        .unwrap()
-                        .first_chunk::<4>()
-                        .unwrap(),
-                ) as usize;
-
-                println!("\tSub-chunk length: {}", sclen);
+    let mut wave_cursor = ChunkReader::open(wav_path).unwrap();
+
+    wave_cursor
+        .visit_nested(&mut |path, head, chunk| {
+            let tag_s = as_string(head.tag);
+            let indent = "\t".repeat(path.len() / 2);
+
+            if path.is_empty() {
+                println!(
+                    "Found \"{}\" chunk that's {} bytes long",
+                    tag_s, head.size
+                );
+            } else {
+                let path_s = path.iter().map(as_string).collect::<Vec<_>>();
+                println!(
+                    "{}Found \"{}\" sub-chunk in {} that's {} bytes long",
+                    indent,
+                    tag_s,
+                    path_s.join("/"),
+                    chunk.len()
+                );
             }
-        }
-    }
+
+            if head.tag == tag::LIST && chunk.len() >= 4 {
+                let ltype_s = as_string(&chunk[..4]);
+                println!("{}\tLIST type = \"{}\"", indent, ltype_s);
+            }
+
+            Ok(())
+        })
+        .unwrap();
+}
+
+fn as_string(tag: impl AsRef<[u8]>) -> String {
+    tag.as_ref().iter().map(|&b| b as char).collect()
 }
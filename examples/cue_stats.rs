@@ -1,7 +1,5 @@
-use cuet::{extract_labeled_text_from_list, parse_cue_points, ChunkReader};
+use cuet::{parse_cue_points, tag, ChunkReader};
 use std::env::args;
-use std::fs::File;
-use std::io;
 
 fn main() {
     let mut arguments = args();
@@ -12,12 +10,10 @@ fn main() {
         panic!("No argument for path");
     };
 
-    let file = File::open(wav_path).unwrap();
-    let reader = io::BufReader::new(file);
-    let mut wave_cursor = ChunkReader::new(reader).unwrap();
+    let mut wave_cursor = ChunkReader::open(wav_path).unwrap();
 
     let sample_byte_ct = wave_cursor
-        .read_next_chunk(Some(*b"data"))
+        .read_next_chunk(Some(tag::DATA))
         .unwrap()
         .unwrap()
         .1
@@ -27,8 +23,8 @@ fn main() {
 
     wave_cursor.reset().unwrap();
 
-    let cue_body = wave_cursor.read_next_chunk(Some(*b"cue ")).unwrap();
-    let list_body = wave_cursor.read_next_chunk(Some(*b"LIST")).unwrap();
+    let cue_body = wave_cursor.read_next_chunk(Some(tag::CUE)).unwrap();
+    let ltxts = wave_cursor.read_labeled_texts().unwrap();
 
     if let Some((_, payload)) = cue_body {
         let cue_points = parse_cue_points(&payload[..]);
@@ -46,17 +42,7 @@ fn main() {
         println!("Cue chunk NOT found");
     }
 
-    let list = list_body.and_then(|(_, payload)| {
-        let ltxts = extract_labeled_text_from_list(&payload);
-
-        if ltxts.is_empty() {
-            None
-        } else {
-            Some(ltxts)
-        }
-    });
-
-    if let Some(ltxts) = list {
+    if !ltxts.is_empty() {
         println!("{} labeled text cue desc. sub-chunks found", ltxts.len());
         for ltxt in ltxts {
             println!("\tcue {} has length {}", ltxt.cue_id, ltxt.sample_length);
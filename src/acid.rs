@@ -0,0 +1,97 @@
+use crate::Error;
+
+pub(crate) const ACID_SZ: usize = 24;
+
+/// Contents of an "acid" chunk, which describes how an ACIDized loop is
+/// played back and time-stretched
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AcidChunk {
+    /// Bit field; see `is_one_shot` and friends
+    pub flags: u32,
+
+    /// MIDI note number of the root note, e.g. 60 for middle C
+    pub root_note: u16,
+
+    /// Undocumented, typically 0x8000
+    pub reserved_1: u16,
+
+    /// Undocumented, typically 0
+    pub reserved_2: f32,
+
+    pub beats: u32,
+    pub meter_denominator: u16,
+    pub meter_numerator: u16,
+
+    /// Beats per minute
+    pub tempo: f32,
+}
+
+impl AcidChunk {
+    pub const ONE_SHOT: u32 = 0x01;
+    pub const ROOT_NOTE_SET: u32 = 0x02;
+    pub const STRETCH: u32 = 0x04;
+    pub const DISK_BASED: u32 = 0x08;
+
+    /// Parse the body of an acid chunk.
+    ///
+    /// Errors if bytes are too short or the tempo is not positive
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        let bytes = bytes
+            .first_chunk::<ACID_SZ>()
+            .ok_or(Error::wave("acid chunk too short"))?;
+
+        let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+        let u32_at = |i: usize| {
+            u32::from_le_bytes(*bytes[i..].first_chunk::<4>().unwrap())
+        };
+
+        let acid = AcidChunk {
+            flags: u32_at(0),
+            root_note: u16_at(4),
+            reserved_1: u16_at(6),
+            reserved_2: f32::from_bits(u32_at(8)),
+            beats: u32_at(12),
+            meter_denominator: u16_at(16),
+            meter_numerator: u16_at(18),
+            tempo: f32::from_bits(u32_at(20)),
+        };
+
+        acid.validate().map(|_| acid)
+    }
+
+    /// Check that the tempo is positive.
+    ///
+    /// Errors if the tempo is zero, negative, or NaN
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.tempo > 0.0 {
+            Ok(())
+        } else {
+            Err(Error::wave(format!("Invalid acid tempo {}", self.tempo)))
+        }
+    }
+
+    /// Whether the file is played once rather than looped
+    pub fn is_one_shot(&self) -> bool {
+        self.flags & Self::ONE_SHOT != 0
+    }
+
+    /// Whether `root_note` is meaningful
+    pub fn has_root_note(&self) -> bool {
+        self.flags & Self::ROOT_NOTE_SET != 0
+    }
+
+    /// Get the bytes for the acid chunk, without any header
+    pub fn as_bytes(&self) -> [u8; ACID_SZ] {
+        let mut bytes = [0u8; ACID_SZ];
+        bytes[..4].copy_from_slice(&self.flags.to_le_bytes()[..]);
+        bytes[4..6].copy_from_slice(&self.root_note.to_le_bytes()[..]);
+        bytes[6..8].copy_from_slice(&self.reserved_1.to_le_bytes()[..]);
+        bytes[8..12].copy_from_slice(&self.reserved_2.to_le_bytes()[..]);
+        bytes[12..16].copy_from_slice(&self.beats.to_le_bytes()[..]);
+        bytes[16..18]
+            .copy_from_slice(&self.meter_denominator.to_le_bytes()[..]);
+        bytes[18..20].copy_from_slice(&self.meter_numerator.to_le_bytes()[..]);
+        bytes[20..].copy_from_slice(&self.tempo.to_le_bytes()[..]);
+        bytes
+    }
+}
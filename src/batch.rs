@@ -0,0 +1,112 @@
+use crate::{
+    cue_chunk_with, label_chunk_with, ChunkWriter, CuePoint, Error, LabeledText,
+};
+use std::io::{Read, Seek, Write};
+
+/// Appends to a WAV collected in memory, made with `ChunkWriter::buffered`,
+/// for stamping many small chunks.  Pending chunks are written by `flush` in
+/// a single append with a single update of the WAV size, rather than with a
+/// seek and write of the size per chunk.  Chunks still pending when dropped
+/// are flushed with any error ignored, so call `flush` to see errors.
+#[derive(Debug)]
+pub struct AppendBuffer<'a, Cursor: Read + Write + Seek> {
+    writer: &'a mut ChunkWriter<Cursor>,
+
+    // Tag, body, and pad byte of each chunk not yet written
+    pending: Vec<([u8; 4], Vec<u8>, u8)>,
+}
+
+impl<Cursor: Read + Write + Seek> AppendBuffer<'_, Cursor> {
+    /// Queue a chunk with the given tag and body, as for
+    /// `ChunkWriter::append_chunk`
+    pub fn append_chunk(&mut self, tag: [u8; 4], payload: &[u8]) {
+        self.append_chunk_with_pad(tag, payload, 0);
+    }
+
+    /// Queue a chunk with the given tag and body and the pad byte written
+    /// after it if odd-sized, as for `ChunkWriter::append_chunk_with_pad`
+    pub fn append_chunk_with_pad(
+        &mut self,
+        tag: [u8; 4],
+        payload: &[u8],
+        pad: u8,
+    ) {
+        self.pending.push((tag, payload.to_vec(), pad));
+    }
+
+    /// Queue a "cue " chunk, as for `ChunkWriter::append_cue_chunk`
+    ///
+    /// Errors if resulting chunk is too large
+    pub fn append_cue_chunk(&mut self, cues: &[CuePoint]) -> Result<(), Error> {
+        if cues.is_empty() && !self.writer.allow_empty {
+            return Ok(());
+        }
+
+        let (tag, payload) = cue_chunk_with(
+            &self.writer.cue_points_to_write(cues),
+            self.writer.endianness,
+        )?;
+        self.pending.push((tag, payload, 0));
+        Ok(())
+    }
+
+    /// Queue an "adtl" LIST chunk of labeled-texts, as for
+    /// `ChunkWriter::append_label_chunk`
+    ///
+    /// Errors if resulting chunk is too large
+    pub fn append_label_chunk(
+        &mut self,
+        labeled_texts: &[LabeledText],
+    ) -> Result<(), Error> {
+        if labeled_texts.is_empty() && !self.writer.allow_empty {
+            return Ok(());
+        }
+
+        let (tag, payload) =
+            label_chunk_with(labeled_texts, self.writer.endianness)?;
+        self.pending.push((tag, payload, 0));
+        Ok(())
+    }
+
+    /// Number of chunks queued and not yet written
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Write every queued chunk, then update WAV size in header once.  A
+    /// failure leaves the WAVE unchanged and the chunks queued.
+    ///
+    /// Errors if underlying cursor errors or resulting sizes cannot be
+    /// recorded
+    pub fn flush(&mut self) -> Result<(), Error> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        let chunks = self
+            .pending
+            .iter()
+            .map(|(tag, payload, pad)| (*tag, &payload[..], *pad))
+            .collect::<Vec<_>>();
+        self.writer.append_chunks(&chunks)?;
+        self.pending.clear();
+        Ok(())
+    }
+}
+
+impl<Cursor: Read + Write + Seek> Drop for AppendBuffer<'_, Cursor> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+impl<Cursor: Read + Write + Seek> ChunkWriter<Cursor> {
+    /// Start collecting appends in memory, written together when the buffer
+    /// is flushed or dropped; see `AppendBuffer`
+    pub fn buffered(&mut self) -> AppendBuffer<'_, Cursor> {
+        AppendBuffer {
+            writer: self,
+            pending: vec![],
+        }
+    }
+}
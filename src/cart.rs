@@ -0,0 +1,197 @@
+use crate::{CuePoint, Error};
+
+pub(crate) const CART_MIN_SZ: usize = 2048;
+const TIMER_CT: usize = 8;
+const RESERVED_SZ: usize = 276;
+
+/// A cart chunk timer, marking a position in the audio such as the start of a
+/// segue or the end of an intro
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CartTimer {
+    /// Four-character usage code, e.g. "SEG1" or "INTe"; all zeros if unused
+    pub usage: [u8; 4],
+
+    /// Sample offset from the start of the audio
+    pub value: u32,
+}
+
+impl CartTimer {
+    pub fn is_used(&self) -> bool {
+        self.usage != [0u8; 4]
+    }
+
+    /// Get the simplest cue point marking the same sample as the timer
+    pub fn to_cue_point(&self, id: u32) -> CuePoint {
+        CuePoint::from_sample_offset(id, self.value)
+    }
+}
+
+/// Contents of an AES46 "cart" chunk, used by radio automation systems.  Text
+/// fields are stored in fixed-width, null-padded fields in the file and are
+/// truncated to fit when written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CartChunk {
+    /// e.g. "0101" for version 1.01
+    pub version: String,
+    pub title: String,
+    pub artist: String,
+    pub cut_id: String,
+    pub client_id: String,
+    pub category: String,
+    pub classification: String,
+    pub out_cue: String,
+
+    /// yyyy-mm-dd
+    pub start_date: String,
+
+    /// hh:mm:ss
+    pub start_time: String,
+    pub end_date: String,
+    pub end_time: String,
+    pub producer_app_id: String,
+    pub producer_app_version: String,
+    pub user_def: String,
+
+    /// Sample value for 0 dB reference
+    pub level_reference: i32,
+
+    pub timers: [CartTimer; TIMER_CT],
+    pub reserved: [u8; RESERVED_SZ],
+    pub url: String,
+
+    /// Free-form text following the fixed-width fields
+    pub tag_text: String,
+}
+
+// Widths in bytes of the leading fixed-width text fields, in file order
+const TEXT_WIDTHS: [usize; 15] =
+    [4, 64, 64, 64, 64, 64, 64, 64, 10, 8, 10, 8, 64, 64, 64];
+const URL_WIDTH: usize = 1024;
+
+fn parse_text(bytes: &[u8]) -> String {
+    let text = bytes.split(|&b| b == 0).next().unwrap_or(&[]);
+    String::from_utf8_lossy(text).to_string()
+}
+
+// Text longer than the field is cut at the last character boundary that fits
+fn write_text(bytes: &mut Vec<u8>, text: &str, width: usize) {
+    let mut len = text.len().min(width);
+    while !text.is_char_boundary(len) {
+        len -= 1;
+    }
+
+    let text = &text.as_bytes()[..len];
+    bytes.extend_from_slice(text);
+    bytes.resize(bytes.len() + width - text.len(), 0);
+}
+
+impl CartChunk {
+    /// Parse the body of a cart chunk.
+    ///
+    /// Errors if bytes are too short to contain the fixed-width fields
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < CART_MIN_SZ {
+            return Err(Error::wave("cart chunk too short"));
+        }
+
+        let mut rest = bytes;
+        let mut next = |len: usize| {
+            let (field, tail) = rest.split_at(len);
+            rest = tail;
+            field
+        };
+
+        let mut texts =
+            TEXT_WIDTHS.map(|width| parse_text(next(width))).into_iter();
+        let mut text = || texts.next().unwrap();
+
+        let level_reference =
+            i32::from_le_bytes(*next(4).first_chunk::<4>().unwrap());
+
+        let timers = [(); TIMER_CT].map(|_| {
+            let timer = next(8);
+            CartTimer {
+                usage: *timer.first_chunk::<4>().unwrap(),
+                value: u32::from_le_bytes(*timer.last_chunk::<4>().unwrap()),
+            }
+        });
+
+        let reserved = *next(RESERVED_SZ).first_chunk::<RESERVED_SZ>().unwrap();
+        let url = parse_text(next(URL_WIDTH));
+        let tag_text = parse_text(rest);
+
+        Ok(CartChunk {
+            version: text(),
+            title: text(),
+            artist: text(),
+            cut_id: text(),
+            client_id: text(),
+            category: text(),
+            classification: text(),
+            out_cue: text(),
+            start_date: text(),
+            start_time: text(),
+            end_date: text(),
+            end_time: text(),
+            producer_app_id: text(),
+            producer_app_version: text(),
+            user_def: text(),
+            level_reference,
+            timers,
+            reserved,
+            url,
+            tag_text,
+        })
+    }
+
+    /// Get the bytes for the cart chunk, without any header
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(CART_MIN_SZ + self.tag_text.len());
+
+        let texts = [
+            &self.version,
+            &self.title,
+            &self.artist,
+            &self.cut_id,
+            &self.client_id,
+            &self.category,
+            &self.classification,
+            &self.out_cue,
+            &self.start_date,
+            &self.start_time,
+            &self.end_date,
+            &self.end_time,
+            &self.producer_app_id,
+            &self.producer_app_version,
+            &self.user_def,
+        ];
+
+        for (text, width) in texts.iter().zip(TEXT_WIDTHS) {
+            write_text(&mut bytes, text, width);
+        }
+
+        bytes.extend_from_slice(&self.level_reference.to_le_bytes());
+
+        for timer in &self.timers {
+            bytes.extend_from_slice(&timer.usage);
+            bytes.extend_from_slice(&timer.value.to_le_bytes());
+        }
+
+        bytes.extend_from_slice(&self.reserved);
+        write_text(&mut bytes, &self.url, URL_WIDTH);
+        bytes.extend_from_slice(self.tag_text.as_bytes());
+
+        bytes
+    }
+
+    /// Get cue points for every used timer, numbered sequentially starting
+    /// with `first_id`
+    pub fn timer_cue_points(&self, first_id: u32) -> Vec<CuePoint> {
+        self.timers
+            .iter()
+            .filter(|timer| timer.is_used())
+            .zip(first_id..)
+            .map(|(timer, id)| timer.to_cue_point(id))
+            .collect()
+    }
+}
@@ -0,0 +1,272 @@
+use crate::{
+    extract_labeled_texts_with, parse_cue_points_with, tag, ChunkHead,
+    ChunkReader, Endianness, Error, FormatChunk, DEFAULT_MAX_TEXT_LEN,
+};
+use std::any::Any;
+use std::io::{Read, Seek};
+
+/// Decoder of the body of one kind of chunk into a typed value, for
+/// registering with `ChunkDecoders` to decode chunks the crate does not know,
+/// such as proprietary vendor chunks
+pub trait ChunkDecoder {
+    /// Tag of the chunks decoded
+    fn tag(&self) -> [u8; 4];
+
+    /// List type of the LIST chunks decoded, for decoders tagged "LIST", or
+    /// `None` to decode every LIST without a decoder of its own list type
+    fn list_type(&self) -> Option<[u8; 4]> {
+        None
+    }
+
+    /// Decode the body of a chunk, without any header, into a value to be
+    /// recovered with `downcast_ref` to the type the decoder produces
+    ///
+    /// Errors if the body is malformed
+    fn decode(&self, bytes: &[u8]) -> Result<Box<dyn Any>, Error>;
+
+    /// Decode the body of a chunk from a file of the given byte order, as
+    /// `ChunkReader::decode_chunks` and `ChunkReader::describe_with` do.
+    /// Defaults to `decode`, for bodies laid out the same in every file.
+    ///
+    /// Errors if the body is malformed
+    fn decode_with(
+        &self,
+        bytes: &[u8],
+        _endianness: Endianness,
+    ) -> Result<Box<dyn Any>, Error> {
+        self.decode(bytes)
+    }
+
+    /// Describe a value this decoder produced as JSON, for
+    /// `ChunkReader::describe_with`, or `None` to only report that the chunk
+    /// decoded
+    fn describe(&self, _value: &dyn Any) -> Option<String> {
+        None
+    }
+}
+
+/// Built-in decoder of "cue " chunks into a `Vec<CuePoint>`, as by
+/// `parse_cue_points` but in the byte order given to `decode_with`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CueDecoder;
+
+impl ChunkDecoder for CueDecoder {
+    fn tag(&self) -> [u8; 4] {
+        tag::CUE
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<Box<dyn Any>, Error> {
+        self.decode_with(bytes, Endianness::Little)
+    }
+
+    fn decode_with(
+        &self,
+        bytes: &[u8],
+        endianness: Endianness,
+    ) -> Result<Box<dyn Any>, Error> {
+        Ok(Box::new(parse_cue_points_with(bytes, endianness)))
+    }
+}
+
+/// Built-in decoder of "fmt " chunks into a `FormatChunk`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FormatDecoder;
+
+impl ChunkDecoder for FormatDecoder {
+    fn tag(&self) -> [u8; 4] {
+        tag::FMT
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<Box<dyn Any>, Error> {
+        Ok(Box::new(FormatChunk::parse(bytes)?))
+    }
+}
+
+/// Built-in decoder of "adtl" LIST chunks into the `Vec<LabeledText>` of
+/// their "ltxt" sub-chunks, as by `extract_labeled_text_from_list`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LabeledTextDecoder;
+
+impl ChunkDecoder for LabeledTextDecoder {
+    fn tag(&self) -> [u8; 4] {
+        tag::LIST
+    }
+
+    fn list_type(&self) -> Option<[u8; 4]> {
+        Some(tag::ADTL)
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<Box<dyn Any>, Error> {
+        self.decode_with(bytes, Endianness::Little)
+    }
+
+    fn decode_with(
+        &self,
+        bytes: &[u8],
+        endianness: Endianness,
+    ) -> Result<Box<dyn Any>, Error> {
+        if !bytes.starts_with(&tag::ADTL) {
+            return Err(Error::wave("Not an \"adtl\" LIST"));
+        }
+
+        Ok(Box::new(
+            extract_labeled_texts_with(bytes, DEFAULT_MAX_TEXT_LEN, endianness)
+                .0,
+        ))
+    }
+}
+
+/// Decoders by chunk tag, and by list type for LIST chunks, for
+/// `ChunkReader::decode_chunks` and `ChunkReader::describe_with`.  `new`
+/// registers the built-in decoders, `CueDecoder`, `FormatDecoder`, and
+/// `LabeledTextDecoder`; `empty` registers none.
+pub struct ChunkDecoders {
+    decoders: Vec<Box<dyn ChunkDecoder>>,
+}
+
+impl ChunkDecoders {
+    /// Registry of the built-in decoders
+    pub fn new() -> Self {
+        let mut decoders = Self::empty();
+        decoders.register(CueDecoder);
+        decoders.register(FormatDecoder);
+        decoders.register(LabeledTextDecoder);
+        decoders
+    }
+
+    /// Registry without any decoders
+    pub fn empty() -> Self {
+        Self { decoders: vec![] }
+    }
+
+    /// Register a decoder, replacing any registered for the same tag and
+    /// list type
+    pub fn register(&mut self, decoder: impl ChunkDecoder + 'static) {
+        let key = (decoder.tag(), decoder.list_type());
+        self.decoders.retain(|registered| {
+            (registered.tag(), registered.list_type()) != key
+        });
+        self.decoders.push(Box::new(decoder));
+    }
+
+    /// Decoder registered for the tag and list type, or for LIST chunks of a
+    /// list type without one, the decoder of every LIST, if registered
+    pub fn get(
+        &self,
+        tag: &[u8; 4],
+        list_type: Option<&[u8; 4]>,
+    ) -> Option<&dyn ChunkDecoder> {
+        let registered = |list_type: Option<&[u8; 4]>| {
+            self.decoders.iter().find(|decoder| {
+                decoder.tag() == *tag
+                    && decoder.list_type().as_ref() == list_type
+            })
+        };
+
+        registered(list_type)
+            .or_else(|| list_type.and_then(|_| registered(None)))
+            .map(|decoder| &**decoder)
+    }
+
+    // Decoder for a chunk with the head and body, by the list type in the
+    // body for LIST chunks
+    pub(crate) fn for_chunk(
+        &self,
+        head: &ChunkHead,
+        body: &[u8],
+    ) -> Option<&dyn ChunkDecoder> {
+        let list_type = match head.tag {
+            tag::LIST => body.first_chunk::<4>(),
+            _ => None,
+        };
+
+        self.get(&head.tag, list_type)
+    }
+
+    // Whether any decoder is registered for the tag
+    pub(crate) fn has_tag(&self, tag: &[u8; 4]) -> bool {
+        self.decoders.iter().any(|decoder| decoder.tag() == *tag)
+    }
+}
+
+impl Default for ChunkDecoders {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for ChunkDecoders {
+    fn fmt(
+        &self,
+        formatter: &mut std::fmt::Formatter<'_>,
+    ) -> Result<(), std::fmt::Error> {
+        let tags = self
+            .decoders
+            .iter()
+            .map(|decoder| {
+                let mut tag =
+                    String::from_utf8_lossy(&decoder.tag()).into_owned();
+
+                if let Some(list_type) = decoder.list_type() {
+                    tag.push('/');
+                    tag.push_str(&String::from_utf8_lossy(&list_type));
+                }
+
+                tag
+            })
+            .collect::<Vec<_>>();
+
+        formatter
+            .debug_struct("ChunkDecoders")
+            .field("tags", &tags)
+            .finish()
+    }
+}
+
+/// A chunk decoded by `ChunkReader::decode_chunks`, with the decoded value or
+/// the error decoding it
+#[derive(Debug)]
+pub struct DecodedChunk {
+    pub head: ChunkHead,
+    pub value: Result<Box<dyn Any>, Error>,
+}
+
+impl<Cursor: Read + Seek> ChunkReader<Cursor> {
+    /// Decode every chunk with a decoder registered in `decoders`, in chunk
+    /// order, skipping the rest.  LIST chunks are decoded by the decoder of
+    /// their list type; see `ChunkDecoders::get`.  Bodies are decoded with
+    /// `ChunkDecoder::decode_with` in the file's byte order.  Only chunks
+    /// with a decoder registered for their tag are read.  A chunk that fails
+    /// to decode is kept with its error, and does not end the walk.  The
+    /// reader is reset before and after the walk.
+    ///
+    /// Errors if a decoded chunk is larger than `max_chunk_size` or if
+    /// underlying cursor errors
+    pub fn decode_chunks(
+        &mut self,
+        decoders: &ChunkDecoders,
+    ) -> Result<Vec<DecodedChunk>, Error> {
+        self.reset()?;
+        let mut decoded = vec![];
+
+        while let Some(head) = self.seek_next_chunk(|_| true)? {
+            if !decoders.has_tag(&head.tag) {
+                self.skip_chunk_body(&head)?;
+                continue;
+            }
+
+            let body = self.read_chunk_body(&head)?;
+            let Some(decoder) = decoders.for_chunk(&head, &body) else {
+                continue;
+            };
+
+            decoded.push(DecodedChunk {
+                head,
+                value: decoder.decode_with(&body, self.endianness),
+            });
+        }
+
+        self.reset()?;
+        Ok(decoded)
+    }
+}
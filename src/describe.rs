@@ -0,0 +1,239 @@
+use crate::{
+    adtl_sub_chunks_with, chunk_name, tag, ChunkDecoder, ChunkDecoders,
+    ChunkHead, ChunkReader, CuePoint, Endianness, Error, FormatChunk,
+    LabeledText,
+};
+use std::any::Any;
+use std::fmt::Write as _;
+use std::io::{Read, Seek};
+
+// Quote and escape text as a JSON string
+fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if u32::from(c) < 0x20 => {
+                write!(quoted, "\\u{:04x}", u32::from(c)).unwrap();
+            }
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+fn json_tag(tag: &[u8; 4]) -> String {
+    json_string(&String::from_utf8_lossy(tag))
+}
+
+fn describe_format(format: &FormatChunk) -> String {
+    format!(
+        "\"fmt\": {{\"format_tag\": {}, \"channels\": {}, \
+         \"sample_rate\": {}, \"byte_rate\": {}, \"block_align\": {}, \
+         \"bits_per_sample\": {}}}",
+        format.format_tag,
+        format.channels,
+        format.sample_rate,
+        format.byte_rate,
+        format.block_align,
+        format.bits_per_sample
+    )
+}
+
+fn describe_cue_points(cues: &[CuePoint]) -> String {
+    let cues = cues
+        .iter()
+        .map(|cue| {
+            format!(
+                "{{\"id\": {}, \"position\": {}, \"data_tag\": {}, \
+                 \"sample_offset\": {}}}",
+                cue.id,
+                cue.position,
+                json_tag(&cue.data_tag),
+                cue.sample_offset
+            )
+        })
+        .collect::<Vec<_>>();
+
+    format!("\"cue_points\": [{}]", cues.join(", "))
+}
+
+fn describe_labels(body: &[u8], endianness: Endianness) -> String {
+    let labels = adtl_sub_chunks_with(body, endianness)
+        .map(|(head, sub_chunk)| describe_label(&head, sub_chunk, endianness))
+        .collect::<Vec<_>>();
+
+    format!("\"labels\": [{}]", labels.join(", "))
+}
+
+fn describe_label(
+    head: &ChunkHead,
+    sub_chunk: &[u8],
+    endianness: Endianness,
+) -> String {
+    let tag = json_tag(&head.tag);
+
+    match &head.tag {
+        &tag::LTXT => {
+            match LabeledText::try_from_bytes_with(sub_chunk, endianness) {
+                Ok(ltxt) => format!(
+                    "{{\"tag\": {}, \"cue_id\": {}, \"sample_length\": {}, \
+                     \"purpose_id\": {}, \"text\": {}}}",
+                    tag,
+                    ltxt.cue_id,
+                    ltxt.sample_length,
+                    json_tag(&ltxt.purpose_id),
+                    json_string(&ltxt.text)
+                ),
+                Err(err) => {
+                    format!("{{\"tag\": {}, {}}}", tag, describe_error(&err))
+                }
+            }
+        }
+        b"labl" | b"note" if sub_chunk.len() >= 4 => {
+            let (cue_id, text) = sub_chunk.split_at(4);
+            let text = text.split(|&b| b == 0).next().unwrap_or(&[]);
+
+            format!(
+                "{{\"tag\": {}, \"cue_id\": {}, \"text\": {}}}",
+                tag,
+                endianness.read_u32(*cue_id.first_chunk::<4>().unwrap()),
+                json_string(&String::from_utf8_lossy(text))
+            )
+        }
+        _ => format!("{{\"tag\": {}, \"unparsed\": {}}}", tag, sub_chunk.len()),
+    }
+}
+
+fn describe_error(err: &Error) -> String {
+    format!("\"error\": {}", json_string(&err.to_string()))
+}
+
+// Contents of a chunk as decoded by the decoder registered for it, preceded by
+// the list type of LISTs
+fn describe_contents(
+    decoders: &ChunkDecoders,
+    head: &ChunkHead,
+    body: &[u8],
+    endianness: Endianness,
+) -> String {
+    let contents = match decoders.for_chunk(head, body) {
+        Some(decoder) => match decoder.decode_with(body, endianness) {
+            Ok(value) => describe_value(decoder, &*value, body, endianness),
+            Err(err) => describe_error(&err),
+        },
+        None => format!("\"unparsed\": {}", body.len()),
+    };
+
+    match (head.tag, body.first_chunk::<4>()) {
+        (tag::LIST, Some(list_type)) => {
+            format!("\"list_type\": {}, {}", json_tag(list_type), contents)
+        }
+        _ => contents,
+    }
+}
+
+// Values of the crate's own types are described field by field, with every
+// sub-chunk of "adtl" LISTs rather than only the labeled-texts decoded
+fn describe_value(
+    decoder: &dyn ChunkDecoder,
+    value: &dyn Any,
+    body: &[u8],
+    endianness: Endianness,
+) -> String {
+    if let Some(format) = value.downcast_ref::<FormatChunk>() {
+        describe_format(format)
+    } else if let Some(cues) = value.downcast_ref::<Vec<CuePoint>>() {
+        describe_cue_points(cues)
+    } else if value.is::<Vec<LabeledText>>() && body.starts_with(&tag::ADTL) {
+        describe_labels(body, endianness)
+    } else {
+        format!(
+            "\"decoded\": {}",
+            decoder.describe(value).as_deref().unwrap_or("true")
+        )
+    }
+}
+
+impl<Cursor: Read + Seek> ChunkReader<Cursor> {
+    /// Describe the structure of the WAVE as indented JSON, for attaching to
+    /// bug reports without sharing the audio.  Every chunk is listed with its
+    /// tag, its name if it is a known chunk, the offset of its header from the
+    /// start of the RIFF header, and its declared size.  Contents are decoded
+    /// by the built-in decoders of `ChunkDecoders::new`, for "fmt " and
+    /// "cue " chunks and "adtl" LISTs; other chunks, and contents that fail
+    /// to decode, are reported by their size or error instead.  The reader is
+    /// reset before and after the walk.
+    ///
+    /// Errors if underlying cursor errors or a decoded chunk is too large
+    pub fn describe(&mut self) -> Result<String, Error> {
+        self.describe_with(&ChunkDecoders::new())
+    }
+
+    /// Describe the WAVE as `describe`, decoding contents with the decoders
+    /// registered in `decoders` in place of the built-in ones.  Values of
+    /// the built-in decoders' types, `FormatChunk`, `Vec<CuePoint>`, and for
+    /// "adtl" LISTs `Vec<LabeledText>`, are described field by field.  Other
+    /// values are reported with the decoder's `describe` as "decoded", `true`
+    /// if the decoder does not describe them, or the error decoding them.
+    ///
+    /// Errors if underlying cursor errors or a decoded chunk is too large
+    pub fn describe_with(
+        &mut self,
+        decoders: &ChunkDecoders,
+    ) -> Result<String, Error> {
+        self.reset()?;
+        let mut chunks = vec![];
+
+        while let Some(head) = self.seek_next_chunk(|_| true)? {
+            let offset = self.base_cursor.stream_position()?
+                - crate::CHUNK_HEAD_SZ as u64
+                - self.wave_start;
+
+            let contents = if decoders.has_tag(&head.tag) {
+                let body = self.read_chunk_body(&head)?;
+                describe_contents(decoders, &head, &body, self.endianness)
+            } else {
+                self.skip_chunk_body(&head)?;
+                format!("\"unparsed\": {}", head.size)
+            };
+
+            let name = chunk_name(&head.tag)
+                .map(|name| format!("\"name\": {}, ", json_string(name)))
+                .unwrap_or_default();
+
+            chunks.push(format!(
+                "    {{\"tag\": {}, {}\"offset\": {}, \"size\": {}, {}}}",
+                json_tag(&head.tag),
+                name,
+                offset,
+                head.size,
+                contents
+            ));
+        }
+
+        self.reset()?;
+
+        let chunks = if chunks.is_empty() {
+            String::from("[]")
+        } else {
+            format!("[\n{}\n  ]", chunks.join(",\n"))
+        };
+
+        Ok(format!(
+            "{{\n  \"form_id\": {},\n  \"riff_size\": {},\n  \
+             \"chunks\": {}\n}}\n",
+            json_tag(&self.form_id),
+            self.head.size,
+            chunks
+        ))
+    }
+}
@@ -0,0 +1,171 @@
+use crate::Marker;
+
+/// A marker present in both sets of markers being compared, with differing
+/// sample offset, label, or region length
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkerChange {
+    pub before: Marker,
+    pub after: Marker,
+}
+
+impl MarkerChange {
+    /// Shared cue ID of both markers
+    pub fn cue_id(&self) -> u32 {
+        self.before.cue.id
+    }
+
+    /// Samples the marker moved by, negative if it moved earlier
+    pub fn sample_delta(&self) -> i64 {
+        i64::from(self.after.cue.sample_offset)
+            - i64::from(self.before.cue.sample_offset)
+    }
+
+    pub fn label_changed(&self) -> bool {
+        self.before.label != self.after.label
+    }
+
+    pub fn region_length_changed(&self) -> bool {
+        self.before.region_length != self.after.region_length
+    }
+}
+
+/// Differences between two sets of markers, matched by cue ID; see
+/// `diff_markers`.  Displays as a changelog with one marker per line.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MarkerDiff {
+    /// Markers only in the second set
+    pub added: Vec<Marker>,
+
+    /// Markers only in the first set
+    pub removed: Vec<Marker>,
+
+    /// Markers in both sets that differ
+    pub changed: Vec<MarkerChange>,
+}
+
+impl MarkerDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+    }
+}
+
+fn describe_label(label: &Option<String>) -> String {
+    match label {
+        Some(label) => format!("\"{}\"", label),
+        None => String::from("(none)"),
+    }
+}
+
+fn describe_region_length(region_length: Option<u32>) -> String {
+    match region_length {
+        Some(length) => length.to_string(),
+        None => String::from("(none)"),
+    }
+}
+
+fn describe_marker(marker: &Marker) -> String {
+    let mut description =
+        format!("cue {} at {}", marker.cue.id, marker.cue.sample_offset);
+
+    if let Some(length) = marker.region_length {
+        description += &format!(" for {} samples", length);
+    }
+
+    if marker.label.is_some() {
+        description += &format!(" {}", describe_label(&marker.label));
+    }
+
+    description
+}
+
+impl std::fmt::Display for MarkerDiff {
+    fn fmt(
+        &self,
+        formatter: &mut std::fmt::Formatter<'_>,
+    ) -> Result<(), std::fmt::Error> {
+        for marker in &self.removed {
+            writeln!(formatter, "- {}", describe_marker(marker))?;
+        }
+
+        for marker in &self.added {
+            writeln!(formatter, "+ {}", describe_marker(marker))?;
+        }
+
+        for change in &self.changed {
+            write!(formatter, "~ cue {}", change.cue_id())?;
+
+            if change.sample_delta() != 0 {
+                write!(
+                    formatter,
+                    " moved {:+} samples to {}",
+                    change.sample_delta(),
+                    change.after.cue.sample_offset
+                )?;
+            }
+
+            if change.region_length_changed() {
+                write!(
+                    formatter,
+                    " region length {} -> {}",
+                    describe_region_length(change.before.region_length),
+                    describe_region_length(change.after.region_length)
+                )?;
+            }
+
+            if change.label_changed() {
+                write!(
+                    formatter,
+                    " label {} -> {}",
+                    describe_label(&change.before.label),
+                    describe_label(&change.after.label)
+                )?;
+            }
+
+            writeln!(formatter)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Compare markers `a` against markers `b`, matching markers by cue ID.  Only
+/// the sample offset, label, and region length are compared.  Added and
+/// removed markers keep the order of `b` and `a` respectively, and changed
+/// markers the order of `a`.  Markers are matched against the first marker
+/// with the same cue ID in the other set.
+pub fn diff_markers(a: &[Marker], b: &[Marker]) -> MarkerDiff {
+    let find = |markers: &[Marker], id| -> Option<Marker> {
+        markers.iter().find(|marker| marker.cue.id == id).cloned()
+    };
+
+    let mut diff = MarkerDiff::default();
+
+    for before in a {
+        match find(b, before.cue.id) {
+            None => diff.removed.push(before.clone()),
+            Some(after) => {
+                let change = MarkerChange {
+                    before: before.clone(),
+                    after,
+                };
+
+                if change.sample_delta() != 0
+                    || change.label_changed()
+                    || change.region_length_changed()
+                {
+                    diff.changed.push(change);
+                }
+            }
+        }
+    }
+
+    diff.added = b
+        .iter()
+        .filter(|after| find(a, after.cue.id).is_none())
+        .cloned()
+        .collect();
+
+    diff
+}
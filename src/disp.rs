@@ -0,0 +1,58 @@
+use crate::Error;
+
+/// Clipboard format of a DISP chunk holding plain text
+pub const CF_TEXT: u32 = 1;
+
+/// Contents of a "DISP" chunk, which stores an object to display for the file
+/// (typically its title) in the form of Windows clipboard data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DispChunk {
+    /// Clipboard format of the data, e.g. `CF_TEXT`
+    pub cf_type: u32,
+    pub data: Vec<u8>,
+}
+
+impl DispChunk {
+    /// Parse the body of a DISP chunk.
+    ///
+    /// Errors if bytes are too short to contain the clipboard format
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        let (cf_type, data) = bytes
+            .split_first_chunk::<4>()
+            .ok_or(Error::wave("DISP chunk too short"))?;
+
+        Ok(DispChunk {
+            cf_type: u32::from_le_bytes(*cf_type),
+            data: Vec::from(data),
+        })
+    }
+
+    /// Get the simplest DISP chunk displaying the given text
+    pub fn from_text(text: &str) -> Self {
+        let mut data = Vec::from(text.as_bytes());
+        data.push(0);
+
+        DispChunk {
+            cf_type: CF_TEXT,
+            data,
+        }
+    }
+
+    /// Get the text up to the first null byte if the data is `CF_TEXT`
+    pub fn text(&self) -> Option<String> {
+        if self.cf_type != CF_TEXT {
+            return None;
+        }
+
+        let text = self.data.split(|&b| b == 0).next().unwrap_or(&[]);
+        Some(String::from_utf8_lossy(text).to_string())
+    }
+
+    /// Get the bytes for the DISP chunk, without any header
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(4 + self.data.len());
+        bytes.extend_from_slice(&self.cf_type.to_le_bytes());
+        bytes.extend_from_slice(&self.data);
+        bytes
+    }
+}
@@ -0,0 +1,101 @@
+use crate::RF64_TAGS;
+
+/// RIFF tag of files storing sizes and fields big-endian
+pub const RIFX_TAG: [u8; 4] = *b"RIFX";
+
+/// Kind of RIFF file, by the tag of its RIFF header, from
+/// `ChunkReader::format_flavor`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WaveFlavor {
+    /// "RIFF", with little-endian 32-bit sizes
+    Riff,
+
+    /// "RIFX", with big-endian 32-bit sizes
+    Rifx,
+
+    /// "RF64", with 64-bit sizes in a "ds64" chunk
+    Rf64,
+
+    /// "BW64", the Broadcast Wave 64 variant of RF64
+    Bw64,
+}
+
+impl WaveFlavor {
+    /// Kind of file with the given RIFF tag, or `None` if the tag is not one
+    /// of a RIFF file
+    pub fn of_riff_tag(tag: &[u8; 4]) -> Option<Self> {
+        match tag {
+            b"RIFF" => Some(Self::Riff),
+            &RIFX_TAG => Some(Self::Rifx),
+            _ if *tag == RF64_TAGS[0] => Some(Self::Rf64),
+            _ if *tag == RF64_TAGS[1] => Some(Self::Bw64),
+            _ => None,
+        }
+    }
+
+    /// Tag of the RIFF header of files of the kind
+    pub fn riff_tag(self) -> [u8; 4] {
+        match self {
+            Self::Riff => *b"RIFF",
+            Self::Rifx => RIFX_TAG,
+            Self::Rf64 => RF64_TAGS[0],
+            Self::Bw64 => RF64_TAGS[1],
+        }
+    }
+
+    /// Byte order of files of the kind
+    pub fn endianness(self) -> Endianness {
+        Endianness::of_riff_tag(&self.riff_tag())
+    }
+}
+
+/// Byte order of the sizes and fields of a RIFF file: little-endian for
+/// "RIFF" and RF64 files, big-endian for "RIFX" files.  Chunk readers and
+/// writers follow the byte order of the file for chunk headers, and for the
+/// cue points and labels they read and append.  Free functions parsing chunk
+/// bodies, such as `parse_cue_points`, always parse little-endian.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Endianness {
+    #[default]
+    Little,
+    Big,
+}
+
+impl Endianness {
+    /// Byte order of files with the given RIFF tag
+    pub fn of_riff_tag(tag: &[u8; 4]) -> Self {
+        if *tag == RIFX_TAG {
+            Self::Big
+        } else {
+            Self::Little
+        }
+    }
+
+    pub(crate) fn u16_bytes(self, value: u16) -> [u8; 2] {
+        match self {
+            Self::Little => value.to_le_bytes(),
+            Self::Big => value.to_be_bytes(),
+        }
+    }
+
+    pub(crate) fn u32_bytes(self, value: u32) -> [u8; 4] {
+        match self {
+            Self::Little => value.to_le_bytes(),
+            Self::Big => value.to_be_bytes(),
+        }
+    }
+
+    pub(crate) fn read_u16(self, bytes: [u8; 2]) -> u16 {
+        match self {
+            Self::Little => u16::from_le_bytes(bytes),
+            Self::Big => u16::from_be_bytes(bytes),
+        }
+    }
+
+    pub(crate) fn read_u32(self, bytes: [u8; 4]) -> u32 {
+        match self {
+            Self::Little => u32::from_le_bytes(bytes),
+            Self::Big => u32::from_be_bytes(bytes),
+        }
+    }
+}
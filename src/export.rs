@@ -0,0 +1,76 @@
+use crate::{
+    tag, wrap_pcm, ChunkReader, ChunkWriter, Error, FormatChunk, InfoMetadata,
+    Marker,
+};
+use std::io::{self, Read, Seek};
+use std::path::Path;
+
+/// Write every region of a WAVE to its own WAVE file in `out_dir`, for
+/// slicing a file of samples into one file per sample.  Regions are the
+/// markers with a region length, as read by `ChunkReader::read_markers`, and
+/// `naming` gives the file name for each.  Each file holds a copy of the
+/// "fmt " chunk of the source, the region's samples as its "data" chunk,
+/// and, if the region has a label, an "INFO" LIST naming it; no markers are
+/// written.  Regions running past the end of the samples are cut short.  The
+/// reader is reset after reading.
+///
+/// Returns the number of files written.  Errors if the WAVE has no "fmt " or
+/// "data" chunk, if a region starts past the end of the samples, or if
+/// reading or writing errors.  Nothing is written unless every region starts
+/// within the samples.
+pub fn export_regions<Cursor: Read + Seek>(
+    reader: &mut ChunkReader<Cursor>,
+    out_dir: impl AsRef<Path>,
+    naming: impl Fn(&Marker) -> String,
+) -> Result<usize, Error> {
+    let regions = reader
+        .read_markers()?
+        .into_iter()
+        .filter(|marker| marker.region_length.is_some())
+        .collect::<Vec<_>>();
+
+    let (_, fmt_body) = reader
+        .read_next_chunk(Some(tag::FMT))?
+        .ok_or(Error::wave("No \"fmt \" chunk"))?;
+    let format = FormatChunk::parse(&fmt_body)?;
+    reader.reset()?;
+
+    let (_, samples) = reader
+        .read_next_chunk(Some(tag::DATA))?
+        .ok_or(Error::wave("No \"data\" chunk"))?;
+    reader.reset()?;
+
+    let frame_len = usize::from(format.block_align);
+    let slices = regions
+        .iter()
+        .map(|region| {
+            let start = region.cue.sample_offset as usize * frame_len;
+            let len = region.region_length.unwrap_or(0) as usize * frame_len;
+
+            if start > samples.len() {
+                return Err(Error::wave(format!(
+                    "Region of cue {} starts past the end of the samples",
+                    region.cue.id
+                )));
+            }
+
+            Ok(&samples[start..][..len.min(samples.len() - start)])
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for (region, slice) in regions.iter().zip(slices) {
+        let mut wave = vec![];
+        wrap_pcm(slice, &format, &mut wave)?;
+
+        let info = InfoMetadata {
+            title: region.label.clone(),
+            ..InfoMetadata::default()
+        };
+        ChunkWriter::new(io::Cursor::new(&mut wave))?
+            .append_info_chunk(&info)?;
+
+        std::fs::write(out_dir.as_ref().join(naming(region)), wave)?;
+    }
+
+    Ok(regions.len())
+}
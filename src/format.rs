@@ -0,0 +1,234 @@
+use crate::Error;
+
+pub(crate) const FORMAT_MIN_SZ: usize = 16;
+const EXTENSIBLE_SZ: usize = 40;
+
+/// Format tag of a WAVE_FORMAT_EXTENSIBLE "fmt " chunk
+pub const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// Speaker a channel is routed to, in the bit order of the extensible format's
+/// channel mask
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpeakerPosition {
+    FrontLeft,
+    FrontRight,
+    FrontCenter,
+
+    /// Low-frequency effects (LFE) channel
+    LowFrequency,
+    BackLeft,
+    BackRight,
+    FrontLeftOfCenter,
+    FrontRightOfCenter,
+    BackCenter,
+    SideLeft,
+    SideRight,
+    TopCenter,
+    TopFrontLeft,
+    TopFrontCenter,
+    TopFrontRight,
+    TopBackLeft,
+    TopBackCenter,
+    TopBackRight,
+}
+
+impl SpeakerPosition {
+    /// Every position, indexed by its bit in the channel mask
+    pub const ALL: [SpeakerPosition; 18] = [
+        Self::FrontLeft,
+        Self::FrontRight,
+        Self::FrontCenter,
+        Self::LowFrequency,
+        Self::BackLeft,
+        Self::BackRight,
+        Self::FrontLeftOfCenter,
+        Self::FrontRightOfCenter,
+        Self::BackCenter,
+        Self::SideLeft,
+        Self::SideRight,
+        Self::TopCenter,
+        Self::TopFrontLeft,
+        Self::TopFrontCenter,
+        Self::TopFrontRight,
+        Self::TopBackLeft,
+        Self::TopBackCenter,
+        Self::TopBackRight,
+    ];
+
+    /// Bit representing the position in the channel mask
+    pub fn mask_bit(&self) -> u32 {
+        1 << *self as u32
+    }
+}
+
+/// Fields of a "fmt " chunk only present in the WAVE_FORMAT_EXTENSIBLE format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatExtensible {
+    /// Bits of precision in each sample, at most `bits_per_sample`
+    pub valid_bits_per_sample: u16,
+
+    /// Bit field of speaker positions; see `SpeakerPosition`
+    pub channel_mask: u32,
+
+    /// GUID of the actual format, whose first two bytes are the format tag
+    pub sub_format: [u8; 16],
+}
+
+/// Contents of a "fmt " chunk, describing how the samples in the "data" chunk
+/// are encoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatChunk {
+    /// e.g. 1 for PCM, or `FORMAT_EXTENSIBLE`
+    pub format_tag: u16,
+    pub channels: u16,
+    pub sample_rate: u32,
+
+    /// Average bytes per second
+    pub byte_rate: u32,
+
+    /// Bytes per sample frame, across all channels
+    pub block_align: u16,
+    pub bits_per_sample: u16,
+
+    /// Present when the format tag is `FORMAT_EXTENSIBLE`
+    pub extensible: Option<FormatExtensible>,
+
+    /// Size of the extension following the common fields, as recorded in the
+    /// chunk, or `None` for a 16-byte chunk without one.  Computed from the
+    /// extension when writing if `None`.
+    pub cb_size: Option<u16>,
+
+    /// Extension bytes following the size field, after the extensible fields
+    /// for extensible formats, kept raw for formats such as A-law and
+    /// mu-law.  Every byte left in the chunk is kept, even past `cb_size`, so
+    /// the chunk is written back exactly.  A 17-byte chunk, without room for
+    /// the extension size, keeps its last byte here with `cb_size` `None`,
+    /// and a lone extra byte is written back without an extension size.
+    pub extra: Vec<u8>,
+}
+
+impl FormatChunk {
+    /// Parse the body of a "fmt " chunk.  Extensible fields are only parsed
+    /// when the format tag is `FORMAT_EXTENSIBLE`.  Chunks with room for an
+    /// extension size after the 16 bytes of common fields have it read into
+    /// `cb_size`, and the remaining bytes are kept in `extra`.
+    ///
+    /// Errors if bytes are too short for the common fields, or for the
+    /// extensible fields of an extensible format
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < FORMAT_MIN_SZ {
+            return Err(Error::wave("fmt chunk too short"));
+        }
+
+        let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+        let u32_at = |i: usize| {
+            u32::from_le_bytes(*bytes[i..].first_chunk::<4>().unwrap())
+        };
+
+        let format_tag = u16_at(0);
+
+        let extensible = if format_tag == FORMAT_EXTENSIBLE {
+            if bytes.len() < EXTENSIBLE_SZ {
+                return Err(Error::wave("Extensible fmt chunk too short"));
+            }
+
+            Some(FormatExtensible {
+                valid_bits_per_sample: u16_at(18),
+                channel_mask: u32_at(20),
+                sub_format: *bytes[24..].first_chunk::<16>().unwrap(),
+            })
+        } else {
+            None
+        };
+
+        let cb_size =
+            (bytes.len() >= FORMAT_MIN_SZ + 2).then(|| u16_at(FORMAT_MIN_SZ));
+        let extra_start = if extensible.is_some() {
+            EXTENSIBLE_SZ
+        } else if cb_size.is_some() {
+            FORMAT_MIN_SZ + 2
+        } else {
+            FORMAT_MIN_SZ
+        };
+
+        Ok(FormatChunk {
+            format_tag,
+            channels: u16_at(2),
+            sample_rate: u32_at(4),
+            byte_rate: u32_at(8),
+            block_align: u16_at(12),
+            bits_per_sample: u16_at(14),
+            extensible,
+            cb_size,
+            extra: bytes.get(extra_start..).unwrap_or(&[]).to_vec(),
+        })
+    }
+
+    /// Get the bytes for the fmt chunk, without any header.  Extensible
+    /// formats include the extension size and extensible fields, and any
+    /// format with `cb_size` or `extra` the extension size and extra bytes.
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(EXTENSIBLE_SZ + self.extra.len());
+        bytes.extend_from_slice(&self.format_tag.to_le_bytes());
+        bytes.extend_from_slice(&self.channels.to_le_bytes());
+        bytes.extend_from_slice(&self.sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&self.byte_rate.to_le_bytes());
+        bytes.extend_from_slice(&self.block_align.to_le_bytes());
+        bytes.extend_from_slice(&self.bits_per_sample.to_le_bytes());
+
+        let extensible_sz = match self.extensible {
+            Some(_) => EXTENSIBLE_SZ - FORMAT_MIN_SZ - 2,
+            None => 0,
+        };
+
+        let lone_byte = self.extensible.is_none()
+            && self.cb_size.is_none()
+            && self.extra.len() == 1;
+
+        if self.extensible.is_some()
+            || self.cb_size.is_some()
+            || (!self.extra.is_empty() && !lone_byte)
+        {
+            let cb_size = self.cb_size.unwrap_or(
+                (extensible_sz + self.extra.len())
+                    .try_into()
+                    .unwrap_or(u16::MAX),
+            );
+            bytes.extend_from_slice(&cb_size.to_le_bytes());
+        }
+
+        if let Some(extensible) = &self.extensible {
+            bytes.extend_from_slice(
+                &extensible.valid_bits_per_sample.to_le_bytes(),
+            );
+            bytes.extend_from_slice(&extensible.channel_mask.to_le_bytes());
+            bytes.extend_from_slice(&extensible.sub_format);
+        }
+
+        bytes.extend_from_slice(&self.extra);
+        bytes
+    }
+
+    /// Number of sample frames in a "data" chunk of `data_bytes` bytes.  A
+    /// frame holds one sample for every channel, so a stereo frame is two
+    /// samples; multiply by `channels` for the count of individual samples.
+    /// Cue point sample offsets count frames.  Trailing bytes short of a
+    /// whole frame are not counted, and 0 is returned if `block_align` is 0.
+    pub fn sample_frames(&self, data_bytes: u32) -> u32 {
+        data_bytes.checked_div(self.block_align.into()).unwrap_or(0)
+    }
+
+    /// Get the speaker position of each channel from the channel mask, in
+    /// channel order.  Channels beyond the positions set in the mask have no
+    /// position, so fewer positions than channels may be returned.  Empty if
+    /// the format is not extensible.
+    pub fn channel_positions(&self) -> Vec<SpeakerPosition> {
+        let mask = self.extensible.map_or(0, |ext| ext.channel_mask);
+
+        SpeakerPosition::ALL
+            .into_iter()
+            .filter(|position| mask & position.mask_bit() != 0)
+            .take(self.channels.into())
+            .collect()
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{ChunkHead, ChunkWriter, Ds64Chunk, Error, CHUNK_HEAD_SZ};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+// Existing bytes of the stream about to be overwritten, to be written back if
+// whatever overwrites them fails partway
+#[derive(Debug, Default)]
+pub(crate) struct Snapshot {
+    ranges: Vec<(u64, Vec<u8>)>,
+}
+
+impl Snapshot {
+    // Save up to `len` bytes from `pos`, stopping at the end of the stream
+    pub(crate) fn save<Cursor: Read + Seek>(
+        &mut self,
+        cursor: &mut Cursor,
+        pos: u64,
+        len: u64,
+    ) -> io::Result<()> {
+        let mut bytes = vec![];
+        cursor.seek(SeekFrom::Start(pos))?;
+        cursor.take(len).read_to_end(&mut bytes)?;
+        self.ranges.push((pos, bytes));
+        Ok(())
+    }
+
+    // Write every saved range back, the last saved first
+    fn restore<Cursor: Write + Seek>(
+        &self,
+        cursor: &mut Cursor,
+    ) -> io::Result<()> {
+        for (pos, bytes) in self.ranges.iter().rev() {
+            cursor.seek(SeekFrom::Start(*pos))?;
+            cursor.write_all(bytes)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<Cursor: Read + Write + Seek> ChunkWriter<Cursor> {
+    // Save the header bytes `record_sizes` overwrites for the size plan: the
+    // RIFF size, or the RIFF header and "ds64" chunk (or the "JUNK" chunk it
+    // is to replace) for RF64
+    pub(crate) fn save_size_fields(
+        &mut self,
+        snapshot: &mut Snapshot,
+        plan: &Option<Ds64Chunk>,
+    ) -> Result<(), Error> {
+        let cursor = &mut self.base_cursor;
+
+        if plan.is_none() {
+            return Ok(snapshot.save(cursor, self.wave_start + 4, 4)?);
+        }
+
+        let slot_pos = self.wave_start + CHUNK_HEAD_SZ as u64 + 4;
+        cursor.seek(SeekFrom::Start(slot_pos))?;
+        let slot = ChunkHead::parse(cursor)?;
+        let len = slot_pos - self.wave_start
+            + CHUNK_HEAD_SZ as u64
+            + u64::from(slot.size);
+
+        Ok(snapshot.save(cursor, self.wave_start, len)?)
+    }
+
+    // Run `write`, and if it fails, write back the bytes saved in the
+    // snapshot so the stream is as it was up to its old length.  The error
+    // of `write` is returned even if writing back fails too.
+    pub(crate) fn guarded<T>(
+        &mut self,
+        snapshot: Snapshot,
+        write: impl FnOnce(&mut Self) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        write(self).inspect_err(|_| {
+            let _ = snapshot.restore(&mut self.base_cursor);
+        })
+    }
+}
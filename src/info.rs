@@ -0,0 +1,120 @@
+use crate::{needs_pad, pad_len, tag, ChunkWriter, Error};
+use std::io::{Read, Seek, Write};
+
+/// Common descriptive tags of an "INFO" LIST, each written only if set
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct InfoMetadata {
+    /// "INAM", the title of the work
+    pub title: Option<String>,
+
+    /// "IART", the artist of the original work
+    pub artist: Option<String>,
+
+    /// "ICMT", comments about the file or its subject
+    pub comment: Option<String>,
+
+    /// "ICRD", the date the work was created, e.g. "2024-05-01"
+    pub date: Option<String>,
+
+    /// "ISFT", the software package used to create the file
+    pub software: Option<String>,
+
+    /// "IGNR", the genre of the work
+    pub genre: Option<String>,
+}
+
+impl InfoMetadata {
+    // Each tag and the field holding its text
+    fn fields(&self) -> [([u8; 4], &Option<String>); 6] {
+        [
+            (*b"INAM", &self.title),
+            (*b"IART", &self.artist),
+            (*b"ICMT", &self.comment),
+            (*b"ICRD", &self.date),
+            (*b"ISFT", &self.software),
+            (*b"IGNR", &self.genre),
+        ]
+    }
+
+    /// Parse the body of an "INFO" LIST, keeping the text of the known tags up
+    /// to its first null byte.  Other tags, and sub-chunks running past the
+    /// end of the list, are ignored.
+    ///
+    /// Errors if bytes are not the body of an "INFO" LIST
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        let Some(mut rest) = bytes.strip_prefix(&tag::INFO[..]) else {
+            return Err(Error::wave("Not an \"INFO\" LIST"));
+        };
+        let mut info = Self::default();
+
+        while let Some((head, body)) = rest.split_first_chunk::<8>() {
+            let (tag, size) = head.split_at(4);
+            let size = u32::from_le_bytes(size.try_into().unwrap()) as usize;
+            let Some(text) = body.get(..size) else {
+                break;
+            };
+            let text = text.split(|&b| b == 0).next().unwrap_or(&[]);
+            let text = Some(String::from_utf8_lossy(text).into_owned());
+
+            match tag {
+                b"INAM" => info.title = text,
+                b"IART" => info.artist = text,
+                b"ICMT" => info.comment = text,
+                b"ICRD" => info.date = text,
+                b"ISFT" => info.software = text,
+                b"IGNR" => info.genre = text,
+                _ => {}
+            }
+
+            rest = body
+                .get(size + pad_len(size as u64) as usize..)
+                .unwrap_or(&[]);
+        }
+
+        Ok(info)
+    }
+
+    /// Get the body of an "INFO" LIST holding a sub-chunk for each field set,
+    /// its text followed by a null byte, and a pad byte if that is odd-sized
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = tag::INFO.to_vec();
+
+        for (tag, text) in self.fields() {
+            let Some(text) = text else {
+                continue;
+            };
+            let size = text.len() + 1;
+
+            bytes.extend_from_slice(&tag);
+            bytes.extend_from_slice(&(size as u32).to_le_bytes());
+            bytes.extend_from_slice(text.as_bytes());
+            bytes.push(0);
+
+            if needs_pad(size as u64) {
+                bytes.push(0);
+            }
+        }
+
+        bytes
+    }
+}
+
+impl<Cursor: Read + Write + Seek> ChunkWriter<Cursor> {
+    /// Append an "INFO" LIST chunk holding the fields of `info` that are set,
+    /// while updating WAV size in header.  Nothing is written when no field
+    /// is set, unless empty chunks are allowed with `set_allow_empty`.
+    ///
+    /// Errors if underlying cursor errors or resulting chunk is too large
+    pub fn append_info_chunk(
+        &mut self,
+        info: &InfoMetadata,
+    ) -> Result<(), Error> {
+        let bytes = info.as_bytes();
+
+        if bytes.len() == tag::INFO.len() && !self.allow_empty {
+            return Ok(());
+        }
+
+        self.append_chunk(tag::LIST, &bytes)
+    }
+}
@@ -0,0 +1,133 @@
+use crate::{Error, CHUNK_HEAD_SZ};
+
+pub(crate) const PEAK_ENVELOPE_SZ: usize = 120;
+
+/// Header of a "levl" chunk (EBU Tech 3285 Supplement 3), which stores a
+/// precomputed peak envelope for drawing waveforms.  The peak frames follow
+/// the header within the same chunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeakEnvelopeChunk {
+    pub version: u32,
+
+    /// 1 for 8-bit peak values, 2 for 16-bit
+    pub format: u32,
+
+    /// 1 for positive peaks only, 2 for positive and negative peak pairs
+    pub points_per_value: u32,
+
+    /// Number of audio frames represented by each peak frame
+    pub block_size: u32,
+
+    pub peak_channels: u32,
+    pub num_peak_frames: u32,
+
+    /// Offset of the peak-of-peaks, in peak values
+    pub pos_peak_of_peaks: u32,
+
+    /// Offset of the peak frames from the start of the chunk *header*,
+    /// typically 128
+    pub offset_to_peaks: u32,
+
+    /// e.g. "2024:01:01:12:00:00:00"
+    pub timestamp: [u8; 28],
+
+    pub reserved: [u8; 60],
+}
+
+impl PeakEnvelopeChunk {
+    pub const FORMAT_8_BIT: u32 = 1;
+    pub const FORMAT_16_BIT: u32 = 2;
+
+    /// Parse the header from the body of a levl chunk.
+    ///
+    /// Errors if bytes are too short to contain the header
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        let bytes = bytes
+            .first_chunk::<PEAK_ENVELOPE_SZ>()
+            .ok_or(Error::wave("levl chunk too short"))?;
+
+        let u32_at = |i: usize| {
+            u32::from_le_bytes(*bytes[i * 4..].first_chunk::<4>().unwrap())
+        };
+
+        Ok(PeakEnvelopeChunk {
+            version: u32_at(0),
+            format: u32_at(1),
+            points_per_value: u32_at(2),
+            block_size: u32_at(3),
+            peak_channels: u32_at(4),
+            num_peak_frames: u32_at(5),
+            pos_peak_of_peaks: u32_at(6),
+            offset_to_peaks: u32_at(7),
+            timestamp: *bytes[32..].first_chunk::<28>().unwrap(),
+            reserved: *bytes[60..].first_chunk::<60>().unwrap(),
+        })
+    }
+
+    /// Get the bytes for the header, without any chunk header
+    pub fn as_bytes(&self) -> [u8; PEAK_ENVELOPE_SZ] {
+        let fields = [
+            self.version,
+            self.format,
+            self.points_per_value,
+            self.block_size,
+            self.peak_channels,
+            self.num_peak_frames,
+            self.pos_peak_of_peaks,
+            self.offset_to_peaks,
+        ];
+
+        let mut bytes = [0u8; PEAK_ENVELOPE_SZ];
+
+        for (i, field) in fields.iter().enumerate() {
+            bytes[i * 4..i * 4 + 4].copy_from_slice(&field.to_le_bytes());
+        }
+
+        bytes[32..60].copy_from_slice(&self.timestamp);
+        bytes[60..].copy_from_slice(&self.reserved);
+        bytes
+    }
+
+    /// Size in bytes of a single peak value
+    pub fn value_size(&self) -> Option<usize> {
+        match self.format {
+            Self::FORMAT_8_BIT => Some(1),
+            Self::FORMAT_16_BIT => Some(2),
+            _ => None,
+        }
+    }
+
+    /// Get the peak frames from the body of the levl chunk this header was
+    /// parsed from.
+    ///
+    /// Errors if the format is unknown or the peak frames described by the
+    /// header run past the end of the body
+    pub fn peak_frames<'a>(&self, bytes: &'a [u8]) -> Result<&'a [u8], Error> {
+        let value_size = self.value_size().ok_or(Error::wave(format!(
+            "Unknown levl format {}",
+            self.format
+        )))?;
+
+        let start = usize::try_from(self.offset_to_peaks)
+            .ok()
+            .and_then(|offset| offset.checked_sub(CHUNK_HEAD_SZ))
+            .filter(|&start| start >= PEAK_ENVELOPE_SZ);
+
+        let len = [
+            self.points_per_value,
+            self.peak_channels,
+            self.num_peak_frames,
+        ]
+        .into_iter()
+        .try_fold(value_size, |len, factor| {
+            usize::try_from(factor)
+                .ok()
+                .and_then(|factor| len.checked_mul(factor))
+        });
+
+        start
+            .zip(len)
+            .and_then(|(start, len)| bytes.get(start..start.checked_add(len)?))
+            .ok_or(Error::wave("levl peak frames out of bounds"))
+    }
+}
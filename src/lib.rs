@@ -31,26 +31,140 @@
 use io::{Read, Seek, SeekFrom, Write};
 use std::io;
 
+mod acid;
+mod batch;
+mod cart;
+mod decoder;
+mod describe;
+mod diff;
+mod disp;
+mod endian;
+mod export;
+mod format;
+mod guard;
+mod info;
+mod levl;
+mod markers;
+mod merge;
+mod nested;
+mod regions;
+mod registry;
+mod rf64;
+mod smpl;
+pub mod tag;
+mod wavl;
+
+#[cfg(any(test, feature = "test-utils"))]
+pub mod testing;
+
+pub use acid::AcidChunk;
+pub use batch::AppendBuffer;
+pub use cart::{CartChunk, CartTimer};
+pub use decoder::{
+    ChunkDecoder, ChunkDecoders, CueDecoder, DecodedChunk, FormatDecoder,
+    LabeledTextDecoder,
+};
+pub use diff::{diff_markers, MarkerChange, MarkerDiff};
+pub use disp::{DispChunk, CF_TEXT};
+pub use endian::{Endianness, WaveFlavor, RIFX_TAG};
+pub use export::export_regions;
+pub use format::{
+    FormatChunk, FormatExtensible, SpeakerPosition, FORMAT_EXTENSIBLE,
+};
+pub use info::InfoMetadata;
+pub use levl::PeakEnvelopeChunk;
+pub use markers::MarkerIter;
+pub use merge::merge_markers_into;
+pub use regions::{
+    regions_from_cue_pairs, RegionStrategy, REGION_END_SUFFIXES,
+};
+pub use registry::{chunk_name, PRO_TOOLS_CHUNKS};
+pub use rf64::{Ds64Chunk, DS64_MIN_SZ, RF64_TAGS};
+pub use smpl::{Manufacturer, SampleLoop, SamplerChunk};
+pub use wavl::{resolve_cue_byte_offset, WaveList, WaveSegment};
+
 pub(crate) const CHUNK_HEAD_SZ: usize = 8;
 pub(crate) const CUE_SZ: usize = 24;
 pub(crate) const LABELED_TEXT_MIN_SZ: usize = 20;
-pub(crate) const CHUNK_TOO_BIG: &str =
-    "Chunk size exceeds bounds of 32-bit integer";
+
+// Offset of the time reference, low 32 bits first, in a "bext" chunk, after
+// the description, originator, originator reference, date, and time
+const BEXT_TIME_REFERENCE_POS: u32 = 256 + 32 + 32 + 10 + 8;
 
 pub type ChunkDefinition = ([u8; 4], Vec<u8>);
 
+// Cue points of the first "cue " chunk with the labels and labeled-texts of
+// every "adtl" LIST
+type CuesAndLabels = (Vec<CuePoint>, Vec<Label>, Vec<LabeledText>);
+
+/// Largest chunk body a `ChunkReader` reads into memory by default, 256 MiB;
+/// see `ChunkReader::set_max_chunk_size`
+pub const DEFAULT_MAX_CHUNK_SIZE: u32 = 256 * 1024 * 1024;
+
+/// Chunks describing the audio itself, kept by `ChunkWriter::strip_metadata`
+/// by default
+pub const ESSENTIAL_CHUNKS: [[u8; 4]; 3] = [tag::FMT, *b"fact", tag::DATA];
+
+/// Longest text, in bytes, kept from each labeled-text by
+/// `extract_labeled_text_from_list` and the reader's marker and labeled-text
+/// methods, 64 KiB; see `extract_labeled_text_from_list_with_max`
+pub const DEFAULT_MAX_TEXT_LEN: usize = 64 * 1024;
+
+/// Both common spellings of the tag for a chunk holding an ID3v2 tag
+pub const ID3_TAGS: [[u8; 4]; 2] = [*b"id3 ", *b"ID3 "];
+
 /// Wave-variant errors are caused by reading invalid WAV files or chunks.
 /// Io-variant errors are caused by underlying IO operations.
+/// ChunkTooBig-variant errors are caused by chunks or WAVs that would be
+/// larger than their sizes can record, with the size that would be needed
+/// and the largest that can be recorded, e.g. to learn how much to split off
+/// a set of labels.  A WAV that outgrows 32-bit sizes without a reserved
+/// "JUNK" chunk to upgrade to RF64 with is reported as a Wave error instead,
+/// saying how to reserve one.
 #[derive(Debug)]
 pub enum Error {
     Wave(String),
     Io(io::Error),
+    ChunkTooBig { needed: u64, limit: u64 },
+}
+
+/// Cloneable, comparable counterpart to `Error`, for storing and comparing
+/// errors.  Io-variant errors keep the IO error's kind and message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    Wave(String),
+    Io(io::ErrorKind, String),
+    ChunkTooBig { needed: u64, limit: u64 },
 }
 
 impl Error {
     fn wave<S: ToString>(s: S) -> Self {
         Self::Wave(s.to_string())
     }
+
+    // Size of `needed` bytes past the limit of a 32-bit size
+    fn chunk_too_big(needed: u64) -> Self {
+        Self::ChunkTooBig {
+            needed,
+            limit: u32::MAX.into(),
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Wave(s) => ErrorKind::Wave(s.clone()),
+            Self::Io(e) => ErrorKind::Io(e.kind(), e.to_string()),
+            Self::ChunkTooBig { needed, limit } => ErrorKind::ChunkTooBig {
+                needed: *needed,
+                limit: *limit,
+            },
+        }
+    }
+}
+
+// A size as recorded in a chunk head or RIFF header
+fn size_u32(size: u64) -> Result<u32, Error> {
+    u32::try_from(size).map_err(|_| Error::chunk_too_big(size))
 }
 
 impl std::fmt::Display for Error {
@@ -61,6 +175,11 @@ impl std::fmt::Display for Error {
         match self {
             Self::Wave(s) => writeln!(formatter, "Wave: {}", s)?,
             Self::Io(e) => writeln!(formatter, "IO: {}", e)?,
+            Self::ChunkTooBig { needed, limit } => writeln!(
+                formatter,
+                "Wave: Size of {} bytes exceeds maximum of {}",
+                needed, limit
+            )?,
         }
 
         Ok(())
@@ -73,7 +192,80 @@ impl From<io::Error> for Error {
     }
 }
 
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Wave(_) | Self::ChunkTooBig { .. } => None,
+            Self::Io(e) => Some(e),
+        }
+    }
+}
+
+/// Inventory of the chunks in a WAVE, from `ChunkReader::summary`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ChunkSummary {
+    /// Each tag found mapped to its number of occurrences and the total size
+    /// of their bodies in bytes
+    pub tags: std::collections::BTreeMap<[u8; 4], (usize, u64)>,
+
+    /// Every chunk's tag, in file order
+    pub order: Vec<[u8; 4]>,
+}
+
+impl ChunkSummary {
+    /// Every chunk's tag in file order, paired with its name from
+    /// `chunk_name` if known
+    pub fn named_order(&self) -> Vec<([u8; 4], Option<&'static str>)> {
+        self.order
+            .iter()
+            .map(|tag| (*tag, chunk_name(tag)))
+            .collect()
+    }
+
+    pub fn has_fmt(&self) -> bool {
+        self.tags.contains_key(&tag::FMT)
+    }
+
+    pub fn has_data(&self) -> bool {
+        self.tags.contains_key(&tag::DATA)
+    }
+
+    /// Whether the first "fmt " chunk precedes the first "data" chunk, as
+    /// required for a valid WAV
+    pub fn fmt_before_data(&self) -> bool {
+        let position = |tag: &[u8; 4]| self.order.iter().position(|t| t == tag);
+
+        matches!(
+            (position(&tag::FMT), position(&tag::DATA)),
+            (Some(fmt), Some(data)) if fmt < data
+        )
+    }
+}
+
+/// Structural problem found by `ChunkReader::verify`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Warning {
+    /// Cue point placed past the last sample of the "data" chunk
+    CueBeyondData {
+        cue_id: u32,
+        sample_offset: u32,
+        sample_count: u32,
+    },
+
+    /// "ltxt" or "labl" sub-chunk referencing a cue point that doesn't exist
+    OrphanLabel { tag: [u8; 4], cue_id: u32 },
+
+    /// Cue point ID shared by more than one cue point
+    DuplicateCueId { cue_id: u32 },
+
+    /// Cue count at the start of the "cue " chunk disagreeing with the number
+    /// of cue points present
+    CueCountMismatch { declared: u32, present: usize },
+
+    /// Labeled-text whose text was longer than the allowed maximum, and was
+    /// cut to that many bytes
+    TextTruncated { cue_id: u32, len: usize },
+}
 
 /// Chunk header that immediately precedes the body of a chunk.  Composed of a
 /// 4-byte ASCII tag and the size of the body in bytes.
@@ -88,28 +280,123 @@ impl ChunkHead {
     ///
     /// Returns an error when there is an IO error
     pub fn parse(cursor: &mut impl Read) -> Result<Self, Error> {
+        Self::parse_with(cursor, Endianness::Little)
+    }
+
+    /// Read out an 8-byte chunk header from cursor, with the size in the given
+    /// byte order.
+    ///
+    /// Returns an error when there is an IO error
+    pub fn parse_with(
+        cursor: &mut impl Read,
+        endianness: Endianness,
+    ) -> Result<Self, Error> {
         let mut tag = [0u8; 4];
         let mut size_bytes = [0u8; 4];
         cursor.read_exact(&mut tag)?;
         cursor.read_exact(&mut size_bytes)?;
-        let size = u32::from_le_bytes(size_bytes);
+        let size = endianness.read_u32(size_bytes);
 
         Ok(ChunkHead { tag, size })
     }
 
+    /// Create a chunk header, checking that the tag is printable ASCII
+    /// (0x20 through 0x7E).
+    ///
+    /// Errors if the tag contains any non-printable byte
+    pub fn try_new(tag: [u8; 4], size: u32) -> Result<Self, Error> {
+        if is_printable_tag(&tag) {
+            Ok(ChunkHead { tag, size })
+        } else {
+            Err(Error::wave(format!("Invalid chunk tag {:?}", tag)))
+        }
+    }
+
     pub fn tag(&self) -> [u8; 4] {
         self.tag
     }
 
+    /// Get the tag as a string slice, or `None` if the tag is not printable
+    /// ASCII
+    pub fn tag_str(&self) -> Option<&str> {
+        if is_printable_tag(&self.tag) {
+            std::str::from_utf8(&self.tag[..]).ok()
+        } else {
+            None
+        }
+    }
+
     pub fn size(&self) -> u32 {
         self.size
     }
 
+    /// Size of the body including its pad byte, as it is laid out on disk
+    pub fn padded_size(&self) -> u64 {
+        u64::from(self.size) + pad_len(self.size.into())
+    }
+
     /// Obtain the bytes of the header as they would appear in a file
     pub fn as_bytes(&self) -> [u8; CHUNK_HEAD_SZ] {
+        self.as_bytes_with(Endianness::Little)
+    }
+
+    /// Obtain the bytes of the header as they would appear in a file of the
+    /// given byte order
+    pub fn as_bytes_with(&self, endianness: Endianness) -> [u8; CHUNK_HEAD_SZ] {
         let mut bytes = [0u8; CHUNK_HEAD_SZ];
         bytes[..4].copy_from_slice(&self.tag[..]);
-        bytes[4..].copy_from_slice(&self.size.to_le_bytes()[..]);
+        bytes[4..].copy_from_slice(&endianness.u32_bytes(self.size)[..]);
+        bytes
+    }
+}
+
+/// Name of a cue point, as stored in a "labl" sub-chunk.  Most applications
+/// show the text of "labl" sub-chunks as marker names, and use labeled-texts
+/// only for region lengths.  "note" sub-chunks, holding comments, share the
+/// same layout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    /// Associated cue point ID
+    pub cue_id: u32,
+    pub text: String,
+}
+
+impl Label {
+    /// Parse the body of a single "labl" sub-chunk, without any header.  Text
+    /// ends at the first null byte.
+    ///
+    /// Errors if there are fewer than the 4 bytes of the cue ID
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Self::try_from_bytes_with(bytes, Endianness::Little)
+    }
+
+    // Parse a label with the cue ID in the given byte order
+    pub(crate) fn try_from_bytes_with(
+        bytes: &[u8],
+        endianness: Endianness,
+    ) -> Result<Self, Error> {
+        let (cue_id, text) = bytes
+            .split_first_chunk::<4>()
+            .ok_or(Error::wave("Label too short for cue ID"))?;
+        let text = text.split(|&b| b == 0).next().unwrap_or(&[]);
+
+        Ok(Label {
+            cue_id: endianness.read_u32(*cue_id),
+            text: String::from_utf8_lossy(text).to_string(),
+        })
+    }
+
+    /// Get the bytes for the "labl" sub-chunk, without any header, including
+    /// the null terminator
+    pub fn as_bytes(&self) -> Vec<u8> {
+        self.as_bytes_with(Endianness::Little)
+    }
+
+    /// Get the bytes for the "labl" sub-chunk in the given byte order
+    pub fn as_bytes_with(&self, endianness: Endianness) -> Vec<u8> {
+        let mut bytes = Vec::from(endianness.u32_bytes(self.cue_id));
+        bytes.extend_from_slice(self.text.as_bytes());
+        bytes.push(0);
         bytes
     }
 }
@@ -136,8 +423,51 @@ pub struct LabeledText {
 }
 
 impl LabeledText {
-    // bytes length must be >= LABELED_TEXT_MIN_SZ
-    fn parse(bytes: &[u8]) -> Self {
+    /// Get the region this labeled-text spans from `start_sample`, the sample
+    /// offset of its cue point, or `None` if it marks a point.  Labeled-texts
+    /// with a non-zero sample length are regions whatever their purpose:
+    /// "rgn " is the purpose for regions, but "mark" with a length is a loop
+    /// for Quake, and other applications write either.  Without a length,
+    /// even "rgn " labeled-texts mark a point.
+    pub fn as_region(&self, start_sample: u32) -> Option<Region> {
+        (self.sample_length > 0).then(|| Region {
+            start: start_sample,
+            length: self.sample_length,
+            name: self.text.clone(),
+        })
+    }
+
+    /// Parse the body of a single "ltxt" sub-chunk, without any header.
+    ///
+    /// Errors if there are fewer bytes than the fixed-size fields require
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Self::try_from_bytes_with(bytes, Endianness::Little)
+    }
+
+    // Parse a labeled-text with integer fields in the given byte order
+    pub(crate) fn try_from_bytes_with(
+        bytes: &[u8],
+        endianness: Endianness,
+    ) -> Result<Self, Error> {
+        if bytes.len() < LABELED_TEXT_MIN_SZ {
+            return Err(Error::wave(format!(
+                "Labeled text is {} bytes, expected at least {}",
+                bytes.len(),
+                LABELED_TEXT_MIN_SZ
+            )));
+        }
+
+        Ok(Self::parse(bytes, usize::MAX, endianness))
+    }
+
+    // bytes length must be >= LABELED_TEXT_MIN_SZ.  Text beyond max_text_len
+    // bytes is dropped before decoding.  Integer fields are in the given byte
+    // order.
+    pub(crate) fn parse(
+        bytes: &[u8],
+        max_text_len: usize,
+        endianness: Endianness,
+    ) -> Self {
         let next_u32 = |iter: &mut std::slice::Iter<'_, u8>| {
             let u32_bytes = [
                 *iter.next().unwrap(),
@@ -145,12 +475,12 @@ impl LabeledText {
                 *iter.next().unwrap(),
                 *iter.next().unwrap(),
             ];
-            u32::from_le_bytes(u32_bytes)
+            endianness.read_u32(u32_bytes)
         };
 
         let next_u16 = |iter: &mut std::slice::Iter<'_, u8>| {
             let u16_bytes = [*iter.next().unwrap(), *iter.next().unwrap()];
-            u16::from_le_bytes(u16_bytes)
+            endianness.read_u16(u16_bytes)
         };
 
         let mut iter = bytes.iter();
@@ -173,9 +503,9 @@ impl LabeledText {
 
         let code_page = next_u16(&mut iter);
 
-        let text =
-            String::from_utf8_lossy(&iter.copied().collect::<Vec<u8>>()[..])
-                .to_string();
+        let text_bytes = iter.as_slice();
+        let text_bytes = &text_bytes[..text_bytes.len().min(max_text_len)];
+        let text = String::from_utf8_lossy(text_bytes).to_string();
 
         LabeledText {
             cue_id,
@@ -189,24 +519,51 @@ impl LabeledText {
         }
     }
 
-    /// Get the bytes for the labeled-text, without any header
+    /// Get the bytes for the labeled-text, without any header.  These are
+    /// exactly the sub-chunk body, whose length is the size in its header,
+    /// and may be odd; see `as_bytes_padded` for composing a LIST by hand.
     pub fn as_bytes(&self) -> Vec<u8> {
-        let mut text_bytes = Vec::from(self.text.as_bytes());
+        self.as_bytes_with(Endianness::Little)
+    }
+
+    /// Get the bytes for the labeled-text as `as_bytes`, followed by a pad
+    /// byte if odd-sized, as they are laid out within an "adtl" LIST.  The
+    /// sub-chunk header must still give the size of `as_bytes`, without the
+    /// pad byte.
+    pub fn as_bytes_padded(&self) -> Vec<u8> {
+        let mut bytes = self.as_bytes();
+
+        if needs_pad(bytes.len() as u64) {
+            bytes.push(0);
+        }
+
+        bytes
+    }
+
+    /// Get the bytes for the labeled-text in the given byte order
+    pub fn as_bytes_with(&self, endianness: Endianness) -> Vec<u8> {
+        let mut text_bytes = self.encoded_text_bytes();
         let mut bytes =
             Vec::<u8>::with_capacity(LABELED_TEXT_MIN_SZ + text_bytes.len());
 
-        bytes.extend_from_slice(&self.cue_id.to_le_bytes());
-        bytes.extend_from_slice(&self.sample_length.to_le_bytes());
+        bytes.extend_from_slice(&endianness.u32_bytes(self.cue_id));
+        bytes.extend_from_slice(&endianness.u32_bytes(self.sample_length));
         bytes.extend_from_slice(&self.purpose_id);
         bytes.extend_from_slice(&self.country);
         bytes.extend_from_slice(&self.language);
         bytes.extend_from_slice(&self.dialect);
-        bytes.extend_from_slice(&self.code_page.to_le_bytes());
+        bytes.extend_from_slice(&endianness.u16_bytes(self.code_page));
         bytes.append(&mut text_bytes);
 
         bytes
     }
 
+    /// Get the text as it is stored in the file.  This determines both the
+    /// length and the trailing bytes of `as_bytes`.
+    pub fn encoded_text_bytes(&self) -> Vec<u8> {
+        Vec::from(self.text.as_bytes())
+    }
+
     ///  Get the simplest labeled-text specifying the length of an associated
     ///  cue point
     pub fn from_cue_length(cue_id: u32, sample_length: u32) -> LabeledText {
@@ -221,6 +578,30 @@ impl LabeledText {
             text: String::from(""),
         }
     }
+
+    /// Get a labeled-text for the region between two samples, to be paired
+    /// with a cue point at `start_sample`.  The purpose is "rgn ".
+    ///
+    /// Errors if `end_sample` precedes `start_sample`
+    pub fn from_region(
+        cue_id: u32,
+        start_sample: u32,
+        end_sample: u32,
+        text: &str,
+    ) -> Result<LabeledText, Error> {
+        let sample_length = end_sample.checked_sub(start_sample).ok_or(
+            Error::wave(format!(
+                "Region end {} precedes start {}",
+                end_sample, start_sample
+            )),
+        )?;
+
+        Ok(LabeledText {
+            purpose_id: *b"rgn ",
+            text: String::from(text),
+            ..Self::from_cue_length(cue_id, sample_length)
+        })
+    }
 }
 
 /// A marker identifying the location of a sample in a WAV file
@@ -228,6 +609,9 @@ impl LabeledText {
 pub struct CuePoint {
     /// Should be unique per cue point
     pub id: u32,
+
+    /// Play order of the cue point within a "plst" playlist, unrelated to
+    /// where the cue point lies in the audio; see `sample_offset`
     pub position: u32,
 
     /// Typically "data"
@@ -243,14 +627,35 @@ pub struct CuePoint {
 }
 
 impl CuePoint {
+    /// Parse a single cue point entry, without the count that leads a "cue "
+    /// chunk.
+    ///
+    /// Errors if bytes are not exactly 24 bytes long
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != CUE_SZ {
+            return Err(Error::wave(format!(
+                "Cue point is {} bytes, expected {}",
+                bytes.len(),
+                CUE_SZ
+            )));
+        }
+
+        Ok(Self::parse(bytes))
+    }
+
     // bytes length must be CUE_SZ long
     fn parse(bytes: &[u8]) -> Self {
+        Self::parse_with(bytes, Endianness::Little)
+    }
+
+    // bytes length must be CUE_SZ long, with fields in the given byte order
+    fn parse_with(bytes: &[u8], endianness: Endianness) -> Self {
         let next_array = |iter: &mut std::slice::ChunksExact<'_, u8>| {
             *iter.next().unwrap().first_chunk::<4>().unwrap()
         };
 
         let next_int = |iter: &mut std::slice::ChunksExact<'_, u8>| {
-            u32::from_le_bytes(next_array(iter))
+            endianness.read_u32(next_array(iter))
         };
 
         let mut chunks = bytes.chunks_exact(4);
@@ -276,328 +681,3432 @@ impl CuePoint {
         CuePoint {
             id,
             position: 0,
-            data_tag: *b"data",
+            data_tag: tag::DATA,
             chunk_start: 0,
             block_start: 0,
             sample_offset: offset,
         }
     }
 
+    /// Get a cue point in a "data" chunk other than the first sample of the
+    /// WAVE, such as a "data" sub-chunk of a "wavl" LIST.  `chunk_start` is
+    /// the offset of the chunk's header as `WaveSegment::chunk_start` gives,
+    /// `block_start` the byte offset of the block holding the sample within
+    /// the chunk's samples, and `sample_offset` the offset of the sample
+    /// from the start of that block.  See `resolve_cue_byte_offset`.
+    pub fn in_data_chunk(
+        id: u32,
+        sample_offset: u32,
+        chunk_start: u32,
+        block_start: u32,
+    ) -> Self {
+        CuePoint {
+            chunk_start,
+            block_start,
+            ..Self::from_sample_offset(id, sample_offset)
+        }
+    }
+
+    /// Get the sample frame of the cue point on the timeline of a recording
+    /// session, given the time reference of the file's "bext" chunk, the
+    /// sample frame at which the file starts; see
+    /// `ChunkReader::read_bext_time_reference`.  Cue points of files recorded
+    /// on the same clock line up by their absolute sample.
+    pub fn absolute_sample(&self, bext_time_reference: u64) -> u64 {
+        bext_time_reference.saturating_add(self.sample_offset.into())
+    }
+
+    /// Get the same cue point with the given playlist position
+    pub fn with_position(self, position: u32) -> Self {
+        CuePoint { position, ..self }
+    }
+
+    /// Check that the cue point lies within the given number of sample frames.
+    /// An offset equal to the count marks the end of the samples.
+    ///
+    /// Errors if the sample offset is past the end of the samples
+    pub fn validate_against(
+        &self,
+        data_sample_count: u32,
+    ) -> Result<(), Error> {
+        if self.sample_offset > data_sample_count {
+            Err(Error::wave(format!(
+                "Cue {} at sample {} is past the last sample {}",
+                self.id, self.sample_offset, data_sample_count
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Heuristic for a cue point mistakenly authored as a byte offset: the
+    /// sample offset is a whole number of sample frames when treated as bytes,
+    /// and lies past the end of the samples but within the "data" chunk's
+    /// bytes.  Always false for single-byte frames (8-bit mono), where samples
+    /// and bytes are indistinguishable.
+    pub fn looks_like_byte_offset(
+        &self,
+        block_align: u16,
+        data_sample_count: u32,
+    ) -> bool {
+        let block_align = u64::from(block_align);
+        let offset = u64::from(self.sample_offset);
+        let data_bytes = u64::from(data_sample_count) * block_align;
+
+        block_align > 1
+            && offset % block_align == 0
+            && offset > u64::from(data_sample_count)
+            && offset <= data_bytes
+    }
+
     /// Get the bytes for a single cue point
     pub fn as_bytes(&self) -> [u8; CUE_SZ] {
+        self.as_bytes_with(Endianness::Little)
+    }
+
+    /// Get the bytes for a single cue point in the given byte order
+    pub fn as_bytes_with(&self, endianness: Endianness) -> [u8; CUE_SZ] {
         let mut bytes = [0u8; CUE_SZ];
-        bytes[..4].copy_from_slice(&self.id.to_le_bytes()[..]);
-        bytes[4..8].copy_from_slice(&self.position.to_le_bytes()[..]);
+        bytes[..4].copy_from_slice(&endianness.u32_bytes(self.id));
+        bytes[4..8].copy_from_slice(&endianness.u32_bytes(self.position));
         bytes[8..12].copy_from_slice(&self.data_tag[..]);
-        bytes[12..16].copy_from_slice(&self.chunk_start.to_le_bytes()[..]);
-        bytes[16..20].copy_from_slice(&self.block_start.to_le_bytes()[..]);
-        bytes[20..].copy_from_slice(&self.sample_offset.to_le_bytes()[..]);
+        bytes[12..16].copy_from_slice(&endianness.u32_bytes(self.chunk_start));
+        bytes[16..20].copy_from_slice(&endianness.u32_bytes(self.block_start));
+        bytes[20..].copy_from_slice(&endianness.u32_bytes(self.sample_offset));
         bytes
     }
 }
 
-/// Parse bytes as an array of cue points.  Leftover bytes are ignored.
-pub fn parse_cue_points(bytes: &[u8]) -> Vec<CuePoint> {
-    (bytes[4..])
-        .chunks_exact(CUE_SZ)
-        .map(CuePoint::parse)
-        .collect()
-}
+/// A cue point joined with the name from the label, and the region length
+/// and purpose from the labeled-text, sharing its ID
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Marker {
+    pub cue: CuePoint,
 
-/// Treat bytes as the body of a LIST chunk, and extract all labeled-text sub-
-/// chunks.
-pub fn extract_labeled_text_from_list(bytes: &[u8]) -> Vec<LabeledText> {
-    let mut labeled_texts = vec![];
+    /// Label text, or labeled-text text if there is no label, `None` if
+    /// absent or empty
+    pub label: Option<String>,
 
-    if bytes.len() < 4 {
-        return labeled_texts;
-    }
+    /// Labeled-text sample length, `None` if absent or 0, in which case the
+    /// marker is a point rather than a region
+    pub region_length: Option<u32>,
+
+    /// Labeled-text purpose, `None` if there is no labeled-text
+    pub purpose_id: Option<[u8; 4]>,
+}
 
-    let mut slice = &bytes[4..];
+impl Marker {
+    /// Join a cue point with the first labeled-text referencing its ID
+    pub fn join(cue: CuePoint, labeled_texts: &[LabeledText]) -> Self {
+        Self::join_with_labels(cue, &[], labeled_texts)
+    }
 
-    while slice.len() >= 8 {
-        let mut sub_chunk_len = [0u8; 4];
+    /// Join a cue point with the first label and first labeled-text
+    /// referencing its ID.  Applications that write both usually keep the
+    /// name in the label and only the region in the labeled-text, so the
+    /// label's text takes precedence as the name, falling back to the
+    /// labeled-text's text when there is no label or the label is empty.
+    /// Region length and purpose always come from the labeled-text.
+    pub fn join_with_labels(
+        cue: CuePoint,
+        labels: &[Label],
+        labeled_texts: &[LabeledText],
+    ) -> Self {
+        let labl = labels.iter().find(|labl| labl.cue_id == cue.id);
+        let ltxt = labeled_texts.iter().find(|ltxt| ltxt.cue_id == cue.id);
 
-        sub_chunk_len.copy_from_slice(&slice[4..8]);
-        let sub_chunk_len = u32::from_le_bytes(sub_chunk_len) as usize;
-        let sub_chunk_tag = slice[..4].chunks(4).next().unwrap();
-        slice = &slice[8..];
+        let label = labl
+            .map(|labl| &labl.text)
+            .filter(|text| !text.is_empty())
+            .or(ltxt.map(|ltxt| &ltxt.text).filter(|text| !text.is_empty()))
+            .cloned();
 
-        if &sub_chunk_tag == b"ltxt"
-            && sub_chunk_len >= LABELED_TEXT_MIN_SZ
-            && slice.len() >= sub_chunk_len
-        {
-            let sub_chunk = &slice[..sub_chunk_len];
-            labeled_texts.push(LabeledText::parse(sub_chunk));
+        Marker {
+            cue,
+            label,
+            region_length: ltxt
+                .and_then(|ltxt| ltxt.as_region(cue.sample_offset))
+                .map(|region| region.length),
+            purpose_id: ltxt.map(|ltxt| ltxt.purpose_id),
         }
+    }
 
-        slice = &slice[sub_chunk_len.min(slice.len())..];
+    /// Get the region the marker spans, named by its label, or `None` if it
+    /// marks a point; see `LabeledText::as_region`
+    pub fn as_region(&self) -> Option<Region> {
+        self.region_length.map(|length| Region {
+            start: self.cue.sample_offset,
+            length,
+            name: self.label.clone().unwrap_or_default(),
+        })
+    }
 
-        if sub_chunk_len & 1 == 1 && !slice.is_empty() {
-            slice = &slice[1..];
-        }
+    /// Split the marker into its cue point and, if it has a label, region
+    /// length, or purpose, a labeled-text referencing the cue point and
+    /// holding the label as its text.  The purpose defaults to "mark".
+    pub fn split(&self) -> (CuePoint, Option<LabeledText>) {
+        let ltxt = (self.label.is_some()
+            || self.region_length.is_some()
+            || self.purpose_id.is_some())
+        .then(|| LabeledText {
+            purpose_id: self.purpose_id.unwrap_or(*b"mark"),
+            text: self.label.clone().unwrap_or_default(),
+            ..LabeledText::from_cue_length(
+                self.cue.id,
+                self.region_length.unwrap_or(0),
+            )
+        });
+
+        (self.cue, ltxt)
     }
 
-    labeled_texts
-}
+    /// Split the marker into its cue point, a label if it has a name, and a
+    /// labeled-text without text if it has a region length or purpose.  The
+    /// purpose defaults to "mark".
+    pub fn split_with_label(
+        &self,
+    ) -> (CuePoint, Option<Label>, Option<LabeledText>) {
+        let labl = self.label.as_ref().map(|text| Label {
+            cue_id: self.cue.id,
+            text: text.clone(),
+        });
 
-fn read_riff_head<Cursor: Read + Seek>(
-    cursor: &mut Cursor,
-) -> Result<ChunkHead, Error> {
-    let mut wave_id = [0u8; 4];
-    let head = ChunkHead::parse(cursor)?;
-    cursor.read_exact(&mut wave_id)?;
+        let ltxt = (self.region_length.is_some() || self.purpose_id.is_some())
+            .then(|| LabeledText {
+                purpose_id: self.purpose_id.unwrap_or(*b"mark"),
+                ..LabeledText::from_cue_length(
+                    self.cue.id,
+                    self.region_length.unwrap_or(0),
+                )
+            });
 
-    if head.tag != *b"RIFF" || wave_id != *b"WAVE" {
-        return Err(Error::wave("Not a WAVE file"));
+        (self.cue, labl, ltxt)
     }
+}
 
-    if head.size & 1 == 1 {
-        return Err(Error::wave("Malformed file: Odd RIFF size"));
+/// A named span of samples, the representation used to interchange regions
+/// between applications.  Regions are reconstructed from the standard chunks,
+/// a cue point for the start and a labeled-text for the length and name,
+/// rather than read from any application's proprietary region chunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    /// Sample offset of the first sample
+    pub start: u32,
+
+    /// Number of samples, always more than 0
+    pub length: u32,
+    pub name: String,
+}
+
+impl Region {
+    /// Get the region starting at the cue point, if the first labeled-text
+    /// referencing its ID with a non-zero sample length exists
+    pub fn join(cue: &CuePoint, labeled_texts: &[LabeledText]) -> Option<Self> {
+        labeled_texts
+            .iter()
+            .filter(|ltxt| ltxt.cue_id == cue.id)
+            .find_map(|ltxt| ltxt.as_region(cue.sample_offset))
     }
+}
 
-    Ok(head)
+/// IDs of the cue points that no labeled-text names, in cue point order, for
+/// finding markers placed but never named.  A cue point is named by a
+/// labeled-text referencing its ID with non-empty text, the same join
+/// `Marker::join` makes; labeled-texts holding only a region length do not
+/// name it.  To also count names in "labl" sub-chunks, use
+/// `unlabeled_cue_ids_with_labels`.
+pub fn unlabeled_cue_ids(
+    cues: &[CuePoint],
+    labeled_texts: &[LabeledText],
+) -> Vec<u32> {
+    unlabeled_cue_ids_with_labels(cues, labeled_texts, &[])
 }
 
-fn pad_size_16(size: usize) -> Option<usize> {
-    if size & 1 == 1 {
-        size.checked_add(1)
-    } else {
-        Some(size)
+/// IDs of the cue points named by neither a labeled-text nor a "labl"
+/// sub-chunk, in cue point order.  Labels with empty text do not name a cue
+/// point, as with labeled-texts.
+pub fn unlabeled_cue_ids_with_labels(
+    cues: &[CuePoint],
+    labeled_texts: &[LabeledText],
+    labels: &[Label],
+) -> Vec<u32> {
+    let named = labeled_texts
+        .iter()
+        .filter(|ltxt| !ltxt.text.is_empty())
+        .map(|ltxt| ltxt.cue_id)
+        .chain(
+            labels
+                .iter()
+                .filter(|label| !label.text.is_empty())
+                .map(|label| label.cue_id),
+        )
+        .collect::<std::collections::HashSet<_>>();
+
+    cues.iter()
+        .map(|cue| cue.id)
+        .filter(|id| !named.contains(id))
+        .collect()
+}
+
+/// Find every pair of markers whose regions share at least one sample, as
+/// indices into `markers` with the lower index first, sorted.  A region spans
+/// `region_length` samples from the marker's sample offset, so regions that
+/// merely touch end to start do not overlap.  Markers without a region
+/// length are points, and never overlap anything.
+///
+/// Runs in O(n log n) time, plus the number of overlapping pairs.
+pub fn find_overlapping_regions(markers: &[Marker]) -> Vec<(usize, usize)> {
+    let span = |i: usize| {
+        let start = u64::from(markers[i].cue.sample_offset);
+        (
+            start,
+            start + u64::from(markers[i].region_length.unwrap_or(0)),
+        )
+    };
+
+    let mut by_start = (0..markers.len())
+        .filter(|&i| markers[i].region_length.is_some_and(|len| len > 0))
+        .collect::<Vec<_>>();
+    by_start.sort_by_key(|&i| span(i));
+
+    // Regions started so far that may still overlap later ones
+    let mut open: Vec<usize> = vec![];
+    let mut overlaps = vec![];
+
+    for i in by_start {
+        let (start, _) = span(i);
+        open.retain(|&j| span(j).1 > start);
+        overlaps.extend(open.iter().map(|&j| (i.min(j), i.max(j))));
+        open.push(i);
     }
+
+    overlaps.sort();
+    overlaps
 }
 
-/// Wraps a cursor (Read + Seek) implementation that can read chunks.
-#[derive(Debug, PartialEq, Eq)]
-pub struct ChunkReader<Cursor: Read + Seek> {
-    head: ChunkHead,
-    base_cursor: Cursor,
-    wave_start: u64,
-    wave_end: u64,
-    first_chunk_pos: u64,
+/// Cue points sorted by sample offset, for looking up cue points by sample in
+/// O(log n) time
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CueIndex {
+    cues: Vec<CuePoint>,
 }
 
-impl<Cursor: Read + Seek> ChunkReader<Cursor> {
-    /// Wraps cursor pointing to the beginning of a WAV file in a reader.
-    ///
-    /// Errors if underlying cursor errors or file is not a valid WAV
-    pub fn new(mut cursor: Cursor) -> Result<Self, Error> {
-        let wave_start = cursor.stream_position()?;
-        let head = read_riff_head(&mut cursor)?;
-        let first_chunk_pos = cursor.stream_position()?;
-        let wave_end = wave_start
-            .checked_add(CHUNK_HEAD_SZ.try_into().unwrap())
-            .and_then(|sz| sz.checked_add(head.size.into()))
-            .ok_or(Error::wave("WAVE size too large for file"))?;
+impl CueIndex {
+    /// Build an index from cue points.  Cue points sharing a sample offset
+    /// keep their relative order.
+    pub fn new(cues: &[CuePoint]) -> Self {
+        let mut cues = Vec::from(cues);
+        cues.sort_by_key(|cue| cue.sample_offset);
+        CueIndex { cues }
+    }
 
-        Ok(Self {
-            head,
-            base_cursor: cursor,
-            wave_start,
-            wave_end,
-            first_chunk_pos,
-        })
+    /// All cue points, sorted by sample offset
+    pub fn cues(&self) -> &[CuePoint] {
+        &self.cues
     }
 
-    /// Reset reader to initial state for reading chunks
-    pub fn reset(&mut self) -> Result<(), Error> {
-        self.base_cursor
-            .seek(SeekFrom::Start(self.first_chunk_pos))
-            .map(|_| ())
-            .map_err(Error::Io)
+    /// Get the last cue point at or before `sample`
+    pub fn at_or_before(&self, sample: u32) -> Option<&CuePoint> {
+        let end = self.cues.partition_point(|cue| cue.sample_offset <= sample);
+        end.checked_sub(1).map(|i| &self.cues[i])
     }
 
-    /// Seek cursor to its position before being wrapped, and return the cursor
-    ///
-    /// Errors if underlying cursor errors
-    pub fn restore_cursor(mut self) -> Result<Cursor, Error> {
-        self.base_cursor.seek(SeekFrom::Start(self.wave_start))?;
-        Ok(self.base_cursor)
+    /// Get the cue points with sample offsets within `samples`
+    pub fn range(&self, samples: std::ops::Range<u32>) -> &[CuePoint] {
+        let start = self
+            .cues
+            .partition_point(|cue| cue.sample_offset < samples.start);
+        let end = self
+            .cues
+            .partition_point(|cue| cue.sample_offset < samples.end)
+            .max(start);
+        &self.cues[start..end]
     }
+}
 
-    /// Read the very next chunk in the stream.  If a tag is provided, find the
-    /// next chunk with the matching tag.
-    ///
-    /// Errors if chunk is too large or if underlying cursor errors
-    pub fn read_next_chunk(
-        &mut self,
-        tag: Option<[u8; 4]>,
-    ) -> Result<Option<ChunkDefinition>, Error> {
-        let current_position = |curs: &mut Cursor| curs.stream_position();
+/// Parse bytes as an array of cue points.  Leftover bytes are ignored; use
+/// `try_parse_cue_points` to reject them.
+pub fn parse_cue_points(bytes: &[u8]) -> Vec<CuePoint> {
+    parse_cue_points_with(bytes, Endianness::Little)
+}
 
-        let mut chunk = None;
+// Parse cue points as `parse_cue_points`, with fields in the given byte order
+pub(crate) fn parse_cue_points_with(
+    bytes: &[u8],
+    endianness: Endianness,
+) -> Vec<CuePoint> {
+    bytes
+        .get(4..)
+        .unwrap_or(&[])
+        .chunks_exact(CUE_SZ)
+        .map(|entry| CuePoint::parse_with(entry, endianness))
+        .collect()
+}
 
-        while current_position(&mut self.base_cursor)? < self.wave_end
-            && chunk.is_none()
-        {
-            let chunk_head = ChunkHead::parse(&mut self.base_cursor)?;
-            let size = chunk_head.size();
-
-            if tag.is_none() || Some(chunk_head.tag) == tag {
-                let mut buffer = vec![
-                    0u8;
-                    usize::try_from(size).map_err(|_| {
-                        Error::wave(format!(
-                            "Chunk size {} too large for platform",
-                            size
-                        ))
-                    })?
-                ];
-
-                self.base_cursor.read_exact(&mut buffer[..])?;
-
-                chunk = Some((chunk_head.tag, buffer));
-            } else {
-                self.base_cursor.seek(SeekFrom::Current(size.into()))?;
-            }
+/// Layout of a "cue " chunk body, as detected by `parse_cue_points_detect`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CueLayout {
+    /// A 4-byte cue count followed by the cue points, as specified
+    Counted,
 
-            if chunk_head.size & 1 == 1 {
-                self.base_cursor.seek(SeekFrom::Current(1))?;
-            }
-        }
+    /// Cue points alone, without the count, as some encoders write
+    Uncounted,
+}
 
-        Ok(chunk)
+/// Parse bytes as an array of cue points, detecting whether the cue count is
+/// missing from before them.  A body of whole cue points, a multiple of 24
+/// bytes, is taken to have no count; any other body is parsed with a count,
+/// as by `parse_cue_points`.  Returns the cue points and the layout chosen.
+pub fn parse_cue_points_detect(bytes: &[u8]) -> (Vec<CuePoint>, CueLayout) {
+    if !bytes.is_empty() && bytes.len().is_multiple_of(CUE_SZ) {
+        let cues = bytes.chunks_exact(CUE_SZ).map(CuePoint::parse).collect();
+        (cues, CueLayout::Uncounted)
+    } else {
+        (parse_cue_points(bytes), CueLayout::Counted)
     }
 }
 
-/// Wraps a cursor for writing chunks to an existing WAV
-#[derive(Debug, PartialEq, Eq)]
-pub struct ChunkWriter<Cursor: Read + Write + Seek> {
-    base_cursor: Cursor,
-    wave_start: u64,
-    wave_end: u64,
+/// How a `ChunkReader` treats a chunk head that cannot be right, with a
+/// non-printable tag or a size running past the end of the WAVE, typically
+/// left by an earlier chunk lying about its size
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecoveryPolicy {
+    /// Take every chunk head as read
+    #[default]
+    Strict,
+
+    /// Scan forward byte by byte for the next plausible chunk head, a
+    /// printable tag and a size fitting within the WAVE, and resume there.
+    /// Skipped bytes are reported by `ChunkReader::skipped_regions`.
+    Resync,
 }
 
-impl<Cursor: Read + Write + Seek> ChunkWriter<Cursor> {
-    /// Wraps cursor pointing to beginning of WAV file in a writer
-    ///
-    /// Errors if underlying cursor errors or not a valid WAV
-    pub fn new(mut cursor: Cursor) -> Result<Self, Error> {
-        let wave_start = cursor.stream_position()?;
-        let head = read_riff_head(&mut cursor)?;
-        let wave_end = wave_start
-            .checked_add(CHUNK_HEAD_SZ.try_into().unwrap())
-            .and_then(|sz| sz.checked_add(head.size.into()))
-            .ok_or(Error::wave("WAVE size too large for file"))?;
+/// How `try_parse_cue_points` treats a "cue " chunk whose declared count
+/// disagrees with the number of cue points present
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CueCountPolicy {
+    /// Trust the byte count, parsing every complete cue point present
+    Lenient,
 
-        Ok(Self {
+    /// Trust the declared count, parsing exactly that many cue points and
+    /// erroring if fewer are present
+    Strict,
+}
+
+/// Parse the body of a "cue " chunk, comparing the declared count against the
+/// number of cue points present.  Any mismatch is returned as a
+/// `Warning::CueCountMismatch` alongside the cue points chosen by `policy`.
+///
+/// `bytes` must be exactly the chunk payload, a 4-byte count followed by whole
+/// cue points.  Payloads are always an even size, so a trailing partial cue
+/// point, such as a pad byte sliced off the next chunk, is rejected rather
+/// than ignored.
+///
+/// Errors if bytes are too short to contain the count, if bytes follow the
+/// last whole cue point, or if the policy is strict and fewer cue points are
+/// present than declared
+pub fn try_parse_cue_points(
+    bytes: &[u8],
+    policy: CueCountPolicy,
+) -> Result<(Vec<CuePoint>, Option<Warning>), Error> {
+    try_parse_cue_points_with(bytes, policy, Endianness::Little)
+}
+
+// Parse cue points as `try_parse_cue_points`, with the count and fields in
+// the given byte order
+pub(crate) fn try_parse_cue_points_with(
+    bytes: &[u8],
+    policy: CueCountPolicy,
+    endianness: Endianness,
+) -> Result<(Vec<CuePoint>, Option<Warning>), Error> {
+    let (count_bytes, entries) = bytes
+        .split_first_chunk::<4>()
+        .ok_or(Error::wave("Cue chunk too short for cue count"))?;
+
+    let declared = endianness.read_u32(*count_bytes);
+    let present = entries.len() / CUE_SZ;
+
+    if entries.len() % CUE_SZ != 0 {
+        return Err(Error::wave(format!(
+            "Cue chunk length {} is not 4 + 24 * {} cue points",
+            bytes.len(),
+            present
+        )));
+    }
+    let declared_len = usize::try_from(declared).unwrap_or(usize::MAX);
+
+    let warning = (declared_len != present)
+        .then_some(Warning::CueCountMismatch { declared, present });
+
+    let count = match policy {
+        CueCountPolicy::Lenient => present,
+        CueCountPolicy::Strict if declared_len <= present => declared_len,
+        CueCountPolicy::Strict => {
+            return Err(Error::wave(format!(
+                "Cue chunk declares {} cue points but only {} present",
+                declared, present
+            )))
+        }
+    };
+
+    let cues = entries
+        .chunks_exact(CUE_SZ)
+        .take(count)
+        .map(|entry| CuePoint::parse_with(entry, endianness))
+        .collect();
+
+    Ok((cues, warning))
+}
+
+/// Check that the size in the head of a "cue " chunk matches the cue count at
+/// the start of its payload, 4 bytes for the count and 24 for each cue point,
+/// and that the payload is as long as the head says, before trusting
+/// `parse_cue_points` with it.
+///
+/// Errors if the head is not tagged "cue ", if the payload is too short to
+/// contain the count, or if the sizes disagree
+pub fn validate_cue_chunk(
+    head: &ChunkHead,
+    payload: &[u8],
+) -> Result<(), Error> {
+    if head.tag != tag::CUE {
+        return Err(Error::wave(format!(
+            "Chunk {:?} is not a cue chunk",
+            String::from_utf8_lossy(&head.tag)
+        )));
+    }
+
+    let count = payload
+        .first_chunk::<4>()
+        .map(|count| u32::from_le_bytes(*count))
+        .ok_or(Error::wave("Cue chunk too short for cue count"))?;
+    let expected = 4 + u64::from(count) * CUE_SZ as u64;
+
+    if u64::from(head.size) != expected {
+        return Err(Error::wave(format!(
+            "Cue chunk size {} does not match 4 + 24 * {} cue points",
+            head.size, count
+        )));
+    }
+
+    if payload.len() as u64 != expected {
+        return Err(Error::wave(format!(
+            "Cue chunk payload is {} bytes, expected {}",
+            payload.len(),
+            expected
+        )));
+    }
+
+    Ok(())
+}
+
+/// Treat bytes as the body of a LIST chunk, and extract all labeled-text sub-
+/// chunks.
+///
+/// Malformed lists never cause a panic, and the walk always terminates since
+/// every step consumes a full 8-byte sub-chunk header.  Recovery is as follows:
+///
+/// * Sub-chunks not tagged "ltxt", or too short to hold a labeled-text
+///   (including zero-length sub-chunks), are skipped.
+/// * A sub-chunk whose length runs past the end of the list is dropped, along
+///   with all remaining bytes.
+/// * Fewer than 8 trailing bytes, too few for a sub-chunk header, are ignored.
+///
+/// Text is cut to `DEFAULT_MAX_TEXT_LEN` bytes without warning; use
+/// `extract_labeled_text_from_list_with_max` to choose the maximum or to learn
+/// which labeled-texts were cut.
+pub fn extract_labeled_text_from_list(bytes: &[u8]) -> Vec<LabeledText> {
+    extract_labeled_text_from_list_with_max(bytes, DEFAULT_MAX_TEXT_LEN).0
+}
+
+/// Treat bytes as the body of a LIST chunk, and extract all labeled-text sub-
+/// chunks, keeping at most `max_text_len` bytes of each text.  Every cut text
+/// is reported as a `Warning::TextTruncated` with the text's original length.
+/// Recovery from malformed lists is the same as for
+/// `extract_labeled_text_from_list`.
+///
+/// Cutting happens before the text is decoded, so a multi-byte character
+/// straddling the cut is replaced with U+FFFD.
+pub fn extract_labeled_text_from_list_with_max(
+    bytes: &[u8],
+    max_text_len: usize,
+) -> (Vec<LabeledText>, Vec<Warning>) {
+    extract_labeled_texts_with(bytes, max_text_len, Endianness::Little)
+}
+
+// Extract labeled-texts as `extract_labeled_text_from_list_with_max`, with
+// sub-chunk heads and fields in the given byte order
+pub(crate) fn extract_labeled_texts_with(
+    bytes: &[u8],
+    max_text_len: usize,
+    endianness: Endianness,
+) -> (Vec<LabeledText>, Vec<Warning>) {
+    let mut warnings = vec![];
+
+    let ltxts = adtl_sub_chunks_with(bytes, endianness)
+        .filter(|(head, body)| {
+            head.tag == tag::LTXT && body.len() >= LABELED_TEXT_MIN_SZ
+        })
+        .map(|(_, body)| {
+            let ltxt = LabeledText::parse(body, max_text_len, endianness);
+            let len = body.len() - LABELED_TEXT_MIN_SZ;
+
+            if len > max_text_len {
+                warnings.push(Warning::TextTruncated {
+                    cue_id: ltxt.cue_id,
+                    len,
+                });
+            }
+
+            ltxt
+        })
+        .collect();
+
+    (ltxts, warnings)
+}
+
+/// Treat bytes as the body of a LIST chunk, and extract all label sub-chunks.
+/// Recovery from malformed lists is the same as for
+/// `extract_labeled_text_from_list`, with sub-chunks too short to hold a cue
+/// ID skipped.
+pub fn extract_labels_from_list(bytes: &[u8]) -> Vec<Label> {
+    extract_labels_with(bytes, Endianness::Little)
+}
+
+// Extract labels as `extract_labels_from_list`, with sub-chunk heads and cue
+// IDs in the given byte order
+pub(crate) fn extract_labels_with(
+    bytes: &[u8],
+    endianness: Endianness,
+) -> Vec<Label> {
+    adtl_sub_chunks_with(bytes, endianness)
+        .filter(|(head, _)| head.tag == *b"labl")
+        .filter_map(|(_, body)| {
+            Label::try_from_bytes_with(body, endianness).ok()
+        })
+        .collect()
+}
+
+// Walk the sub-chunks of a LIST body following its 4-byte list type, yielding
+// each sub-chunk that fits within the list
+fn adtl_sub_chunks(bytes: &[u8]) -> impl Iterator<Item = (ChunkHead, &[u8])> {
+    adtl_sub_chunks_with(bytes, Endianness::Little)
+}
+
+// Walk sub-chunks as `adtl_sub_chunks`, with heads in the given byte order
+fn adtl_sub_chunks_with(
+    bytes: &[u8],
+    endianness: Endianness,
+) -> impl Iterator<Item = (ChunkHead, &[u8])> {
+    let mut slice = bytes.get(4..).unwrap_or(&[]);
+
+    std::iter::from_fn(move || loop {
+        let sub_chunk_head =
+            ChunkHead::parse_with(&mut slice, endianness).ok()?;
+        let sub_chunk_len =
+            usize::try_from(sub_chunk_head.size).unwrap_or(usize::MAX);
+        let (sub_chunk, rest) = slice.split_at(sub_chunk_len.min(slice.len()));
+        slice = rest;
+
+        if needs_pad(sub_chunk_len as u64) && !slice.is_empty() {
+            slice = &slice[1..];
+        }
+
+        if sub_chunk.len() == sub_chunk_len {
+            return Some((sub_chunk_head, sub_chunk));
+        }
+    })
+}
+
+// First cue ID after `after` not in `taken`, wrapping around past
+// `u32::MAX`, for renumbering cue points whose IDs collide
+pub(crate) fn free_cue_id(
+    taken: &std::collections::BTreeSet<u32>,
+    after: u32,
+) -> Result<u32, Error> {
+    (after.wrapping_add(1)..=u32::MAX)
+        .chain(0..=after)
+        .find(|id| !taken.contains(id))
+        .ok_or(Error::wave("No free cue ID left"))
+}
+
+/// Build a complete "cue " chunk holding the cue points, to be written with
+/// `ChunkWriter::append_chunk` or composed into a larger file
+///
+/// Errors if resulting chunk is too large
+pub fn cue_chunk(cues: &[CuePoint]) -> Result<ChunkDefinition, Error> {
+    cue_chunk_with(cues, Endianness::Little)
+}
+
+// Build a "cue " chunk in the given byte order
+fn cue_chunk_with(
+    cues: &[CuePoint],
+    endianness: Endianness,
+) -> Result<ChunkDefinition, Error> {
+    let chunk_size = size_u32(
+        (cues.len() as u64)
+            .saturating_mul(CUE_SZ as u64)
+            .saturating_add(4),
+    )?;
+
+    let mut payload = Vec::with_capacity(chunk_size as usize);
+    payload.extend_from_slice(&endianness.u32_bytes(cues.len() as u32));
+
+    for cue in cues {
+        payload.extend_from_slice(&cue.as_bytes_with(endianness));
+    }
+
+    Ok((tag::CUE, payload))
+}
+
+/// Build a complete "adtl" LIST chunk holding a labeled-text sub-chunk for
+/// each of the labeled texts, to be written with `ChunkWriter::append_chunk`
+/// or composed into a larger file
+///
+/// Errors if resulting chunk is too large
+pub fn label_chunk(
+    labeled_texts: &[LabeledText],
+) -> Result<ChunkDefinition, Error> {
+    label_chunk_with(labeled_texts, Endianness::Little)
+}
+
+// Build an "adtl" LIST chunk of labeled-texts in the given byte order
+fn label_chunk_with(
+    labeled_texts: &[LabeledText],
+    endianness: Endianness,
+) -> Result<ChunkDefinition, Error> {
+    adtl_chunk(
+        labeled_texts.iter().map(|labeled_text| {
+            (tag::LTXT, labeled_text.as_bytes_with(endianness))
+        }),
+        endianness,
+    )
+}
+
+/// Build a complete "adtl" LIST chunk holding a "labl" sub-chunk for each of
+/// the labels, to be written with `ChunkWriter::append_chunk` or composed into
+/// a larger file
+///
+/// Errors if resulting chunk is too large
+pub fn labl_chunk(labels: &[Label]) -> Result<ChunkDefinition, Error> {
+    labl_chunk_with(labels, Endianness::Little)
+}
+
+// Build an "adtl" LIST chunk of labels in the given byte order
+fn labl_chunk_with(
+    labels: &[Label],
+    endianness: Endianness,
+) -> Result<ChunkDefinition, Error> {
+    adtl_chunk(
+        labels
+            .iter()
+            .map(|label| (*b"labl", label.as_bytes_with(endianness))),
+        endianness,
+    )
+}
+
+// Build an "adtl" LIST chunk from tagged sub-chunk bodies, with sub-chunk
+// headers in the given byte order
+fn adtl_chunk(
+    sub_chunks: impl Iterator<Item = ([u8; 4], Vec<u8>)>,
+    endianness: Endianness,
+) -> Result<ChunkDefinition, Error> {
+    let mut payload = tag::ADTL.to_vec();
+
+    for (tag, sub_chunk) in sub_chunks {
+        let sub_chunk_head = ChunkHead {
+            tag,
+            size: size_u32(sub_chunk.len() as u64)?,
+        };
+
+        payload.extend_from_slice(&sub_chunk_head.as_bytes_with(endianness));
+        payload.extend_from_slice(&sub_chunk);
+
+        if needs_pad(sub_chunk.len() as u64) {
+            payload.push(0);
+        }
+
+        size_u32(payload.len() as u64)?;
+    }
+
+    Ok((tag::LIST, payload))
+}
+
+/// Rebase cue IDs so the lowest becomes `base`, e.g. 1 for files from tools
+/// that count from 0, and shift the cue IDs of labeled-texts by the same
+/// amount so every label stays joined to its cue point.  IDs wrap around
+/// rather than overflow, so distinct IDs always stay distinct.  Nothing
+/// changes when there are no cue points.
+pub fn normalize_cue_ids(
+    cues: &mut [CuePoint],
+    labels: &mut [LabeledText],
+    base: u32,
+) {
+    let Some(lowest) = cues.iter().map(|cue| cue.id).min() else {
+        return;
+    };
+    let delta = base.wrapping_sub(lowest);
+
+    for cue in cues.iter_mut() {
+        cue.id = cue.id.wrapping_add(delta);
+    }
+
+    for label in labels.iter_mut() {
+        label.cue_id = label.cue_id.wrapping_add(delta);
+    }
+}
+
+/// Whether cue points are in order of sample offset, as some strict readers
+/// require of the cue points in a "cue " chunk; see
+/// `ChunkWriter::set_sort_cue_points`
+pub fn cue_points_are_sorted(cues: &[CuePoint]) -> bool {
+    cues.is_sorted_by_key(|cue| cue.sample_offset)
+}
+
+/// Set the playlist position of every cue point to its 0-based rank by sample
+/// offset, so playing a playlist in position order follows the audio.  Cue
+/// points sharing an offset are ranked in slice order.  The cue points
+/// themselves are not reordered.
+pub fn reindex_positions(cues: &mut [CuePoint]) {
+    let mut order = (0..cues.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| cues[i].sample_offset);
+
+    for (position, i) in (0u32..).zip(order) {
+        cues[i].position = position;
+    }
+}
+
+/// Add `delta` to the sample offset of every cue point, e.g. a negative delta
+/// of N after trimming N samples from the front of a file.  Cue points that
+/// would end up before the first sample are clamped to 0 when `clamp` is set
+/// and removed otherwise.  Offsets saturate at `u32::MAX`, and the order of
+/// cue points is preserved.
+pub fn shift_cue_points(cues: &mut Vec<CuePoint>, delta: i64, clamp: bool) {
+    cues.retain_mut(|cue| match shift_sample(cue.sample_offset, delta) {
+        Some(offset) => {
+            cue.sample_offset = offset;
+            true
+        }
+        None if clamp => {
+            cue.sample_offset = 0;
+            true
+        }
+        None => false,
+    });
+}
+
+/// Adjust labeled-texts to match `shift_cue_points` given the same `delta` and
+/// `clamp`.  `cues` must be the cue points *before* shifting, as each label's
+/// region starts at the sample offset of its associated cue point.
+///
+/// Labels are removed along with their cue points when not clamping.  When
+/// clamping, a region straddling the first sample is shortened to the part
+/// that remains, and a region ending before it is reduced to length 0.  Labels
+/// without an associated cue point are left untouched.
+pub fn shift_labeled_texts(
+    labeled_texts: &mut Vec<LabeledText>,
+    cues: &[CuePoint],
+    delta: i64,
+    clamp: bool,
+) {
+    labeled_texts.retain_mut(|ltxt| {
+        let start = match cues.iter().find(|cue| cue.id == ltxt.cue_id) {
+            Some(cue) => cue.sample_offset,
+            None => return true,
+        };
+
+        if shift_sample(start, delta).is_some() {
+            true
+        } else if clamp {
+            let end = i64::from(start) + i64::from(ltxt.sample_length) + delta;
+            ltxt.sample_length = u32::try_from(end.max(0)).unwrap_or(u32::MAX);
+            true
+        } else {
+            false
+        }
+    });
+}
+
+// None if the shifted sample falls before the first sample
+fn shift_sample(sample: u32, delta: i64) -> Option<u32> {
+    let shifted = i64::from(sample).saturating_add(delta);
+
+    if shifted < 0 {
+        None
+    } else {
+        Some(u32::try_from(shifted).unwrap_or(u32::MAX))
+    }
+}
+
+/// Rescale the sample offset of every cue point from a sample rate of
+/// `from_rate` to `to_rate`, as when resampling the audio.  Offsets are
+/// rounded to the nearest sample and saturate at `u32::MAX`.  Nothing changes
+/// if `from_rate` is 0.
+pub fn resample_cue_points(
+    cues: &mut [CuePoint],
+    from_rate: u32,
+    to_rate: u32,
+) {
+    for cue in cues {
+        cue.sample_offset =
+            resample_sample(cue.sample_offset, from_rate, to_rate);
+    }
+}
+
+/// Rescale the sample length of every labeled-text to match
+/// `resample_cue_points` given the same rates
+pub fn resample_labeled_texts(
+    labeled_texts: &mut [LabeledText],
+    from_rate: u32,
+    to_rate: u32,
+) {
+    for ltxt in labeled_texts {
+        ltxt.sample_length =
+            resample_sample(ltxt.sample_length, from_rate, to_rate);
+    }
+}
+
+fn resample_sample(sample: u32, from_rate: u32, to_rate: u32) -> u32 {
+    if from_rate == 0 {
+        return sample;
+    }
+
+    let from_rate = u64::from(from_rate);
+    let scaled =
+        (u64::from(sample) * u64::from(to_rate) + from_rate / 2) / from_rate;
+    u32::try_from(scaled).unwrap_or(u32::MAX)
+}
+
+/// Check whether a stream starts like a WAVE file, by a "RIFF", "RIFX",
+/// "RF64", or "BW64" tag and a "WAVE" form id in its first 12 bytes, without
+/// checking any sizes.  Cheaper than `ChunkReader::new` for classifying
+/// files, and needs no `Seek`.  "RIFX" files store sizes big-endian; see
+/// `Endianness`.  The cursor is left after the bytes read.
+///
+/// Returns `false` if the stream has fewer than 12 bytes or errors
+pub fn is_wave(cursor: &mut impl Read) -> bool {
+    let mut header = [0u8; 12];
+
+    if cursor.read_exact(&mut header).is_err() {
+        return false;
+    }
+
+    let (riff_tag, rest) = header.split_first_chunk::<4>().unwrap();
+    let form_id = rest.last_chunk::<4>().unwrap();
+
+    WaveFlavor::of_riff_tag(riff_tag).is_some() && *form_id == *b"WAVE"
+}
+
+// Read the RIFF header and form id, checking the form id if one is expected,
+// along with the kind of RIFF header matched.  RF64 headers are accepted; see
+// `rf64::riff_body_size` for their size.  RIFX headers are accepted too, with
+// the size read big-endian.
+fn read_riff_head<Cursor: Read + Seek>(
+    cursor: &mut Cursor,
+    expected_form: Option<[u8; 4]>,
+) -> Result<(ChunkHead, [u8; 4], WaveFlavor), Error> {
+    let mut form_id = [0u8; 4];
+    let head = ChunkHead::parse(cursor)?;
+    cursor.read_exact(&mut form_id)?;
+    let flavor = WaveFlavor::of_riff_tag(&head.tag);
+    let is_rf64 = matches!(flavor, Some(WaveFlavor::Rf64 | WaveFlavor::Bw64));
+    let head = match Endianness::of_riff_tag(&head.tag) {
+        Endianness::Little => head,
+        Endianness::Big => ChunkHead {
+            size: head.size.swap_bytes(),
+            ..head
+        },
+    };
+
+    let flavor = match flavor {
+        Some(flavor)
+            if expected_form.is_none_or(|expected| expected == form_id) =>
+        {
+            flavor
+        }
+        _ => {
+            return Err(match expected_form {
+                Some(expected) if expected == *b"WAVE" => {
+                    Error::wave("Not a WAVE file")
+                }
+                Some(expected) => Error::wave(format!(
+                    "Not a RIFF {:?} file",
+                    String::from_utf8_lossy(&expected)
+                )),
+                None => Error::wave("Not a RIFF file"),
+            });
+        }
+    };
+
+    if head.size & 1 == 1 && !(is_rf64 && head.size == u32::MAX) {
+        return Err(Error::wave("Malformed file: Odd RIFF size"));
+    }
+
+    // The RIFF size counts the form id, so appends seeking past the chunks
+    // from just after the form id never seek backwards
+    if head.size < 4 {
+        return Err(Error::wave("Malformed file: RIFF size excludes form id"));
+    }
+
+    Ok((head, form_id, flavor))
+}
+
+// Size of a RIFF header, for edits that move chunks around, which are not
+// supported for RF64 or RIFX files
+fn non_rf64_size(riff_head: &ChunkHead) -> Result<u32, Error> {
+    if RF64_TAGS.contains(&riff_head.tag) || riff_head.tag == RIFX_TAG {
+        Err(Error::wave(
+            "Only appends are supported for RF64 and RIFX files",
+        ))
+    } else {
+        Ok(riff_head.size)
+    }
+}
+
+fn is_printable_tag(tag: &[u8; 4]) -> bool {
+    tag.iter().all(|b| (0x20..=0x7E).contains(b))
+}
+
+/// Whether a chunk body of `size` bytes is followed by a pad byte, keeping
+/// chunks aligned to 16 bits
+pub fn needs_pad(size: u64) -> bool {
+    size & 1 == 1
+}
+
+/// Number of pad bytes following a chunk body of `size` bytes, 0 or 1
+pub fn pad_len(size: u64) -> u64 {
+    u64::from(needs_pad(size))
+}
+
+/// Size of a chunk body of `size` bytes including its pad byte, or `None` on
+/// overflow
+pub fn pad_size_16(size: usize) -> Option<usize> {
+    size.checked_add(pad_len(size as u64) as usize)
+}
+
+/// Write the smallest valid WAVE holding raw samples: the RIFF header, a "fmt "
+/// chunk, and a "data" chunk, padded if odd-sized.  Markers and other chunks
+/// can then be added with `ChunkWriter`.
+///
+/// Errors if the samples are too large for a RIFF file or if writing errors
+pub fn wrap_pcm(
+    samples: &[u8],
+    format: &FormatChunk,
+    out: &mut impl Write,
+) -> Result<(), Error> {
+    let fmt_body = format.as_bytes();
+    let data_size = size_u32(samples.len() as u64)?;
+    let riff_size =
+        size_u32([fmt_body.len(), samples.len()].into_iter().fold(
+            4u64,
+            |sz, body_len| {
+                let body_len = body_len as u64;
+                sz.saturating_add(CHUNK_HEAD_SZ as u64 + body_len)
+                    .saturating_add(pad_len(body_len))
+            },
+        ))?;
+
+    let riff_head = ChunkHead {
+        tag: *b"RIFF",
+        size: riff_size,
+    };
+    let fmt_head = ChunkHead {
+        tag: tag::FMT,
+        size: fmt_body.len() as u32,
+    };
+    let data_head = ChunkHead {
+        tag: tag::DATA,
+        size: data_size,
+    };
+
+    out.write_all(&riff_head.as_bytes())?;
+    out.write_all(b"WAVE")?;
+    out.write_all(&fmt_head.as_bytes())?;
+    out.write_all(&fmt_body)?;
+    out.write_all(&data_head.as_bytes())?;
+    out.write_all(samples)?;
+
+    if needs_pad(samples.len() as u64) {
+        out.write_all(&[0u8])?;
+    }
+
+    Ok(())
+}
+
+/// Total bytes a chunk with a body of `payload_len` bytes takes up on disk,
+/// including its head and pad byte, for working out how much a file grows
+/// before appending.  `None` if the body is too large for a chunk.
+pub fn aligned_chunk_len(payload_len: usize) -> Option<u64> {
+    let size = u32::try_from(payload_len).ok()?;
+    Some(CHUNK_HEAD_SZ as u64 + ChunkHead { tag: [0; 4], size }.padded_size())
+}
+
+// Walk chunk heads between start and end, returning the position and head of
+// the first chunk satisfying `matches`
+fn find_chunk<Cursor: Read + Seek>(
+    cursor: &mut Cursor,
+    start: u64,
+    end: u64,
+    endianness: Endianness,
+    matches: impl Fn(&ChunkHead) -> bool,
+) -> Result<Option<(u64, ChunkHead)>, Error> {
+    let mut position = start;
+
+    while position < end {
+        cursor.seek(SeekFrom::Start(position))?;
+        let head = ChunkHead::parse_with(cursor, endianness)?;
+
+        if matches(&head) {
+            return Ok(Some((position, head)));
+        }
+
+        position += CHUNK_HEAD_SZ as u64
+            + u64::from(head.size)
+            + pad_len(head.size.into());
+    }
+
+    Ok(None)
+}
+
+// Move `len` bytes at `from` to `to`, copying in the direction that never
+// overwrites a byte before it is moved
+fn move_bytes<Cursor: Read + Write + Seek>(
+    cursor: &mut Cursor,
+    from: u64,
+    to: u64,
+    len: u64,
+) -> Result<(), Error> {
+    const BLOCK_SZ: u64 = 0x10000;
+    let mut buffer = vec![0u8; BLOCK_SZ.min(len) as usize];
+    let mut moved = 0;
+
+    while moved < len {
+        let block_len = BLOCK_SZ.min(len - moved);
+
+        // Back to front when moving forward, front to back otherwise
+        let block_offset = if to > from {
+            len - moved - block_len
+        } else {
+            moved
+        };
+
+        let block = &mut buffer[..block_len as usize];
+        cursor.seek(SeekFrom::Start(from + block_offset))?;
+        cursor.read_exact(block)?;
+        cursor.seek(SeekFrom::Start(to + block_offset))?;
+        cursor.write_all(block)?;
+        moved += block_len;
+    }
+
+    Ok(())
+}
+
+// Bytes making up a rebuilt WAVE: either a range already in the file, copied
+// verbatim, or new bytes
+enum Piece {
+    Existing { offset: u64, len: u64 },
+    New(Vec<u8>),
+}
+
+impl Piece {
+    fn len(&self) -> u64 {
+        match self {
+            Self::Existing { len, .. } => *len,
+            Self::New(bytes) => bytes.len() as u64,
+        }
+    }
+
+    // Head, body, and padding of a chunk already in the file
+    fn existing((offset, head): &(u64, ChunkHead)) -> Self {
+        Self::Existing {
+            offset: *offset,
+            len: CHUNK_HEAD_SZ as u64
+                + u64::from(head.size)
+                + pad_len(head.size.into()),
+        }
+    }
+
+    // Head, body, and padding of a chunk to be written
+    fn new_chunk(tag: [u8; 4], payload: &[u8]) -> Result<Self, Error> {
+        let size = size_u32(payload.len() as u64)?;
+        let mut bytes = Vec::with_capacity(CHUNK_HEAD_SZ + payload.len() + 1);
+        bytes.extend_from_slice(&ChunkHead { tag, size }.as_bytes());
+        bytes.extend_from_slice(payload);
+
+        if needs_pad(size.into()) {
+            bytes.push(0);
+        }
+
+        Ok(Self::New(bytes))
+    }
+}
+
+/// Streams that can be cut short, required by writer operations that may
+/// shrink a WAV file
+pub trait Truncate {
+    /// Shorten the stream to `len` bytes
+    fn truncate(&mut self, len: u64) -> io::Result<()>;
+}
+
+impl Truncate for std::fs::File {
+    fn truncate(&mut self, len: u64) -> io::Result<()> {
+        self.set_len(len)
+    }
+}
+
+impl Truncate for io::Cursor<Vec<u8>> {
+    fn truncate(&mut self, len: u64) -> io::Result<()> {
+        self.get_mut()
+            .truncate(len.try_into().unwrap_or(usize::MAX));
+        Ok(())
+    }
+}
+
+impl Truncate for io::Cursor<&mut Vec<u8>> {
+    fn truncate(&mut self, len: u64) -> io::Result<()> {
+        self.get_mut()
+            .truncate(len.try_into().unwrap_or(usize::MAX));
+        Ok(())
+    }
+}
+
+impl<T: Truncate + ?Sized> Truncate for &mut T {
+    fn truncate(&mut self, len: u64) -> io::Result<()> {
+        (**self).truncate(len)
+    }
+}
+
+/// What `ChunkReader::visit` does after a handler has seen a chunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkAction {
+    /// Keep the given bytes, typically the chunk body read by the handler, and
+    /// continue with the next chunk
+    Read(Vec<u8>),
+
+    /// Continue with the next chunk, seeking past whatever the handler left
+    /// unread
+    Skip,
+
+    /// End the walk without visiting any more chunks
+    Stop,
+}
+
+/// Wraps a cursor (Read + Seek) implementation that can read chunks.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ChunkReader<Cursor: Read + Seek> {
+    head: ChunkHead,
+    form_id: [u8; 4],
+    base_cursor: Cursor,
+    wave_start: u64,
+    wave_end: u64,
+    first_chunk_pos: u64,
+    max_chunk_size: u32,
+
+    // Position of the head of the chunk most recently found since reset
+    last_chunk_pos: Option<u64>,
+
+    recovery_policy: RecoveryPolicy,
+    skipped_regions: Vec<std::ops::Range<u64>>,
+
+    // 64-bit sizes of RF64 files
+    ds64: Option<Ds64Chunk>,
+
+    flavor: WaveFlavor,
+    endianness: Endianness,
+}
+
+impl<Cursor: Read + Seek> ChunkReader<Cursor> {
+    /// Wraps cursor pointing to the beginning of a WAV file in a reader.
+    ///
+    /// Errors if underlying cursor errors or file is not a valid WAV
+    pub fn new(cursor: Cursor) -> Result<Self, Error> {
+        Self::new_form(cursor, Some(*b"WAVE"))
+    }
+
+    /// Wraps cursor holding a WAV file at byte `offset`, such as one embedded
+    /// in another container, in a reader.  Offsets of chunks and cue points
+    /// are relative to the WAV, and `restore_cursor` seeks back to `offset`,
+    /// the same as if the cursor had been at `offset` for `new`.
+    ///
+    /// Errors if underlying cursor errors or file is not a valid WAV
+    pub fn new_at(mut cursor: Cursor, offset: u64) -> Result<Self, Error> {
+        cursor.seek(SeekFrom::Start(offset))?;
+        Self::new(cursor)
+    }
+
+    /// Wraps cursor pointing to the beginning of a WAV file in a reader that
+    /// reads chunks of any size into memory, for trusted input only.
+    ///
+    /// Errors if underlying cursor errors or file is not a valid WAV
+    pub fn new_unbounded(cursor: Cursor) -> Result<Self, Error> {
+        let mut reader = Self::new(cursor)?;
+        reader.set_max_chunk_size(u32::MAX);
+        Ok(reader)
+    }
+
+    /// Wraps cursor pointing to the beginning of any RIFF file in a reader,
+    /// such as "AVI " or "RMID".  If a form id is expected, the RIFF file must
+    /// have that form id.  Chunks are read the same as for WAV files, though
+    /// the cue and label helpers only make sense for WAV files.
+    ///
+    /// Errors if underlying cursor errors or file is not a valid RIFF of the
+    /// expected form
+    pub fn new_form(
+        mut cursor: Cursor,
+        expected_form: Option<[u8; 4]>,
+    ) -> Result<Self, Error> {
+        let wave_start = cursor.stream_position()?;
+        let (head, form_id, flavor) =
+            read_riff_head(&mut cursor, expected_form)?;
+        let (riff_size, ds64) = rf64::riff_body_size(&mut cursor, &head)?;
+        let first_chunk_pos = cursor.stream_position()?;
+        let wave_end = wave_start
+            .checked_add(CHUNK_HEAD_SZ.try_into().unwrap())
+            .and_then(|sz| sz.checked_add(riff_size))
+            .ok_or(Error::wave("WAVE size too large for file"))?;
+
+        Ok(Self {
+            head,
+            form_id,
+            base_cursor: cursor,
+            wave_start,
+            wave_end,
+            first_chunk_pos,
+            max_chunk_size: DEFAULT_MAX_CHUNK_SIZE,
+            last_chunk_pos: None,
+            recovery_policy: RecoveryPolicy::Strict,
+            skipped_regions: vec![],
+            ds64,
+            flavor,
+            endianness: flavor.endianness(),
+        })
+    }
+
+    /// Wraps cursor pointing to the beginning of a WAV file in a reader, after
+    /// additionally checking that a "fmt " chunk precedes a "data" chunk and
+    /// that no chunk runs past the end of the WAVE.
+    ///
+    /// Errors if underlying cursor errors or file is not a valid WAV
+    pub fn new_strict(cursor: Cursor) -> Result<Self, Error> {
+        let mut reader = Self::new(cursor)?;
+        let mut fmt_found = false;
+        let mut data_found = false;
+
+        while let Some(head) = reader.seek_next_chunk(|_| true)? {
+            let body_end =
+                reader.base_cursor.stream_position()? + u64::from(head.size);
+
+            if body_end > reader.wave_end {
+                return Err(Error::wave(format!(
+                    "Chunk {:?} of size {} runs past end of WAVE",
+                    String::from_utf8_lossy(&head.tag),
+                    head.size
+                )));
+            }
+
+            match head.tag {
+                tag::FMT => fmt_found = true,
+                tag::DATA if !fmt_found => {
+                    return Err(Error::wave("\"data\" chunk precedes \"fmt \""))
+                }
+                tag::DATA => data_found = true,
+                _ => {}
+            }
+
+            reader.skip_chunk_body(&head)?;
+        }
+
+        if !fmt_found {
+            return Err(Error::wave("No \"fmt \" chunk"));
+        }
+
+        if !data_found {
+            return Err(Error::wave("No \"data\" chunk"));
+        }
+
+        reader.reset()?;
+        Ok(reader)
+    }
+
+    /// Form id following the RIFF header, "WAVE" for WAV files
+    pub fn form_id(&self) -> [u8; 4] {
+        self.form_id
+    }
+
+    /// Kind of RIFF file, by the tag of its RIFF header as read on wrapping
+    pub fn format_flavor(&self) -> WaveFlavor {
+        self.flavor
+    }
+
+    /// Byte order of the file, which chunk headers are read in
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+
+    /// Largest chunk body read into memory, `DEFAULT_MAX_CHUNK_SIZE` unless
+    /// set otherwise
+    pub fn max_chunk_size(&self) -> u32 {
+        self.max_chunk_size
+    }
+
+    /// Set the largest chunk body read into memory.  Reading a larger chunk
+    /// errors instead of attempting the allocation, guarding against
+    /// untrusted files declaring huge chunks.  Chunks are still skipped over
+    /// regardless of size.
+    pub fn set_max_chunk_size(&mut self, max_chunk_size: u32) {
+        self.max_chunk_size = max_chunk_size;
+    }
+
+    pub fn recovery_policy(&self) -> RecoveryPolicy {
+        self.recovery_policy
+    }
+
+    /// Set how chunk heads that cannot be right are treated, for salvaging
+    /// chunks from partially corrupt files; see `RecoveryPolicy`.  Strict by
+    /// default.
+    pub fn set_recovery_policy(&mut self, recovery_policy: RecoveryPolicy) {
+        self.recovery_policy = recovery_policy;
+    }
+
+    /// Byte ranges skipped while resynchronizing past bad chunk heads, as
+    /// offsets from the start of the RIFF header, in the order found.  Each
+    /// range is reported once, however many walks pass over it.
+    pub fn skipped_regions(&self) -> &[std::ops::Range<u64>] {
+        &self.skipped_regions
+    }
+
+    /// Position of the start of the RIFF header in the underlying cursor
+    pub fn wave_start(&self) -> u64 {
+        self.wave_start
+    }
+
+    /// Position just past the end of the WAVE, as declared by the RIFF header
+    pub fn wave_end(&self) -> u64 {
+        self.wave_end
+    }
+
+    /// Position of the first chunk head, immediately following the "WAVE" id
+    pub fn first_chunk_pos(&self) -> u64 {
+        self.first_chunk_pos
+    }
+
+    /// Length in bytes of the whole WAVE, including the RIFF header
+    pub fn wave_len(&self) -> u64 {
+        self.wave_end - self.wave_start
+    }
+
+    /// Number of bytes between the cursor and the end of the WAVE, 0 if the
+    /// cursor is past the end
+    ///
+    /// Errors if underlying cursor errors
+    pub fn remaining(&mut self) -> Result<u64, Error> {
+        let position = self.base_cursor.stream_position()?;
+        Ok(self.wave_end.saturating_sub(position))
+    }
+
+    /// Whether the cursor has reached the end of the WAVE.  Bytes remaining
+    /// after the last chunk that are too few for a chunk head are a sign that
+    /// the RIFF size disagrees with the chunk sizes.
+    ///
+    /// Errors if underlying cursor errors
+    pub fn at_end(&mut self) -> Result<bool, Error> {
+        Ok(self.remaining()? == 0)
+    }
+
+    /// Reset reader to initial state for reading chunks
+    pub fn reset(&mut self) -> Result<(), Error> {
+        self.last_chunk_pos = None;
+        self.base_cursor
+            .seek(SeekFrom::Start(self.first_chunk_pos))
+            .map(|_| ())
+            .map_err(Error::Io)
+    }
+
+    /// Walk every chunk head, recording the position of each chunk's payload
+    /// in the underlying cursor along with its head, for random access with
+    /// `seek_to_chunk`.  The reader is reset before and after the walk.
+    ///
+    /// Errors if underlying cursor errors
+    pub fn chunk_index(&mut self) -> Result<Vec<(u64, ChunkHead)>, Error> {
+        self.reset()?;
+        let mut index = vec![];
+
+        while let Some(head) = self.seek_next_chunk(|_| true)? {
+            index.push((self.base_cursor.stream_position()?, head));
+            self.skip_chunk_body(&head)?;
+        }
+
+        self.reset()?;
+        Ok(index)
+    }
+
+    /// Jump to the chunk whose payload starts at `payload_offset`, as recorded
+    /// by `chunk_index`, so the next `read_next_chunk` reads that chunk.  The
+    /// chunk head is parsed again and returned, and the cursor is left at the
+    /// start of the head.
+    ///
+    /// Errors if the offset leaves no room for a chunk head within the WAVE,
+    /// or if underlying cursor errors
+    pub fn seek_to_chunk(
+        &mut self,
+        payload_offset: u64,
+    ) -> Result<ChunkHead, Error> {
+        let head_offset = payload_offset
+            .checked_sub(CHUNK_HEAD_SZ as u64)
+            .filter(|&offset| {
+                offset >= self.first_chunk_pos
+                    && payload_offset <= self.wave_end
+            })
+            .ok_or(Error::wave(format!(
+                "Chunk payload offset {} outside of WAVE",
+                payload_offset
+            )))?;
+
+        self.base_cursor.seek(SeekFrom::Start(head_offset))?;
+        let head =
+            ChunkHead::parse_with(&mut self.base_cursor, self.endianness)?;
+        self.base_cursor.seek(SeekFrom::Start(head_offset))?;
+        Ok(head)
+    }
+
+    /// Seek back to the head of the chunk most recently read, e.g. by
+    /// `read_next_chunk`, so the next read returns it again.  Methods that
+    /// reset the reader, such as `summary`, forget the chunk.
+    ///
+    /// Returns whether there was a chunk to rewind to.  Errors if underlying
+    /// cursor errors
+    pub fn rewind_chunk(&mut self) -> Result<bool, Error> {
+        match self.last_chunk_pos {
+            Some(chunk_pos) => {
+                self.base_cursor.seek(SeekFrom::Start(chunk_pos))?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Seek cursor to its position before being wrapped, and return the cursor
+    ///
+    /// Errors if underlying cursor errors
+    pub fn restore_cursor(mut self) -> Result<Cursor, Error> {
+        self.base_cursor.seek(SeekFrom::Start(self.wave_start))?;
+        Ok(self.base_cursor)
+    }
+
+    /// Consume the reader and wrap the WAVE immediately following this one's
+    /// end, for streams containing several concatenated WAVEs.  Returns `None`
+    /// if the stream ends at this WAVE's end.
+    ///
+    /// Errors if underlying cursor errors or the bytes following this WAVE are
+    /// not a valid WAVE
+    pub fn next_wave(mut self) -> Result<Option<Self>, Error> {
+        let stream_end = self.base_cursor.seek(SeekFrom::End(0))?;
+
+        if stream_end <= self.wave_end {
+            return Ok(None);
+        }
+
+        self.base_cursor.seek(SeekFrom::Start(self.wave_end))?;
+        let max_chunk_size = self.max_chunk_size;
+        let recovery_policy = self.recovery_policy;
+        let mut next = Self::new(self.base_cursor)?;
+        next.max_chunk_size = max_chunk_size;
+        next.recovery_policy = recovery_policy;
+        Ok(Some(next))
+    }
+
+    /// Read the very next chunk in the stream.  If a tag is provided, find the
+    /// next chunk with the matching tag.
+    ///
+    /// Errors if chunk is larger than `max_chunk_size` or if underlying cursor
+    /// errors
+    pub fn read_next_chunk(
+        &mut self,
+        tag: Option<[u8; 4]>,
+    ) -> Result<Option<ChunkDefinition>, Error> {
+        let chunk_head = match self
+            .seek_next_chunk(|head| tag.is_none() || Some(head.tag) == tag)?
+        {
+            Some(chunk_head) => chunk_head,
+            None => return Ok(None),
+        };
+
+        let buffer = self.read_chunk_body(&chunk_head)?;
+        Ok(Some((chunk_head.tag, buffer)))
+    }
+
+    /// Read the next chunk as with `read_next_chunk`, into a buffer that is
+    /// cleared first, so a loop over many chunks can reuse one allocation.
+    /// Returns the chunk's head rather than its tag.
+    ///
+    /// Errors if chunk is larger than `max_chunk_size` or if underlying cursor
+    /// errors
+    pub fn read_next_chunk_into(
+        &mut self,
+        tag: Option<[u8; 4]>,
+        buffer: &mut Vec<u8>,
+    ) -> Result<Option<ChunkHead>, Error> {
+        let chunk_head = match self
+            .seek_next_chunk(|head| tag.is_none() || Some(head.tag) == tag)?
+        {
+            Some(chunk_head) => chunk_head,
+            None => return Ok(None),
+        };
+
+        self.read_chunk_body_into(&chunk_head, buffer)?;
+        Ok(Some(chunk_head))
+    }
+
+    /// Read the next chunk whose tag is one of `tags`, seeking past the bodies
+    /// of all others without reading them, e.g. to read metadata without
+    /// buffering the "data" chunk.
+    ///
+    /// Errors if chunk is larger than `max_chunk_size` or if underlying cursor
+    /// errors
+    pub fn read_next_chunk_in(
+        &mut self,
+        tags: &[[u8; 4]],
+    ) -> Result<Option<ChunkDefinition>, Error> {
+        let chunk_head =
+            match self.seek_next_chunk(|head| tags.contains(&head.tag))? {
+                Some(chunk_head) => chunk_head,
+                None => return Ok(None),
+            };
+
+        let buffer = self.read_chunk_body(&chunk_head)?;
+        Ok(Some((chunk_head.tag, buffer)))
+    }
+
+    /// Read the next chunk as with `read_next_chunk`, along with the pad byte
+    /// following an odd-sized body.  The spec calls for a pad byte of 0, but
+    /// some files use other values; together with
+    /// `ChunkWriter::append_chunk_with_pad` this allows bit-exact round trips.
+    /// The pad is `None` for even-sized bodies, or if the stream ends before
+    /// the pad byte.  `ChunkWriter` edits that keep a chunk in place, such as
+    /// `remove_chunk`, always keep its pad byte verbatim.
+    ///
+    /// Errors if chunk is larger than `max_chunk_size` or if underlying cursor
+    /// errors
+    pub fn read_next_chunk_with_pad(
+        &mut self,
+        tag: Option<[u8; 4]>,
+    ) -> Result<Option<(ChunkDefinition, Option<u8>)>, Error> {
+        let chunk_head = match self
+            .seek_next_chunk(|head| tag.is_none() || Some(head.tag) == tag)?
+        {
+            Some(chunk_head) => chunk_head,
+            None => return Ok(None),
+        };
+
+        let buffer = self.read_chunk_body(&chunk_head)?;
+        let mut pad = None;
+
+        if needs_pad(chunk_head.size.into()) {
+            let mut pad_byte = [0u8];
+            let pad_end = self.base_cursor.stream_position()?;
+            self.base_cursor.seek(SeekFrom::Current(-1))?;
+
+            pad = match self.base_cursor.read_exact(&mut pad_byte) {
+                Ok(()) => Some(pad_byte[0]),
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => None,
+                Err(err) => return Err(err.into()),
+            };
+
+            self.base_cursor.seek(SeekFrom::Start(pad_end))?;
+        }
+
+        Ok(Some(((chunk_head.tag, buffer), pad)))
+    }
+
+    /// Stream the cue points of the first "cue " chunk, reading and parsing
+    /// one 24-byte entry at a time rather than buffering the whole chunk.  As
+    /// with `parse_cue_points`, the number of cue points is taken from the
+    /// chunk size rather than the declared count.  The reader is reset before
+    /// streaming, and left partway through the cue chunk once the iterator is
+    /// dropped; call `reset` to read chunks again from the start.
+    ///
+    /// Yields nothing if there is no cue chunk.  Yields an error, then ends,
+    /// if underlying cursor errors
+    pub fn cue_points_iter(
+        &mut self,
+    ) -> impl Iterator<Item = Result<CuePoint, Error>> + '_ {
+        let (mut remaining, mut error) = match self.seek_cue_entries() {
+            Ok(count) => (count, None),
+            Err(err) => (0, Some(err)),
+        };
+
+        std::iter::from_fn(move || {
+            if let Some(err) = error.take() {
+                return Some(Err(err));
+            }
+
+            if remaining == 0 {
+                return None;
+            }
+
+            remaining -= 1;
+            let mut entry = [0u8; CUE_SZ];
+
+            match self.base_cursor.read_exact(&mut entry) {
+                Ok(()) => {
+                    Some(Ok(CuePoint::parse_with(&entry, self.endianness)))
+                }
+                Err(err) => {
+                    remaining = 0;
+                    Some(Err(err.into()))
+                }
+            }
+        })
+    }
+
+    // Leave the cursor at the first entry of the first "cue " chunk, and
+    // return the number of whole entries following
+    fn seek_cue_entries(&mut self) -> Result<u32, Error> {
+        self.reset()?;
+
+        match self.seek_next_chunk(|head| head.tag == tag::CUE)? {
+            Some(head) if head.size >= 4 => {
+                self.base_cursor.seek(SeekFrom::Current(4))?;
+                Ok((head.size - 4) / CUE_SZ as u32)
+            }
+            _ => Ok(0),
+        }
+    }
+
+    /// Count the cue points in the first "cue " chunk, reading only the
+    /// chunk's declared count rather than every cue point.  Returns 0 if there
+    /// is no cue chunk.  The reader is reset before and after counting.
+    ///
+    /// Errors if underlying cursor errors
+    pub fn cue_count(&mut self) -> Result<u32, Error> {
+        self.reset()?;
+        let mut count = 0;
+
+        if let Some(head) = self.seek_next_chunk(|head| head.tag == tag::CUE)? {
+            if head.size >= 4 {
+                let mut count_bytes = [0u8; 4];
+                self.base_cursor.read_exact(&mut count_bytes)?;
+                count = self.endianness.read_u32(count_bytes);
+            }
+        }
+
+        self.reset()?;
+        Ok(count)
+    }
+
+    /// Count the "ltxt" and "labl" sub-chunks across all "adtl" LIST chunks,
+    /// reading only sub-chunk headers.  The reader is reset before and after
+    /// counting.
+    ///
+    /// Errors if underlying cursor errors
+    pub fn label_count(&mut self) -> Result<usize, Error> {
+        self.reset()?;
+        let mut count = 0;
+
+        while let Some(head) =
+            self.seek_next_chunk(|head| head.tag == tag::LIST)?
+        {
+            let list_start = self.base_cursor.stream_position()?;
+            let list_end = list_start + u64::from(head.size);
+            let mut list_type = [0u8; 4];
+
+            if head.size >= 4 {
+                self.base_cursor.read_exact(&mut list_type)?;
+            }
+
+            if list_type == tag::ADTL {
+                while self.base_cursor.stream_position()? + CHUNK_HEAD_SZ as u64
+                    <= list_end
+                {
+                    let sub_head = ChunkHead::parse_with(
+                        &mut self.base_cursor,
+                        self.endianness,
+                    )?;
+
+                    if sub_head.tag == tag::LTXT || sub_head.tag == *b"labl" {
+                        count += 1;
+                    }
+
+                    let padded_size = u64::from(sub_head.size)
+                        + pad_len(sub_head.size.into());
+                    self.base_cursor
+                        .seek(SeekFrom::Current(padded_size as i64))?;
+                }
+            }
+
+            let padded_end = list_end + pad_len(head.size.into());
+            self.base_cursor.seek(SeekFrom::Start(padded_end))?;
+        }
+
+        self.reset()?;
+        Ok(count)
+    }
+
+    /// Walk every chunk head, tallying up tags and sizes without reading any
+    /// chunk bodies.  The reader is reset before and after the walk.
+    ///
+    /// Errors if underlying cursor errors
+    pub fn summary(&mut self) -> Result<ChunkSummary, Error> {
+        self.reset()?;
+        let mut summary = ChunkSummary::default();
+
+        while let Some(head) = self.seek_next_chunk(|_| true)? {
+            let (count, size) = summary.tags.entry(head.tag).or_default();
+            *count += 1;
+            *size += u64::from(head.size);
+            summary.order.push(head.tag);
+            self.skip_chunk_body(&head)?;
+        }
+
+        self.reset()?;
+        Ok(summary)
+    }
+
+    /// Walk every chunk once, handing each chunk head to `handler` along with
+    /// the underlying cursor positioned at the start of the chunk body.  The
+    /// handler may read as much of the body as it likes, and decides with the
+    /// returned `ChunkAction` whether to keep bytes, skip ahead, or stop.  The
+    /// walk continues from the end of the chunk no matter where the handler
+    /// leaves the cursor.  The reader is reset before and after the walk.
+    ///
+    /// Returns the tag and bytes of every `ChunkAction::Read`, in chunk order.
+    /// Errors if underlying cursor errors or the handler errors
+    pub fn visit(
+        &mut self,
+        handler: &mut dyn FnMut(
+            ChunkHead,
+            &mut Cursor,
+        ) -> Result<ChunkAction, Error>,
+    ) -> Result<Vec<ChunkDefinition>, Error> {
+        self.reset()?;
+        let mut kept = vec![];
+
+        while let Some(head) = self.seek_next_chunk(|_| true)? {
+            let body_start = self.base_cursor.stream_position()?;
+
+            match handler(head, &mut self.base_cursor)? {
+                ChunkAction::Read(bytes) => kept.push((head.tag, bytes)),
+                ChunkAction::Skip => {}
+                ChunkAction::Stop => break,
+            }
+
+            self.base_cursor.seek(SeekFrom::Start(body_start))?;
+            self.skip_chunk_body(&head)?;
+        }
+
+        self.reset()?;
+        Ok(kept)
+    }
+
+    /// Read the tag and body of every chunk not tagged one of `skip`, in chunk
+    /// order, seeking past the skipped chunks without reading them.  Skipping
+    /// "data" collects the metadata of a WAVE without ever holding its
+    /// samples in memory.  The reader is reset before and after reading.
+    ///
+    /// Errors if a chunk read is larger than `max_chunk_size` or if
+    /// underlying cursor errors
+    pub fn read_all_except(
+        &mut self,
+        skip: &[[u8; 4]],
+    ) -> Result<Vec<ChunkDefinition>, Error> {
+        self.reset()?;
+        let mut chunks = vec![];
+
+        while let Some(head) = self.seek_next_chunk(|_| true)? {
+            if skip.contains(&head.tag) {
+                self.skip_chunk_body(&head)?;
+            } else {
+                chunks.push((head.tag, self.read_chunk_body(&head)?));
+            }
+        }
+
+        self.reset()?;
+        Ok(chunks)
+    }
+
+    /// Stream the body of the first "data" chunk through a hasher in blocks,
+    /// without buffering the whole body.  The reader is reset before and
+    /// after hashing.
+    ///
+    /// Errors if underlying cursor errors or there is no "data" chunk
+    pub fn hash_data<H: std::hash::Hasher>(
+        &mut self,
+        hasher: &mut H,
+    ) -> Result<(), Error> {
+        self.reset()?;
+
+        let head = self
+            .seek_next_chunk(|head| head.tag == tag::DATA)?
+            .ok_or(Error::wave("No \"data\" chunk"))?;
+
+        let mut remaining = rf64::chunk_body_size(&head, self.ds64.as_ref());
+        let mut buffer = vec![0u8; 0x10000.min(remaining) as usize];
+
+        while remaining > 0 {
+            let block_len = remaining.min(buffer.len() as u64) as usize;
+            self.base_cursor.read_exact(&mut buffer[..block_len])?;
+            hasher.write(&buffer[..block_len]);
+            remaining -= block_len as u64;
+        }
+
+        self.reset()
+    }
+
+    /// Read the cue points of the first "cue " chunk, each joined with the
+    /// label and labeled-text sharing its ID from any "adtl" LIST.  Names
+    /// are taken from labels in preference to labeled-texts; see
+    /// `Marker::join_with_labels`.  The reader is reset before and after
+    /// reading.
+    ///
+    /// Labeled-text is cut to `DEFAULT_MAX_TEXT_LEN` bytes without warning,
+    /// as by `extract_labeled_text_from_list`; text from labels is kept whole.
+    ///
+    /// Errors if underlying cursor errors
+    pub fn read_markers(&mut self) -> Result<Vec<Marker>, Error> {
+        let (cues, labels, labeled_texts) = self.read_cues_and_labels()?;
+
+        Ok(cues
+            .into_iter()
+            .map(|cue| Marker::join_with_labels(cue, &labels, &labeled_texts))
+            .collect())
+    }
+
+    /// Read the body of the first LIST chunk with list type "adtl", wherever
+    /// it lies in the WAVE.  LISTs of other types, such as "INFO", are
+    /// skipped.  The reader is reset before and after reading.
+    ///
+    /// Errors if chunk is larger than `max_chunk_size` or if underlying cursor
+    /// errors
+    pub fn find_adtl_list(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        self.reset()?;
+        let mut adtl_list = None;
+
+        while let Some(head) = self.seek_next_chunk(|h| h.tag == tag::LIST)? {
+            let body = self.read_chunk_body(&head)?;
+
+            if body.starts_with(&tag::ADTL) {
+                adtl_list = Some(body);
+                break;
+            }
+        }
+
+        self.reset()?;
+        Ok(adtl_list)
+    }
+
+    /// Read the bodies of every LIST chunk with list type "adtl", in file
+    /// order.  Files may split their labels across several such LISTs; each
+    /// body can be passed to `extract_labeled_text_from_list` to find which
+    /// LIST a labeled-text came from.  The reader is reset before and after
+    /// reading.
+    ///
+    /// Errors if chunk is larger than `max_chunk_size` or if underlying cursor
+    /// errors
+    pub fn find_adtl_lists(&mut self) -> Result<Vec<Vec<u8>>, Error> {
+        self.reset()?;
+        let mut adtl_lists = vec![];
+
+        while let Some(head) = self.seek_next_chunk(|h| h.tag == tag::LIST)? {
+            let body = self.read_chunk_body(&head)?;
+
+            if body.starts_with(&tag::ADTL) {
+                adtl_lists.push(body);
+            }
+        }
+
+        self.reset()?;
+        Ok(adtl_lists)
+    }
+
+    /// Read the labeled-texts of every "adtl" LIST, concatenated in file
+    /// order.  See `find_adtl_lists`.  The reader is reset before and after
+    /// reading.
+    ///
+    /// Text is cut to `DEFAULT_MAX_TEXT_LEN` bytes without warning.  To
+    /// choose the maximum or learn which texts were cut, pass each body from
+    /// `find_adtl_lists` to `extract_labeled_text_from_list_with_max`.
+    ///
+    /// Errors if chunk is larger than `max_chunk_size` or if underlying cursor
+    /// errors
+    pub fn read_labeled_texts(&mut self) -> Result<Vec<LabeledText>, Error> {
+        Ok(self
+            .find_adtl_lists()?
+            .iter()
+            .flat_map(|list| {
+                extract_labeled_texts_with(
+                    list,
+                    DEFAULT_MAX_TEXT_LEN,
+                    self.endianness,
+                )
+                .0
+            })
+            .collect())
+    }
+
+    /// Read the regions formed by cue points of the first "cue " chunk and
+    /// labeled-texts with a non-zero sample length from any "adtl" LIST, in
+    /// cue point order.  See `Region`.  The reader is reset before and after
+    /// reading.
+    ///
+    /// Errors if underlying cursor errors
+    pub fn read_regions(&mut self) -> Result<Vec<Region>, Error> {
+        let (cues, _, labeled_texts) = self.read_cues_and_labels()?;
+
+        Ok(cues
+            .iter()
+            .filter_map(|cue| Region::join(cue, &labeled_texts))
+            .collect())
+    }
+
+    /// Read the sample rate from the first "fmt " chunk, without parsing the
+    /// rest of the chunk.  The reader is reset before and after reading.
+    ///
+    /// Errors if there is no "fmt " chunk, if it is too short, or if
+    /// underlying cursor errors
+    pub fn sample_rate(&mut self) -> Result<u32, Error> {
+        let endianness = self.endianness;
+        self.read_fmt_field::<4>(4)
+            .map(|field| endianness.read_u32(field))
+    }
+
+    /// Read the number of channels from the first "fmt " chunk, without
+    /// parsing the rest of the chunk.  The reader is reset before and after
+    /// reading.
+    ///
+    /// Errors if there is no "fmt " chunk, if it is too short, or if
+    /// underlying cursor errors
+    pub fn channels(&mut self) -> Result<u16, Error> {
+        let endianness = self.endianness;
+        self.read_fmt_field::<2>(2)
+            .map(|field| endianness.read_u16(field))
+    }
+
+    // Read N bytes at offset into the body of the first "fmt " chunk
+    fn read_fmt_field<const N: usize>(
+        &mut self,
+        offset: u32,
+    ) -> Result<[u8; N], Error> {
+        self.reset()?;
+        let head = self
+            .seek_next_chunk(|head| head.tag == tag::FMT)?
+            .ok_or(Error::wave("No \"fmt \" chunk"))?;
+
+        if (head.size as usize) < crate::format::FORMAT_MIN_SZ {
+            self.reset()?;
+            return Err(Error::wave("fmt chunk too short"));
+        }
+
+        let mut field = [0u8; N];
+        self.base_cursor.seek(SeekFrom::Current(offset.into()))?;
+        self.base_cursor.read_exact(&mut field)?;
+        self.reset()?;
+        Ok(field)
+    }
+
+    /// Read the time reference from the first "bext" chunk of a Broadcast
+    /// WAVE, the sample frame since midnight at which the recording starts,
+    /// without parsing the rest of the chunk.  See `CuePoint::absolute_sample`.
+    /// The reader is reset before and after reading.
+    ///
+    /// Returns `None` if there is no "bext" chunk.  Errors if the chunk is too
+    /// short to hold the time reference or if underlying cursor errors
+    pub fn read_bext_time_reference(&mut self) -> Result<Option<u64>, Error> {
+        self.reset()?;
+
+        let Some(head) = self.seek_next_chunk(|head| head.tag == *b"bext")?
+        else {
+            self.reset()?;
+            return Ok(None);
+        };
+
+        if head.size < BEXT_TIME_REFERENCE_POS + 8 {
+            self.reset()?;
+            return Err(Error::wave("bext chunk too short for time reference"));
+        }
+
+        let mut field = [0u8; 8];
+        self.base_cursor
+            .seek(SeekFrom::Current(BEXT_TIME_REFERENCE_POS.into()))?;
+        self.base_cursor.read_exact(&mut field)?;
+        self.reset()?;
+
+        let [low, high] = [0, 4].map(|i| {
+            u64::from(self.endianness.read_u32([
+                field[i],
+                field[i + 1],
+                field[i + 2],
+                field[i + 3],
+            ]))
+        });
+        Ok(Some(high << 32 | low))
+    }
+
+    /// Read the raw ID3v2 tag from the first "id3 " or "ID3 " chunk, without
+    /// parsing its frames.  The reader is reset before and after reading.
+    ///
+    /// Errors if chunk is too large or if underlying cursor errors
+    pub fn read_id3(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        self.reset()?;
+        let id3 =
+            match self.seek_next_chunk(|head| ID3_TAGS.contains(&head.tag))? {
+                Some(chunk_head) => Some(self.read_chunk_body(&chunk_head)?),
+                None => None,
+            };
+        self.reset()?;
+        Ok(id3)
+    }
+
+    /// Cross-check the cue points and labels against each other and against
+    /// the length of the "data" chunk, reporting any structural problems
+    /// found.  Cue points are checked against the first "cue " chunk, and
+    /// labels from every "adtl" LIST.  The reader is reset before and after
+    /// checking.
+    ///
+    /// Errors if underlying cursor errors
+    pub fn verify(&mut self) -> Result<Vec<Warning>, Error> {
+        self.reset()?;
+
+        let mut block_align = None;
+        let mut data_size = None;
+        let mut cue_chunk = None;
+        let mut lists = vec![];
+
+        while let Some(head) = self.seek_next_chunk(|_| true)? {
+            match head.tag {
+                tag::FMT if block_align.is_none() => {
+                    let body = self.read_chunk_body(&head)?;
+
+                    // Read in place, in the file's byte order, so short or
+                    // unusual format chunks still give the block alignment
+                    block_align = body
+                        .get(12..14)
+                        .map(|b| self.endianness.read_u16([b[0], b[1]]));
+                }
+                tag::DATA if data_size.is_none() => {
+                    data_size =
+                        Some(rf64::chunk_body_size(&head, self.ds64.as_ref()));
+                    self.skip_chunk_body(&head)?;
+                }
+                tag::CUE if cue_chunk.is_none() => {
+                    cue_chunk = Some(self.read_chunk_body(&head)?);
+                }
+                tag::LIST => lists.push(self.read_chunk_body(&head)?),
+                _ => self.skip_chunk_body(&head)?,
+            }
+        }
+
+        self.reset()?;
+
+        let mut warnings = vec![];
+        let cues = match cue_chunk.as_deref().map(|cue_chunk| {
+            try_parse_cue_points_with(
+                cue_chunk,
+                CueCountPolicy::Lenient,
+                self.endianness,
+            )
+        }) {
+            Some(Ok((cues, warning))) => {
+                warnings.extend(warning);
+                cues
+            }
+            _ => vec![],
+        };
+
+        let mut cue_ids = std::collections::BTreeSet::new();
+        let mut duplicate_ids = std::collections::BTreeSet::new();
+
+        for cue in &cues {
+            if !cue_ids.insert(cue.id) && duplicate_ids.insert(cue.id) {
+                warnings.push(Warning::DuplicateCueId { cue_id: cue.id });
+            }
+        }
+
+        if let (Some(block_align @ 1..), Some(data_size)) =
+            (block_align, data_size)
+        {
+            let sample_count = data_size / u64::from(block_align);
+
+            // A cue point beyond the data has an offset above the sample
+            // count, so the count fits in 32 bits
+            for cue in &cues {
+                if u64::from(cue.sample_offset) > sample_count {
+                    warnings.push(Warning::CueBeyondData {
+                        cue_id: cue.id,
+                        sample_offset: cue.sample_offset,
+                        sample_count: sample_count as u32,
+                    });
+                }
+            }
+        }
+
+        for list in lists.iter().filter(|list| list.starts_with(&tag::ADTL)) {
+            for (head, body) in adtl_sub_chunks_with(list, self.endianness) {
+                if head.tag != tag::LTXT && head.tag != *b"labl" {
+                    continue;
+                }
+
+                if let Some(id_bytes) = body.first_chunk::<4>() {
+                    let cue_id = self.endianness.read_u32(*id_bytes);
+
+                    if !cue_ids.contains(&cue_id) {
+                        warnings.push(Warning::OrphanLabel {
+                            tag: head.tag,
+                            cue_id,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(warnings)
+    }
+
+    // Cue points from the first cue chunk and labeled-texts from every adtl
+    // list, resetting before and after
+    fn read_cues_and_labels(&mut self) -> Result<CuesAndLabels, Error> {
+        self.reset()?;
+
+        let mut cues = None;
+        let mut labels = vec![];
+        let mut labeled_texts = vec![];
+
+        while let Some(head) = self.seek_next_chunk(|head| {
+            head.tag == tag::LIST || (head.tag == tag::CUE && cues.is_none())
+        })? {
+            let body = self.read_chunk_body(&head)?;
+
+            if head.tag == tag::CUE {
+                cues = Some(parse_cue_points_with(&body, self.endianness));
+            } else if body.starts_with(&tag::ADTL) {
+                labels.append(&mut extract_labels_with(&body, self.endianness));
+                labeled_texts.append(
+                    &mut extract_labeled_texts_with(
+                        &body,
+                        DEFAULT_MAX_TEXT_LEN,
+                        self.endianness,
+                    )
+                    .0,
+                );
+            }
+        }
+
+        self.reset()?;
+        Ok((cues.unwrap_or_default(), labels, labeled_texts))
+    }
+
+    // Walk forward to the next chunk whose head satisfies `matches`, skipping
+    // any others, and leave the cursor at the start of its payload
+    fn seek_next_chunk(
+        &mut self,
+        matches: impl Fn(&ChunkHead) -> bool,
+    ) -> Result<Option<ChunkHead>, Error> {
+        while self.base_cursor.stream_position()? < self.wave_end {
+            let chunk_pos = self.base_cursor.stream_position()?;
+            let chunk_head =
+                ChunkHead::parse_with(&mut self.base_cursor, self.endianness)?;
+
+            if self.recovery_policy == RecoveryPolicy::Resync
+                && !self.is_plausible_head(chunk_pos, &chunk_head)
+            {
+                let resume_pos = self.resync(chunk_pos + 1)?;
+                let skipped = (chunk_pos - self.wave_start)
+                    ..(resume_pos - self.wave_start);
+
+                if !self.skipped_regions.contains(&skipped) {
+                    self.skipped_regions.push(skipped);
+                }
+
+                self.base_cursor.seek(SeekFrom::Start(resume_pos))?;
+                continue;
+            }
+
+            if matches(&chunk_head) {
+                self.last_chunk_pos = Some(chunk_pos);
+                return Ok(Some(chunk_head));
+            }
+
+            self.skip_chunk_body(&chunk_head)?;
+        }
+
+        Ok(None)
+    }
+
+    // Whether a head read at chunk_pos has a printable tag and a body ending
+    // within the WAVE
+    fn is_plausible_head(&self, chunk_pos: u64, head: &ChunkHead) -> bool {
+        is_printable_tag(&head.tag)
+            && chunk_pos + CHUNK_HEAD_SZ as u64 + u64::from(head.size)
+                <= self.wave_end
+    }
+
+    // Find the first position from start holding a plausible chunk head, or
+    // the end of the WAVE if there is none.  The WAVE is scanned a block at a
+    // time, each block overlapping the last by all but one byte of a head so
+    // heads straddling two blocks are found.
+    fn resync(&mut self, start: u64) -> Result<u64, Error> {
+        const BLOCK_SZ: u64 = 0x10000;
+        let mut buffer = Vec::new();
+        let mut pos = start;
+
+        while pos + CHUNK_HEAD_SZ as u64 <= self.wave_end {
+            self.base_cursor.seek(SeekFrom::Start(pos))?;
+            buffer.clear();
+            (&mut self.base_cursor)
+                .take(BLOCK_SZ.min(self.wave_end - pos))
+                .read_to_end(&mut buffer)?;
+
+            if buffer.len() < CHUNK_HEAD_SZ {
+                break;
+            }
+
+            for (offset, mut head_bytes) in
+                buffer.windows(CHUNK_HEAD_SZ).enumerate()
+            {
+                let head_pos = pos + offset as u64;
+                let head =
+                    ChunkHead::parse_with(&mut head_bytes, self.endianness)?;
+
+                if self.is_plausible_head(head_pos, &head) {
+                    return Ok(head_pos);
+                }
+            }
+
+            pos += (buffer.len() - CHUNK_HEAD_SZ + 1) as u64;
+        }
+
+        Ok(self.wave_end)
+    }
+
+    // With the cursor at the start of a payload, read it and skip any padding
+    fn read_chunk_body(
+        &mut self,
+        chunk_head: &ChunkHead,
+    ) -> Result<Vec<u8>, Error> {
+        let mut buffer = vec![];
+        self.read_chunk_body_into(chunk_head, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    // As `read_chunk_body`, replacing the contents of the buffer
+    fn read_chunk_body_into(
+        &mut self,
+        chunk_head: &ChunkHead,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        let size = chunk_head.size();
+
+        if size > self.max_chunk_size {
+            return Err(Error::wave(format!(
+                "Chunk size {} exceeds maximum of {}",
+                size, self.max_chunk_size
+            )));
+        }
+
+        let len = usize::try_from(size).map_err(|_| {
+            Error::wave(format!("Chunk size {} too large for platform", size))
+        })?;
+
+        buffer.clear();
+        buffer.resize(len, 0u8);
+        self.base_cursor.read_exact(&mut buffer[..])?;
+
+        if needs_pad(chunk_head.size.into()) {
+            self.base_cursor.seek(SeekFrom::Current(1))?;
+        }
+
+        Ok(())
+    }
+
+    // With the cursor at the start of a payload, seek past it and any padding
+    fn skip_chunk_body(&mut self, chunk_head: &ChunkHead) -> Result<(), Error> {
+        let size = rf64::chunk_body_size(chunk_head, self.ds64.as_ref());
+        let skip = size + pad_len(size);
+        self.base_cursor.seek(SeekFrom::Current(
+            i64::try_from(skip).map_err(|_| Error::ChunkTooBig {
+                needed: skip,
+                limit: i64::MAX as u64,
+            })?,
+        ))?;
+
+        Ok(())
+    }
+
+    /// The "ds64" chunk of RF64 and BW64 files, whose 64-bit sizes stand in
+    /// for RIFF and "data" chunk sizes of `u32::MAX`.  `None` for RIFF files.
+    pub fn ds64(&self) -> Option<&Ds64Chunk> {
+        self.ds64.as_ref()
+    }
+}
+
+impl<Bytes: AsRef<[u8]>> ChunkReader<io::Cursor<Bytes>> {
+    /// Wraps bytes of a WAV file already in memory in a reader, for reading
+    /// chunks without copying them with `read_next_chunk_ref`.  Memory-mapped
+    /// files (such as those from the memmap2 crate) may be wrapped this way to
+    /// read chunks of very large files without buffering.
+    ///
+    /// Errors if bytes are not a valid WAV
+    pub fn from_bytes(bytes: Bytes) -> Result<Self, Error> {
+        Self::new(io::Cursor::new(bytes))
+    }
+
+    /// Read the very next chunk like `read_next_chunk`, but borrow the
+    /// payload from the underlying bytes rather than copying it.
+    ///
+    /// Errors if the chunk runs past the end of the underlying bytes
+    pub fn read_next_chunk_ref(
+        &mut self,
+        tag: Option<[u8; 4]>,
+    ) -> Result<Option<(ChunkHead, &[u8])>, Error> {
+        let chunk_head = match self
+            .seek_next_chunk(|head| tag.is_none() || Some(head.tag) == tag)?
+        {
+            Some(chunk_head) => chunk_head,
+            None => return Ok(None),
+        };
+
+        let start = self.base_cursor.position();
+        let end = start + u64::from(chunk_head.size);
+        self.base_cursor
+            .set_position(end + pad_len(chunk_head.size.into()));
+
+        let payload = usize::try_from(start)
+            .ok()
+            .zip(usize::try_from(end).ok())
+            .and_then(|(start, end)| {
+                self.base_cursor.get_ref().as_ref().get(start..end)
+            })
+            .ok_or(Error::Io(io::ErrorKind::UnexpectedEof.into()))?;
+
+        Ok(Some((chunk_head, payload)))
+    }
+}
+
+impl ChunkReader<io::BufReader<std::fs::File>> {
+    /// Open the WAV file at `path` for buffered reading
+    ///
+    /// Errors if the file cannot be opened or is not a valid WAV
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self, Error> {
+        Self::new(io::BufReader::new(std::fs::File::open(path)?))
+    }
+}
+
+/// Wraps a cursor for writing chunks to an existing WAV
+#[derive(Debug, PartialEq, Eq)]
+pub struct ChunkWriter<Cursor: Read + Write + Seek> {
+    form_id: [u8; 4],
+    base_cursor: Cursor,
+    wave_start: u64,
+    wave_end: u64,
+    allow_empty: bool,
+    sort_cue_points: bool,
+    endianness: Endianness,
+}
+
+impl<Cursor: Read + Write + Seek> ChunkWriter<Cursor> {
+    /// Wraps cursor pointing to beginning of WAV file in a writer
+    ///
+    /// Errors if underlying cursor errors or not a valid WAV
+    pub fn new(cursor: Cursor) -> Result<Self, Error> {
+        Self::new_form(cursor, Some(*b"WAVE"))
+    }
+
+    /// Wraps cursor pointing to beginning of any RIFF file in a writer.  If a
+    /// form id is expected, the RIFF file must have that form id.
+    ///
+    /// Errors if underlying cursor errors or not a valid RIFF of the expected
+    /// form
+    pub fn new_form(
+        mut cursor: Cursor,
+        expected_form: Option<[u8; 4]>,
+    ) -> Result<Self, Error> {
+        let wave_start = cursor.stream_position()?;
+        let (head, form_id, _) = read_riff_head(&mut cursor, expected_form)?;
+        let (riff_size, _) = rf64::riff_body_size(&mut cursor, &head)?;
+        let wave_end = wave_start
+            .checked_add(CHUNK_HEAD_SZ.try_into().unwrap())
+            .and_then(|sz| sz.checked_add(riff_size))
+            .ok_or(Error::wave("WAVE size too large for file"))?;
+
+        Ok(Self {
+            form_id,
             base_cursor: cursor,
             wave_start,
             wave_end,
+            allow_empty: false,
+            sort_cue_points: false,
+            endianness: Endianness::of_riff_tag(&head.tag),
         })
     }
 
-    /// Seek cursor to its position before being wrapped, and return the cursor
+    /// Whether appending an empty list of cue points or labeled-texts writes
+    /// an empty chunk (`true`) or leaves the file untouched (`false`, the
+    /// default)
+    pub fn set_allow_empty(&mut self, allow_empty: bool) {
+        self.allow_empty = allow_empty;
+    }
+
+    /// Whether cue points are sorted by sample offset before being written
+    /// (`true`) or written in the order given (`false`, the default).  Some
+    /// strict readers reject cue chunks that are out of order, and hosts that
+    /// list markers in chunk order, such as Sound Forge and Adobe Audition,
+    /// show them shuffled.  Samplers that map cue points to slices in chunk
+    /// order, as when importing into Kontakt or an Akai MPC, assign the
+    /// slices out of sequence.  Sorting is stable, so cue points sharing an
+    /// offset keep their order.
+    pub fn set_sort_cue_points(&mut self, sort_cue_points: bool) {
+        self.sort_cue_points = sort_cue_points;
+    }
+
+    // Cue points in the order they are written, per `set_sort_cue_points`
+    fn cue_points_to_write(&self, cues: &[CuePoint]) -> Vec<CuePoint> {
+        let mut cues = cues.to_vec();
+
+        if self.sort_cue_points {
+            cues.sort_by_key(|cue| cue.sample_offset);
+        }
+
+        cues
+    }
+
+    /// Form id following the RIFF header, "WAVE" for WAV files
+    pub fn form_id(&self) -> [u8; 4] {
+        self.form_id
+    }
+
+    /// Byte order of the file, which appended chunk headers, cue points, and
+    /// labels follow
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+
+    /// Seek cursor to its position before being wrapped, and return the cursor
+    ///
+    /// Errors if underlying cursor errors
+    pub fn restore_cursor(mut self) -> Result<Cursor, Error> {
+        self.base_cursor.seek(SeekFrom::Start(self.wave_start))?;
+        Ok(self.base_cursor)
+    }
+
+    // Body of a chunk at the position with the head, refusing bodies larger
+    // than `DEFAULT_MAX_CHUNK_SIZE` as `ChunkReader::read_chunk_body` does
+    pub(crate) fn read_body_at(
+        &mut self,
+        (offset, head): &(u64, ChunkHead),
+    ) -> Result<Vec<u8>, Error> {
+        if head.size > DEFAULT_MAX_CHUNK_SIZE {
+            return Err(Error::wave(format!(
+                "Chunk size {} exceeds maximum of {}",
+                head.size, DEFAULT_MAX_CHUNK_SIZE
+            )));
+        }
+
+        let mut body = vec![0u8; head.size as usize];
+        self.base_cursor
+            .seek(SeekFrom::Start(offset + CHUNK_HEAD_SZ as u64))?;
+        self.base_cursor.read_exact(&mut body)?;
+        Ok(body)
+    }
+
+    // Position and head of every chunk, in file order.  The walk steps over
+    // "data" chunks by their 64-bit size in RF64 files.
+    fn chunk_positions(&mut self) -> Result<Vec<(u64, ChunkHead)>, Error> {
+        let cursor = &mut self.base_cursor;
+        cursor.seek(SeekFrom::Start(self.wave_start))?;
+        let riff_head = read_riff_head(cursor, Some(self.form_id))?.0;
+        let (riff_size, ds64) = rf64::riff_body_size(cursor, &riff_head)?;
+        let mut position = cursor.stream_position()?;
+        let wave_end = position - 4 + riff_size;
+        let mut chunks = vec![];
+
+        while let Some(chunk) =
+            find_chunk(cursor, position, wave_end, self.endianness, |_| true)?
+        {
+            let size = rf64::chunk_body_size(&chunk.1, ds64.as_ref());
+            position = chunk.0 + CHUNK_HEAD_SZ as u64 + size + pad_len(size);
+            chunks.push(chunk);
+        }
+
+        Ok(chunks)
+    }
+
+    /// Append a chunk with the given tag and body to the WAV file while
+    /// updating WAV size in header.  A pad byte is written after odd-sized
+    /// bodies.
     ///
-    /// Errors if underlying cursor errors
-    pub fn restore_cursor(mut self) -> Result<Cursor, Error> {
-        self.base_cursor.seek(SeekFrom::Start(self.wave_start))?;
-        Ok(self.base_cursor)
+    /// If writing fails partway, the bytes written over, including the sizes
+    /// in the header, are written back before the error is returned, so the
+    /// stream is as before up to its old length and `restore_cursor` can be
+    /// called as usual.  A stream that grew is not shortened; for a WAV that
+    /// ended the stream, `truncate_to_wave_end` cuts off what was written.
+    /// The same holds for every other append and `grow_data_chunk`.
+    ///
+    /// A WAV growing past 4 GiB is upgraded to RF64, with its "JUNK" chunk
+    /// turned into a "ds64" chunk holding the 64-bit sizes.  The "JUNK" chunk
+    /// must be the first chunk and at least `DS64_MIN_SZ` bytes, as writers
+    /// that may exceed 4 GiB reserve for the purpose.
+    ///
+    /// Errors if underlying cursor errors or resulting sizes cannot be
+    /// recorded
+    pub fn append_chunk(
+        &mut self,
+        tag: [u8; 4],
+        payload: &[u8],
+    ) -> Result<(), Error> {
+        self.append_chunks(&[(tag, payload, 0)])
     }
 
-    /// Append list of cue points to WAV file while updating WAV size in header.
+    /// Append a chunk as with `append_chunk`, writing `pad` as the pad byte
+    /// after an odd-sized body rather than 0, e.g. to reproduce a non-zero pad
+    /// byte read by `ChunkReader::read_next_chunk_with_pad`.
     ///
     /// Errors if underlying cursor errors or resulting chunk is too large
-    pub fn append_cue_chunk(&mut self, cues: &[CuePoint]) -> Result<(), Error> {
+    pub fn append_chunk_with_pad(
+        &mut self,
+        tag: [u8; 4],
+        payload: &[u8],
+        pad: u8,
+    ) -> Result<(), Error> {
+        self.append_chunks(&[(tag, payload, pad)])
+    }
+
+    // Append every chunk, then update the RIFF size once they are all written.
+    // Bytes overwritten past the end of the WAVE and the size fields are
+    // saved first and written back on failure.
+    fn append_chunks(
+        &mut self,
+        chunks: &[([u8; 4], &[u8], u8)],
+    ) -> Result<(), Error> {
+        let cursor = &mut self.base_cursor;
+        cursor.seek(SeekFrom::Start(self.wave_start))?;
+        let riff_head = read_riff_head(cursor, Some(self.form_id))?.0;
+        let (old_size, _) = rf64::riff_body_size(cursor, &riff_head)?;
+        let chunks_end = cursor.stream_position()? - 4 + old_size;
+
+        let heads = chunks
+            .iter()
+            .map(|(tag, payload, _)| {
+                size_u32(payload.len() as u64)
+                    .map(|size| ChunkHead { tag: *tag, size })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Every chunk fits a 32-bit size, so each aligned length too.  Even
+        // RF64 sizes must leave every position in the WAVE seekable.
+        let new_size = chunks
+            .iter()
+            .filter_map(|(_, payload, _)| aligned_chunk_len(payload.len()))
+            .fold(old_size, u64::saturating_add);
+        let limit = i64::MAX as u64 - self.wave_start - CHUNK_HEAD_SZ as u64;
+
+        if new_size > limit {
+            return Err(Error::ChunkTooBig {
+                needed: new_size,
+                limit,
+            });
+        }
+
+        let size_plan = self.plan_sizes(new_size, None)?;
+        let mut snapshot = guard::Snapshot::default();
+        snapshot.save(
+            &mut self.base_cursor,
+            chunks_end,
+            new_size - old_size,
+        )?;
+        self.save_size_fields(&mut snapshot, &size_plan)?;
+
+        self.guarded(snapshot, |writer| {
+            let cursor = &mut writer.base_cursor;
+
+            // Skip the chunks following the form id; for a WAVE without
+            // chunks, a RIFF size of 4, this is right after the form id
+            cursor.seek(SeekFrom::Start(chunks_end))?;
+
+            for (chunk_head, (_, payload, pad)) in heads.iter().zip(chunks) {
+                cursor
+                    .write_all(&chunk_head.as_bytes_with(writer.endianness))?;
+                cursor.write_all(payload)?;
+
+                if needs_pad(chunk_head.size.into()) {
+                    cursor.write_all(&[*pad])?;
+                }
+            }
+
+            writer.record_sizes(size_plan, new_size, None)
+        })
+    }
+
+    /// Insert a chunk with the given tag and body immediately before the first
+    /// chunk tagged `target` (typically "data"), shifting the following
+    /// chunks forward and updating WAV size in header.  A pad byte is written
+    /// after odd-sized bodies.
+    ///
+    /// Errors if underlying cursor errors, no chunk is tagged `target`, or
+    /// resulting WAV is too large
+    pub fn insert_chunk_before(
+        &mut self,
+        target: [u8; 4],
+        tag: [u8; 4],
+        payload: &[u8],
+    ) -> Result<(), Error> {
         let cursor = &mut self.base_cursor;
         cursor.seek(SeekFrom::Start(self.wave_start))?;
-        let old_size = read_riff_head(cursor)?.size;
-        let riff_sz_position = cursor.stream_position()? - 8;
-
-        let chunk_size = cues
-            .len()
-            .checked_mul(CUE_SZ)
-            .and_then(|sz| sz.checked_add(4))
-            .and_then(|sz| u32::try_from(sz).ok())
-            .ok_or(Error::wave(CHUNK_TOO_BIG))?;
-
-        let new_size = chunk_size
-            .checked_add(CHUNK_HEAD_SZ as u32)
-            .and_then(|sz| sz.checked_add(old_size))
-            .ok_or(Error::wave(CHUNK_TOO_BIG))?;
-
-        cursor.seek(SeekFrom::Start(riff_sz_position))?;
+        let (riff_head, ..) = read_riff_head(cursor, Some(self.form_id))?;
+        let old_size = non_rf64_size(&riff_head)?;
+        let first_chunk_pos = cursor.stream_position()?;
+        let old_end = first_chunk_pos - 4 + u64::from(old_size);
+
+        let (insert_pos, _) = find_chunk(
+            cursor,
+            first_chunk_pos,
+            old_end,
+            Endianness::Little,
+            |h| h.tag == target,
+        )?
+        .ok_or(Error::wave(format!(
+            "No chunk tagged {:?} found",
+            String::from_utf8_lossy(&target)
+        )))?;
+
+        let chunk_size = size_u32(payload.len() as u64)?;
+        let grow = CHUNK_HEAD_SZ as u64
+            + ChunkHead {
+                tag,
+                size: chunk_size,
+            }
+            .padded_size();
+
+        let new_size = size_u32(u64::from(old_size) + grow)?;
+
+        move_bytes(
+            cursor,
+            insert_pos,
+            insert_pos + grow,
+            old_end - insert_pos,
+        )?;
+
+        cursor.seek(SeekFrom::Start(self.wave_start + 4))?;
         cursor.write_all(&new_size.to_le_bytes()[..])?;
-        cursor.seek(SeekFrom::Current(old_size.into()))?;
 
         let chunk_head = ChunkHead {
-            tag: *b"cue ",
+            tag,
             size: chunk_size,
         };
 
+        cursor.seek(SeekFrom::Start(insert_pos))?;
         cursor.write_all(&chunk_head.as_bytes()[..])?;
-        cursor.write_all(&(cues.len() as u32).to_le_bytes()[..])?;
+        cursor.write_all(payload)?;
 
-        for cue in cues {
-            cursor.write_all(&cue.as_bytes()[..])?;
+        if needs_pad(chunk_size.into()) {
+            cursor.write_all(&[0])?;
         }
 
         Ok(())
     }
 
+    /// Overwrite the tag of the first chunk tagged `from` with `to`, e.g. to
+    /// repair a "CUE " chunk to "cue ".  Sizes are unchanged, so nothing else
+    /// in the file moves.
+    ///
+    /// Returns whether a chunk was retagged.  Errors if `to` is not printable
+    /// ASCII or if underlying cursor errors
+    pub fn retag_chunk(
+        &mut self,
+        from: [u8; 4],
+        to: [u8; 4],
+    ) -> Result<bool, Error> {
+        let chunks = self.chunk_positions()?;
+
+        match chunks.iter().find(|(_, head)| head.tag == from) {
+            Some((offset, head)) => {
+                let new_head = ChunkHead::try_new(to, head.size)?;
+                self.base_cursor.seek(SeekFrom::Start(*offset))?;
+                self.base_cursor.write_all(&new_head.tag)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Add `extra_bytes` to the size of the "data" chunk, after samples have
+    /// been written directly following its existing samples (over any pad
+    /// byte), and update WAV size in header to match.  A pad byte is written
+    /// if the new size is odd.  Sizes past 4 GiB upgrade the file to RF64, as
+    /// for `append_chunk`.
+    ///
+    /// Errors if there is no "data" chunk, if it is not the last chunk, if
+    /// resulting sizes cannot be recorded, or if underlying cursor errors
+    pub fn grow_data_chunk(&mut self, extra_bytes: u32) -> Result<(), Error> {
+        let chunks = self.chunk_positions()?;
+
+        let (offset, head) = match chunks.last() {
+            Some((offset, head)) if head.tag == tag::DATA => (*offset, *head),
+            _ if chunks.iter().any(|(_, head)| head.tag == tag::DATA) => {
+                return Err(Error::wave("\"data\" chunk is not the last chunk"))
+            }
+            _ => return Err(Error::wave("No \"data\" chunk")),
+        };
+
+        let old_size = match self.read_ds64()? {
+            Some(ds64) if head.size == u32::MAX => ds64.data_size,
+            _ => head.size.into(),
+        };
+        let new_size = old_size + u64::from(extra_bytes);
+        let body_start = offset + CHUNK_HEAD_SZ as u64;
+        let riff_size = body_start + new_size + pad_len(new_size)
+            - self.wave_start
+            - CHUNK_HEAD_SZ as u64;
+
+        let size_plan = self.plan_sizes(riff_size, Some(new_size))?;
+        let new_head = ChunkHead {
+            size: u32::try_from(new_size).unwrap_or(u32::MAX),
+            ..head
+        };
+
+        let mut snapshot = guard::Snapshot::default();
+        snapshot.save(&mut self.base_cursor, body_start + new_size, 1)?;
+        snapshot.save(&mut self.base_cursor, offset, CHUNK_HEAD_SZ as u64)?;
+        self.save_size_fields(&mut snapshot, &size_plan)?;
+
+        self.guarded(snapshot, |writer| {
+            let cursor = &mut writer.base_cursor;
+
+            if needs_pad(new_size) {
+                cursor.seek(SeekFrom::Start(body_start + new_size))?;
+                cursor.write_all(&[0u8])?;
+            }
+
+            cursor.seek(SeekFrom::Start(offset))?;
+            cursor.write_all(&new_head.as_bytes_with(writer.endianness))?;
+
+            writer.record_sizes(size_plan, riff_size, Some(new_size))
+        })
+    }
+
+    /// Append DISP chunk while updating WAV size in header.
+    ///
+    /// Errors if underlying cursor errors or resulting chunk is too large
+    pub fn append_disp_chunk(&mut self, disp: &DispChunk) -> Result<(), Error> {
+        self.append_chunk(*b"DISP", &disp.as_bytes())
+    }
+
+    /// Append acid chunk while updating WAV size in header.
+    ///
+    /// Errors if underlying cursor errors or the tempo is not positive
+    pub fn append_acid_chunk(&mut self, acid: &AcidChunk) -> Result<(), Error> {
+        acid.validate()?;
+        self.append_chunk(*b"acid", &acid.as_bytes())
+    }
+
+    /// Append smpl chunk while updating WAV size in header.
+    ///
+    /// Errors if underlying cursor errors or resulting chunk is too large
+    pub fn append_sampler_chunk(
+        &mut self,
+        sampler: &SamplerChunk,
+    ) -> Result<(), Error> {
+        self.append_chunk(*b"smpl", &sampler.as_bytes())
+    }
+
+    /// Append cart chunk while updating WAV size in header.
+    ///
+    /// Errors if underlying cursor errors or resulting chunk is too large
+    pub fn append_cart_chunk(&mut self, cart: &CartChunk) -> Result<(), Error> {
+        self.append_chunk(*b"cart", &cart.as_bytes())
+    }
+
+    /// Append list of cue points to WAV file while updating WAV size in header,
+    /// sorted if enabled with `set_sort_cue_points`.  Nothing is written when
+    /// `cues` is empty, unless empty chunks are allowed with
+    /// `set_allow_empty`.
+    ///
+    /// Errors if underlying cursor errors or resulting chunk is too large
+    pub fn append_cue_chunk(&mut self, cues: &[CuePoint]) -> Result<(), Error> {
+        if cues.is_empty() && !self.allow_empty {
+            return Ok(());
+        }
+
+        let (tag, payload) =
+            cue_chunk_with(&self.cue_points_to_write(cues), self.endianness)?;
+        self.append_chunk(tag, &payload)
+    }
+
+    /// Append a "cue " chunk and an "adtl" LIST chunk for the markers while
+    /// updating WAV size in header, with cue points sorted if enabled with
+    /// `set_sort_cue_points`.  Both chunks are built before anything is
+    /// written, and the WAV size is only updated once both are written, so a
+    /// failure leaves the WAVE unchanged.  Names are written as labels, and
+    /// labeled-texts are only written for markers with a region length or
+    /// purpose; see `Marker::split_with_label`.  The LIST chunk is left out if
+    /// no marker has a label, region length, or purpose, and nothing is
+    /// written when `markers` is empty, unless empty chunks are allowed with
+    /// `set_allow_empty`.
+    ///
+    /// Errors if two markers share a cue ID, if underlying cursor errors, or
+    /// if resulting chunks are too large
+    pub fn append_markers(&mut self, markers: &[Marker]) -> Result<(), Error> {
+        let mut ids = std::collections::BTreeSet::new();
+
+        if let Some(marker) = markers.iter().find(|m| !ids.insert(m.cue.id)) {
+            return Err(Error::wave(format!(
+                "Duplicate marker cue ID {}",
+                marker.cue.id
+            )));
+        }
+
+        let mut cues = vec![];
+        let mut sub_chunks = vec![];
+
+        for marker in markers {
+            let (cue, labl, ltxt) = marker.split_with_label();
+            cues.push(cue);
+            sub_chunks.extend(
+                labl.map(|labl| {
+                    (*b"labl", labl.as_bytes_with(self.endianness))
+                }),
+            );
+            sub_chunks.extend(
+                ltxt.map(|ltxt| {
+                    (tag::LTXT, ltxt.as_bytes_with(self.endianness))
+                }),
+            );
+        }
+
+        let mut chunks = vec![];
+
+        if !cues.is_empty() || self.allow_empty {
+            chunks.push(cue_chunk_with(
+                &self.cue_points_to_write(&cues),
+                self.endianness,
+            )?);
+        }
+
+        if !sub_chunks.is_empty() || self.allow_empty {
+            chunks.push(adtl_chunk(sub_chunks.into_iter(), self.endianness)?);
+        }
+
+        let chunks = chunks
+            .iter()
+            .map(|(tag, payload)| (*tag, &payload[..], 0))
+            .collect::<Vec<_>>();
+
+        self.append_chunks(&chunks)
+    }
+
+    /// Append LIST chunk containing list of labels while updating WAV size in
+    /// header.  Nothing is written when `labels` is empty, unless empty chunks
+    /// are allowed with `set_allow_empty`.
+    ///
+    /// Errors if underlying cursor errors or resulting chunk is too large
+    pub fn append_labl_chunk(&mut self, labels: &[Label]) -> Result<(), Error> {
+        if labels.is_empty() && !self.allow_empty {
+            return Ok(());
+        }
+
+        let (tag, payload) = labl_chunk_with(labels, self.endianness)?;
+        self.append_chunk(tag, &payload)
+    }
+
+    /// Append markers built from sample offsets and names, with cue IDs
+    /// assigned sequentially from 1 in the order given, as with
+    /// `append_markers`.  Empty names leave their marker unlabeled.
+    ///
+    /// Errors if underlying cursor errors or resulting chunks are too large
+    pub fn append_named_markers(
+        &mut self,
+        markers: &[(u32, String)],
+    ) -> Result<(), Error> {
+        let markers = markers
+            .iter()
+            .zip(1..)
+            .map(|((sample, name), id)| Marker {
+                cue: CuePoint::from_sample_offset(id, *sample),
+                label: Some(name.clone()).filter(|name| !name.is_empty()),
+                region_length: None,
+                purpose_id: None,
+            })
+            .collect::<Vec<_>>();
+
+        self.append_markers(&markers)
+    }
+
     /// Append LIST chunk containing list of labeled-text while updating WAV
-    /// size in header.
+    /// size in header.  Nothing is written when `labeled_texts` is empty,
+    /// unless empty chunks are allowed with `set_allow_empty`.
     ///
     /// Errors if underlying cursor errors or resulting chunk is too large
     pub fn append_label_chunk(
         &mut self,
         labeled_texts: &[LabeledText],
     ) -> Result<(), Error> {
+        if labeled_texts.is_empty() && !self.allow_empty {
+            return Ok(());
+        }
+
+        let (tag, payload) = label_chunk_with(labeled_texts, self.endianness)?;
+        self.append_chunk(tag, &payload)
+    }
+}
+
+impl ChunkWriter<std::fs::File> {
+    /// Open the existing WAV file at `path` for reading and writing
+    ///
+    /// Errors if the file cannot be opened or is not a valid WAV
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self, Error> {
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)?;
+        Self::new(file)
+    }
+}
+
+impl<Cursor: Read + Write + Seek + Truncate> ChunkWriter<Cursor> {
+    /// Remove every chunk whose tag is not in `keep` while updating WAV size in
+    /// header, in a single pass.  An empty `keep` keeps `ESSENTIAL_CHUNKS` and
+    /// `PRO_TOOLS_CHUNKS`.
+    /// Kept chunks, including their padding, are kept verbatim.  Anything in
+    /// the stream past the end of the WAVE is discarded.
+    ///
+    /// Returns the number of chunks removed.  Errors if underlying cursor
+    /// errors
+    pub fn strip_metadata(&mut self, keep: &[[u8; 4]]) -> Result<usize, Error> {
+        let default_keep =
+            [&ESSENTIAL_CHUNKS[..], &PRO_TOOLS_CHUNKS[..]].concat();
+        let keep = if keep.is_empty() {
+            &default_keep[..]
+        } else {
+            keep
+        };
+
+        let chunks = self.chunk_positions()?;
+        let pieces = chunks
+            .iter()
+            .filter(|(_, head)| keep.contains(&head.tag))
+            .map(Piece::existing)
+            .collect::<Vec<_>>();
+        let removed = chunks.len() - pieces.len();
+
+        if removed > 0 {
+            self.rebuild(pieces)?;
+        }
+
+        Ok(removed)
+    }
+
+    /// Remove the first chunk with the given tag while updating WAV size in
+    /// header.  Every other chunk, including its padding, is kept verbatim.
+    /// Anything in the stream past the end of the WAVE is discarded.
+    ///
+    /// Returns whether a chunk was removed.  Errors if underlying cursor
+    /// errors
+    pub fn remove_chunk(&mut self, tag: [u8; 4]) -> Result<bool, Error> {
+        let chunks = self.chunk_positions()?;
+        let target = chunks.iter().position(|(_, head)| head.tag == tag);
+
+        if let Some(target) = target {
+            let pieces = chunks
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != target)
+                .map(|(_, chunk)| Piece::existing(chunk))
+                .collect();
+
+            self.rebuild(pieces)?;
+        }
+
+        Ok(target.is_some())
+    }
+
+    /// Replace the body of the first chunk with the given tag while updating
+    /// WAV size in header.  Every other chunk, including its padding, is kept
+    /// verbatim.  Anything in the stream past the end of the WAVE is
+    /// discarded.
+    ///
+    /// Returns whether a chunk was replaced.  Errors if underlying cursor
+    /// errors or resulting WAV is too large
+    pub fn replace_chunk(
+        &mut self,
+        tag: [u8; 4],
+        payload: &[u8],
+    ) -> Result<bool, Error> {
+        let chunks = self.chunk_positions()?;
+        let target = chunks.iter().position(|(_, head)| head.tag == tag);
+
+        if let Some(target) = target {
+            let pieces = chunks
+                .iter()
+                .enumerate()
+                .map(|(i, chunk)| {
+                    if i == target {
+                        Piece::new_chunk(tag, payload)
+                    } else {
+                        Ok(Piece::existing(chunk))
+                    }
+                })
+                .collect::<Result<_, _>>()?;
+
+            self.rebuild(pieces)?;
+        }
+
+        Ok(target.is_some())
+    }
+
+    /// Merge the cue points of every "cue " chunk into the first one and
+    /// remove the rest, while updating WAV size in header.  Cue points keep
+    /// their order, and any whose ID is already taken by an earlier cue point
+    /// is given the first free ID past the largest in use, wrapping around
+    /// past `u32::MAX`.  Every other chunk, including its padding, is kept
+    /// verbatim.  Anything in the stream past the end of the WAVE is
+    /// discarded.
+    ///
+    /// Returns the number of chunks merged into the first and removed.
+    /// Errors if underlying cursor errors, a cue chunk is larger than
+    /// `DEFAULT_MAX_CHUNK_SIZE`, or resulting WAV is too large
+    pub fn coalesce_cue_chunks(&mut self) -> Result<usize, Error> {
+        let chunks = self.chunk_positions()?;
+        let cue_chunks = chunks
+            .iter()
+            .filter(|(_, head)| head.tag == tag::CUE)
+            .collect::<Vec<_>>();
+
+        if cue_chunks.len() < 2 {
+            return Ok(0);
+        }
+
+        let first_offset = cue_chunks[0].0;
+
+        let mut cues = vec![];
+
+        for chunk in &cue_chunks {
+            let body = self.read_body_at(chunk)?;
+            cues.append(&mut parse_cue_points_with(&body, self.endianness));
+        }
+
+        let mut ids = std::collections::BTreeSet::new();
+        let mut next_id = cues.iter().map(|cue| cue.id).max().unwrap_or(0);
+        let mut taken = cues.iter().map(|cue| cue.id).collect();
+
+        for cue in &mut cues {
+            if !ids.insert(cue.id) {
+                next_id = free_cue_id(&taken, next_id)?;
+                taken.insert(next_id);
+                cue.id = next_id;
+                ids.insert(next_id);
+            }
+        }
+
+        let (tag, payload) = cue_chunk(&cues)?;
+        let pieces = chunks
+            .iter()
+            .filter(|(offset, head)| head.tag != tag || *offset == first_offset)
+            .map(|chunk| {
+                if chunk.0 == first_offset {
+                    Piece::new_chunk(tag, &payload)
+                } else {
+                    Ok(Piece::existing(chunk))
+                }
+            })
+            .collect::<Result<_, _>>()?;
+
+        self.rebuild(pieces)?;
+        Ok(cue_chunks.len() - 1)
+    }
+
+    /// Replace every "cue " chunk with a single one holding exactly `cues`,
+    /// while updating WAV size in header once.  The new chunk takes the place
+    /// of the first cue chunk, and is appended if there is none.  A single
+    /// cue chunk of the same size is overwritten in place, without moving any
+    /// other chunk.  Otherwise every other chunk, including its padding, is
+    /// kept verbatim, and anything in the stream past the end of the WAVE is
+    /// discarded.  Labels are kept as they are.  Cue points are sorted first
+    /// if set with `set_sort_cue_points`.  Without any cue points, every cue
+    /// chunk is removed instead, unless empty chunks are allowed with
+    /// `set_allow_empty`.
+    ///
+    /// Errors if underlying cursor errors, resulting WAV is too large, or
+    /// cue chunks must be moved in an RF64 or RIFX file
+    pub fn set_cue_points(&mut self, cues: &[CuePoint]) -> Result<(), Error> {
+        let chunks = self.chunk_positions()?;
+        let (tag, payload) =
+            cue_chunk_with(&self.cue_points_to_write(cues), self.endianness)?;
+        let cue_chunks = chunks
+            .iter()
+            .filter(|(_, head)| head.tag == tag)
+            .collect::<Vec<_>>();
+
+        if cues.is_empty() && !self.allow_empty {
+            if cue_chunks.is_empty() {
+                return Ok(());
+            }
+
+            let pieces = chunks
+                .iter()
+                .filter(|(_, head)| head.tag != tag)
+                .map(Piece::existing)
+                .collect();
+            return self.rebuild(pieces);
+        }
+
+        match cue_chunks[..] {
+            [] => return self.append_chunk(tag, &payload),
+            [(offset, head)] if head.size as usize == payload.len() => {
+                self.base_cursor
+                    .seek(SeekFrom::Start(offset + CHUNK_HEAD_SZ as u64))?;
+                self.base_cursor.write_all(&payload)?;
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        let first_offset = cue_chunks[0].0;
+        let pieces = chunks
+            .iter()
+            .filter(|(offset, head)| head.tag != tag || *offset == first_offset)
+            .map(|chunk| {
+                if chunk.0 == first_offset {
+                    Piece::new_chunk(tag, &payload)
+                } else {
+                    Ok(Piece::existing(chunk))
+                }
+            })
+            .collect::<Result<_, _>>()?;
+
+        self.rebuild(pieces)
+    }
+
+    /// Replace the body of the first "id3 " or "ID3 " chunk with a raw ID3v2
+    /// tag, keeping its spelling, or append an "id3 " chunk if there is none,
+    /// while updating WAV size in header.
+    ///
+    /// Errors if underlying cursor errors or resulting WAV is too large
+    pub fn write_id3(&mut self, tag: &[u8]) -> Result<(), Error> {
+        let chunks = self.chunk_positions()?;
+
+        match chunks.iter().find(|(_, head)| ID3_TAGS.contains(&head.tag)) {
+            Some((_, head)) => {
+                self.replace_chunk(head.tag, tag)?;
+                Ok(())
+            }
+            None => self.append_chunk(ID3_TAGS[0], tag),
+        }
+    }
+
+    /// Cut off anything in the stream past the end of the WAVE given by the
+    /// RIFF size in the header, such as bytes left by a download of the wrong
+    /// length or by concatenating files.  Streams ending before the end of
+    /// the WAVE are left as they are.
+    ///
+    /// Returns the number of bytes removed.  Errors if underlying cursor
+    /// errors
+    pub fn truncate_to_wave_end(&mut self) -> Result<u64, Error> {
         let cursor = &mut self.base_cursor;
         cursor.seek(SeekFrom::Start(self.wave_start))?;
-        let old_size = read_riff_head(cursor)?.size;
-        let riff_sz_position = cursor.stream_position()? - 8;
+        let riff_head = read_riff_head(cursor, Some(self.form_id))?.0;
+        let (riff_size, _) = rf64::riff_body_size(cursor, &riff_head)?;
+
+        self.wave_end = self
+            .wave_start
+            .saturating_add(CHUNK_HEAD_SZ as u64)
+            .saturating_add(riff_size);
+        let stream_end = cursor.seek(SeekFrom::End(0))?;
+
+        if stream_end <= self.wave_end {
+            return Ok(0);
+        }
+
+        cursor.truncate(self.wave_end)?;
+        Ok(stream_end - self.wave_end)
+    }
+
+    /// Start a batch of edits, applied together in a single rebuild when
+    /// committed
+    pub fn edit(&mut self) -> Editor<'_, Cursor> {
+        Editor {
+            writer: self,
+            edits: vec![],
+            error: None,
+        }
+    }
+
+    // Lay out the pieces one after another following the WAVE id, then update
+    // the RIFF size and cut off anything left past the new end
+    fn rebuild(&mut self, pieces: Vec<Piece>) -> Result<(), Error> {
+        self.base_cursor.seek(SeekFrom::Start(self.wave_start))?;
+        non_rf64_size(&ChunkHead::parse(&mut self.base_cursor)?)?;
+        let first_chunk_pos = self.wave_start + CHUNK_HEAD_SZ as u64 + 4;
+
+        let riff_size = size_u32(
+            pieces
+                .iter()
+                .fold(4u64, |sz, piece| sz.saturating_add(piece.len())),
+        )?;
 
-        let chunk_size = labeled_texts
+        let destinations = pieces
             .iter()
-            .map(|ltxt| {
-                pad_size_16(ltxt.text.len())
-                    .and_then(|sz| sz.checked_add(LABELED_TEXT_MIN_SZ))
+            .scan(first_chunk_pos, |position, piece| {
+                let destination = *position;
+                *position += piece.len();
+                Some(destination)
             })
-            .try_fold(0usize, |accum, element| {
-                element
-                    .and_then(|sz| sz.checked_add(accum))
-                    .and_then(|sum| sum.checked_add(CHUNK_HEAD_SZ))
+            .collect::<Vec<_>>();
+
+        let moves = pieces
+            .iter()
+            .zip(&destinations)
+            .filter_map(|(piece, &to)| match piece {
+                Piece::Existing { offset, len } => Some((*offset, to, *len)),
+                Piece::New(_) => None,
             })
-            .and_then(|sz| sz.checked_add(4usize))
-            .and_then(|sz| u32::try_from(sz).ok())
-            .ok_or(Error::wave(CHUNK_TOO_BIG))?;
+            .collect::<Vec<_>>();
 
-        let new_size = chunk_size
-            .checked_add(CHUNK_HEAD_SZ as u32)
-            .and_then(|sz| sz.checked_add(old_size))
-            .ok_or(Error::wave(CHUNK_TOO_BIG))?;
+        // Pieces keep their relative order, so moving backward pieces front
+        // to back, then forward pieces back to front, never overwrites bytes
+        // that have yet to be moved
+        for &(from, to, len) in moves.iter().filter(|(from, to, _)| to < from) {
+            move_bytes(&mut self.base_cursor, from, to, len)?;
+        }
 
-        cursor.seek(SeekFrom::Start(riff_sz_position))?;
-        cursor.write_all(&new_size.to_le_bytes()[..])?;
-        cursor.seek(SeekFrom::Current(old_size.into()))?;
+        for &(from, to, len) in
+            moves.iter().rev().filter(|(from, to, _)| to > from)
+        {
+            move_bytes(&mut self.base_cursor, from, to, len)?;
+        }
 
-        let chunk_head = ChunkHead {
-            tag: *b"LIST",
-            size: chunk_size,
-        };
+        for (piece, &to) in pieces.iter().zip(&destinations) {
+            if let Piece::New(bytes) = piece {
+                self.base_cursor.seek(SeekFrom::Start(to))?;
+                self.base_cursor.write_all(bytes)?;
+            }
+        }
 
-        cursor.write_all(&chunk_head.as_bytes()[..])?;
-        cursor.write_all(b"adtl")?;
+        self.base_cursor
+            .seek(SeekFrom::Start(self.wave_start + 4))?;
+        self.base_cursor.write_all(&riff_size.to_le_bytes())?;
 
-        for ltxt in labeled_texts {
-            let sub_chunk_sz =
-                u32::try_from(ltxt.text.len() + LABELED_TEXT_MIN_SZ).unwrap();
+        self.wave_end =
+            self.wave_start + CHUNK_HEAD_SZ as u64 + u64::from(riff_size);
+        self.base_cursor.truncate(self.wave_end)?;
 
-            let sub_chunk_head = ChunkHead {
-                tag: *b"ltxt",
-                size: sub_chunk_sz,
-            };
+        Ok(())
+    }
+}
+
+// An edit planned by an `Editor`
+#[derive(Debug)]
+enum Edit {
+    Append([u8; 4], Vec<u8>),
+    Remove([u8; 4]),
+    Replace([u8; 4], Vec<u8>),
+}
+
+/// Batch of edits to a WAV, made with `ChunkWriter::edit`.  Edits are only
+/// recorded until `commit`, which applies them in order to the list of chunks
+/// and then lays out the result in a single pass with a single update of the
+/// WAV size, rather than shifting bytes and updating the size once per edit.
+#[derive(Debug)]
+pub struct Editor<'a, Cursor: Read + Write + Seek + Truncate> {
+    writer: &'a mut ChunkWriter<Cursor>,
+    edits: Vec<Edit>,
+
+    // First edit that could not be planned, reported by commit
+    error: Option<Error>,
+}
+
+impl<Cursor: Read + Write + Seek + Truncate> Editor<'_, Cursor> {
+    /// Append a chunk with the given tag and body after every other chunk
+    pub fn append_chunk(mut self, tag: [u8; 4], payload: &[u8]) -> Self {
+        self.edits.push(Edit::Append(tag, payload.to_vec()));
+        self
+    }
+
+    /// Append a "cue " chunk, sorted if the writer sorts cue points, and
+    /// skipped when `cues` is empty unless empty chunks are allowed by the
+    /// writer
+    pub fn append_cue_chunk(self, cues: &[CuePoint]) -> Self {
+        let definition = cue_chunk(&self.writer.cue_points_to_write(cues));
+        self.append_definition(definition, cues.is_empty())
+    }
 
-            cursor.write_all(&sub_chunk_head.as_bytes()[..])?;
-            cursor.write_all(&ltxt.as_bytes()[..])?;
+    /// Append an "adtl" LIST chunk, skipped when `labeled_texts` is empty
+    /// unless empty chunks are allowed by the writer
+    pub fn append_label_chunk(self, labeled_texts: &[LabeledText]) -> Self {
+        self.append_definition(
+            label_chunk(labeled_texts),
+            labeled_texts.is_empty(),
+        )
+    }
+
+    /// Remove the first chunk with the given tag, if any, including chunks
+    /// appended earlier in the batch
+    pub fn remove_chunk(mut self, tag: [u8; 4]) -> Self {
+        self.edits.push(Edit::Remove(tag));
+        self
+    }
+
+    /// Replace the body of the first chunk with the given tag, if any,
+    /// including chunks appended earlier in the batch
+    pub fn replace_chunk(mut self, tag: [u8; 4], payload: &[u8]) -> Self {
+        self.edits.push(Edit::Replace(tag, payload.to_vec()));
+        self
+    }
+
+    /// Apply every edit, in order.  Chunks left alone, including their
+    /// padding, are kept verbatim, and anything in the stream past the end of
+    /// the WAVE is discarded.  Nothing is written if any edit is invalid.
+    ///
+    /// Errors if underlying cursor errors or resulting WAV is too large
+    pub fn commit(self) -> Result<(), Error> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
 
-            if sub_chunk_sz & 1 == 1 {
-                cursor.write_all(&[0])?;
+        let mut chunks = self
+            .writer
+            .chunk_positions()?
+            .iter()
+            .map(|chunk| (chunk.1.tag, Piece::existing(chunk)))
+            .collect::<Vec<_>>();
+
+        for edit in self.edits {
+            match edit {
+                Edit::Append(tag, payload) => {
+                    chunks.push((tag, Piece::new_chunk(tag, &payload)?));
+                }
+                Edit::Remove(tag) => {
+                    if let Some(i) = chunks.iter().position(|c| c.0 == tag) {
+                        chunks.remove(i);
+                    }
+                }
+                Edit::Replace(tag, payload) => {
+                    if let Some(chunk) = chunks.iter_mut().find(|c| c.0 == tag)
+                    {
+                        chunk.1 = Piece::new_chunk(tag, &payload)?;
+                    }
+                }
             }
         }
 
-        Ok(())
+        self.writer
+            .rebuild(chunks.into_iter().map(|(_, piece)| piece).collect())
+    }
+
+    fn append_definition(
+        mut self,
+        definition: Result<ChunkDefinition, Error>,
+        is_empty: bool,
+    ) -> Self {
+        if !is_empty || self.writer.allow_empty {
+            match definition {
+                Ok((tag, payload)) => {
+                    self.edits.push(Edit::Append(tag, payload));
+                }
+                Err(err) => {
+                    self.error.get_or_insert(err);
+                }
+            }
+        }
+
+        self
     }
 }
 
@@ -0,0 +1,263 @@
+use crate::{
+    pad_len, parse_cue_points_with, tag, ChunkHead, ChunkReader, CuePoint,
+    Endianness, Error, Label, LabeledText, Marker, CHUNK_HEAD_SZ,
+    DEFAULT_MAX_TEXT_LEN, LABELED_TEXT_MIN_SZ,
+};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{Read, Seek};
+
+// Index of an "adtl" LIST body and byte offset of a sub-chunk within it
+type Position = (usize, usize);
+
+const FIRST_SUB_CHUNK: usize = 4;
+
+/// Iterator over the markers of a WAVE, made by `ChunkReader::markers_iter`.
+/// The "labl" and "ltxt" sub-chunk heads are indexed by cue ID in one walk
+/// when the iterator is made.  Markers are then joined as they are reached
+/// walking the "adtl" LISTs, so only the labels and labeled-texts of markers
+/// taken are parsed, and each marker finds its other label in constant time.
+///
+/// Markers are yielded in the order their first label or labeled-text appears,
+/// followed by the remaining cue points, unlabeled, in cue chunk order.  Each
+/// marker is the same as `ChunkReader::read_markers` would join.  Labels and
+/// labeled-texts too short to parse are yielded as errors in their place, and
+/// the walk continues past them.
+#[derive(Debug, Clone)]
+pub struct MarkerIter {
+    cues: Vec<CuePoint>,
+
+    // Indices into `cues` by cue ID
+    cue_indices: HashMap<u32, Vec<usize>>,
+
+    // IDs of cue points already yielded with their labels
+    joined: HashSet<u32>,
+
+    lists: Vec<Vec<u8>>,
+
+    // Positions of the "labl" and "ltxt" sub-chunks by tag and cue ID, in
+    // list order
+    sub_chunk_index: HashMap<([u8; 4], u32), Vec<Position>>,
+
+    endianness: Endianness,
+    position: Position,
+    pending: VecDeque<Marker>,
+    next_unlabeled: usize,
+}
+
+// Sub-chunk at the position, or the first one of a later list if the rest of
+// the list holds no complete sub-chunk, along with its position and the
+// position following it
+fn sub_chunk_at(
+    lists: &[Vec<u8>],
+    (mut list, mut offset): Position,
+    endianness: Endianness,
+) -> Option<(ChunkHead, &[u8], Position, Position)> {
+    loop {
+        let mut rest = lists.get(list)?.get(offset..).unwrap_or(&[]);
+
+        match ChunkHead::parse_with(&mut rest, endianness) {
+            Ok(head) if head.size as usize <= rest.len() => {
+                let len = head.size as usize;
+                let next = offset
+                    + CHUNK_HEAD_SZ
+                    + len
+                    + pad_len(head.size.into()) as usize;
+
+                return Some((
+                    head,
+                    &rest[..len],
+                    (list, offset),
+                    (list, next),
+                ));
+            }
+            _ => {
+                list += 1;
+                offset = FIRST_SUB_CHUNK;
+            }
+        }
+    }
+}
+
+fn cue_id(body: &[u8], endianness: Endianness) -> Option<u32> {
+    body.first_chunk::<4>()
+        .map(|id_bytes| endianness.read_u32(*id_bytes))
+}
+
+fn parse_labeled_text(
+    body: &[u8],
+    endianness: Endianness,
+) -> Result<LabeledText, Error> {
+    if body.len() < LABELED_TEXT_MIN_SZ {
+        return LabeledText::try_from_bytes_with(body, endianness);
+    }
+
+    Ok(LabeledText::parse(body, DEFAULT_MAX_TEXT_LEN, endianness))
+}
+
+impl MarkerIter {
+    // Find the first sub-chunk with the tag referencing the cue ID, from the
+    // position onward
+    fn find_sub_chunk(
+        &self,
+        position: Position,
+        tag: [u8; 4],
+        id: u32,
+    ) -> Option<&[u8]> {
+        let positions = self.sub_chunk_index.get(&(tag, id))?;
+        let found = positions[positions.partition_point(|p| *p < position)..]
+            .first()?;
+        let (_, body, ..) = sub_chunk_at(&self.lists, *found, self.endianness)?;
+        Some(body)
+    }
+
+    // Queue a marker for every cue point with the ID, joined with the label
+    // or labeled-text just reached and the first of the other kind after it
+    fn join(
+        &mut self,
+        id: u32,
+        label: Option<Label>,
+        labeled_text: Option<LabeledText>,
+    ) {
+        let Some(indices) = self.cue_indices.get(&id) else {
+            return;
+        };
+
+        if !self.joined.insert(id) {
+            return;
+        }
+
+        let label = label.or_else(|| {
+            self.find_sub_chunk(self.position, *b"labl", id)
+                .and_then(|body| {
+                    Label::try_from_bytes_with(body, self.endianness).ok()
+                })
+        });
+        let labeled_text = labeled_text.or_else(|| {
+            self.find_sub_chunk(self.position, tag::LTXT, id)
+                .and_then(|body| parse_labeled_text(body, self.endianness).ok())
+        });
+
+        for &index in indices {
+            self.pending.push_back(Marker::join_with_labels(
+                self.cues[index],
+                label.as_slice(),
+                labeled_text.as_slice(),
+            ));
+        }
+    }
+}
+
+impl Iterator for MarkerIter {
+    type Item = Result<Marker, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            let Some((head, body, _, next)) =
+                sub_chunk_at(&self.lists, self.position, self.endianness)
+            else {
+                break;
+            };
+            self.position = next;
+
+            match &head.tag {
+                &tag::LTXT => match parse_labeled_text(body, self.endianness) {
+                    Ok(ltxt) => self.join(ltxt.cue_id, None, Some(ltxt)),
+                    Err(err) => return Some(Err(err)),
+                },
+                b"labl" => {
+                    match Label::try_from_bytes_with(body, self.endianness) {
+                        Ok(labl) => self.join(labl.cue_id, Some(labl), None),
+                        Err(err) => return Some(Err(err)),
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        if let Some(marker) = self.pending.pop_front() {
+            return Some(Ok(marker));
+        }
+
+        while let Some(&cue) = self.cues.get(self.next_unlabeled) {
+            self.next_unlabeled += 1;
+
+            if !self.joined.contains(&cue.id) {
+                return Some(Ok(Marker::join(cue, &[])));
+            }
+        }
+
+        None
+    }
+}
+
+impl std::iter::FusedIterator for MarkerIter {}
+
+impl<Cursor: Read + Seek> ChunkReader<Cursor> {
+    /// Read the cue points of the first "cue " chunk and the bodies of every
+    /// "adtl" LIST, to be joined into markers lazily as they are taken from
+    /// the returned iterator; see `MarkerIter`.  The reader is reset before
+    /// and after reading.
+    ///
+    /// As with `read_markers`, labeled-text is cut to `DEFAULT_MAX_TEXT_LEN`
+    /// bytes without warning.
+    ///
+    /// Errors if chunk is larger than `max_chunk_size` or if underlying cursor
+    /// errors
+    pub fn markers_iter(&mut self) -> Result<MarkerIter, Error> {
+        self.reset()?;
+
+        let mut cues = None;
+        let mut lists = vec![];
+
+        while let Some(head) = self.seek_next_chunk(|head| {
+            head.tag == tag::LIST || (head.tag == tag::CUE && cues.is_none())
+        })? {
+            let body = self.read_chunk_body(&head)?;
+
+            if head.tag == tag::CUE {
+                cues = Some(parse_cue_points_with(&body, self.endianness));
+            } else if body.starts_with(&tag::ADTL) {
+                lists.push(body);
+            }
+        }
+
+        self.reset()?;
+
+        let cues = cues.unwrap_or_default();
+        let mut cue_indices = HashMap::<u32, Vec<usize>>::new();
+
+        for (index, cue) in cues.iter().enumerate() {
+            cue_indices.entry(cue.id).or_default().push(index);
+        }
+
+        let mut sub_chunk_index = HashMap::<_, Vec<_>>::new();
+        let mut position = (0, FIRST_SUB_CHUNK);
+
+        while let Some((head, body, start, next)) =
+            sub_chunk_at(&lists, position, self.endianness)
+        {
+            if head.tag == tag::LTXT || head.tag == *b"labl" {
+                if let Some(id) = cue_id(body, self.endianness) {
+                    sub_chunk_index
+                        .entry((head.tag, id))
+                        .or_default()
+                        .push(start);
+                }
+            }
+
+            position = next;
+        }
+
+        Ok(MarkerIter {
+            cues,
+            cue_indices,
+            joined: HashSet::new(),
+            lists,
+            sub_chunk_index,
+            endianness: self.endianness,
+            position: (0, FIRST_SUB_CHUNK),
+            pending: VecDeque::new(),
+            next_unlabeled: 0,
+        })
+    }
+}
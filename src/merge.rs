@@ -0,0 +1,94 @@
+use crate::{
+    free_cue_id, non_rf64_size, parse_cue_points_with, tag, ChunkHead,
+    ChunkReader, ChunkWriter, Error, Truncate,
+};
+use std::collections::BTreeSet;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+impl<Cursor: Read + Write + Seek> ChunkWriter<Cursor> {
+    // IDs of the cue points of every "cue " chunk, or `None` if there are no
+    // cue chunks
+    fn cue_ids(&mut self) -> Result<Option<BTreeSet<u32>>, Error> {
+        let mut ids = None;
+
+        for chunk in self.chunk_positions()? {
+            if chunk.1.tag != tag::CUE {
+                continue;
+            }
+
+            let body = self.read_body_at(&chunk)?;
+            ids.get_or_insert_with(BTreeSet::new).extend(
+                parse_cue_points_with(&body, self.endianness)
+                    .iter()
+                    .map(|cue| cue.id),
+            );
+        }
+
+        Ok(ids)
+    }
+}
+
+/// Copy the markers of `source`, as read by `ChunkReader::read_markers`, into
+/// `dest`, moved later by `sample_offset_shift` samples, for combining the
+/// markers of recordings concatenated into one file.  Region lengths are
+/// kept.  Markers whose cue ID is already taken by a cue point of `dest` are
+/// given free IDs past the largest in use, wrapping around past `u32::MAX`,
+/// along with their labels.  The markers are appended as by
+/// `ChunkWriter::append_markers`, and the cue chunks of `dest` are then
+/// merged into one, as by `ChunkWriter::coalesce_cue_chunks`.  The source
+/// reader is reset after reading.
+///
+/// Errors if a shifted sample offset is too large, if `dest` has a cue chunk
+/// and is an RF64 or RIFX file, which cannot be rebuilt, if a cue chunk of
+/// `dest` is larger than `DEFAULT_MAX_CHUNK_SIZE`, if either cursor errors,
+/// or if resulting WAV is too large
+pub fn merge_markers_into<Dest, Source>(
+    dest: &mut ChunkWriter<Dest>,
+    source: &mut ChunkReader<Source>,
+    sample_offset_shift: u32,
+) -> Result<(), Error>
+where
+    Dest: Read + Write + Seek + Truncate,
+    Source: Read + Seek,
+{
+    let mut markers = source.read_markers()?;
+    let ids = dest.cue_ids()?;
+
+    // Merging into existing cue chunks rebuilds the file, so check it can be
+    // rebuilt before appending anything
+    if ids.is_some() {
+        dest.base_cursor.seek(SeekFrom::Start(dest.wave_start))?;
+        non_rf64_size(&ChunkHead::parse(&mut dest.base_cursor)?)?;
+    }
+
+    let mut ids = ids.unwrap_or_default();
+    let mut taken = ids.clone();
+    taken.extend(markers.iter().map(|marker| marker.cue.id));
+    let mut next_id = taken.last().copied().unwrap_or(0);
+
+    for marker in &mut markers {
+        marker.cue.sample_offset = marker
+            .cue
+            .sample_offset
+            .checked_add(sample_offset_shift)
+            .ok_or(Error::wave(format!(
+                "Shifted sample offset of cue {} is too large",
+                marker.cue.id
+            )))?;
+
+        if !ids.insert(marker.cue.id) {
+            next_id = free_cue_id(&taken, next_id)?;
+            marker.cue.id = next_id;
+            taken.insert(next_id);
+            ids.insert(next_id);
+        }
+    }
+
+    if markers.is_empty() {
+        return Ok(());
+    }
+
+    dest.append_markers(&markers)?;
+    dest.coalesce_cue_chunks()?;
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use crate::{pad_len, tag, ChunkHead, ChunkReader, Endianness, Error};
+use std::io::{Read, Seek};
+
+// Handler of `ChunkReader::visit_nested`, given the path, head, and body of
+// each chunk
+type NestedHandler<'a> =
+    dyn FnMut(&[[u8; 4]], ChunkHead, &[u8]) -> Result<(), Error> + 'a;
+
+// Hand every sub-chunk of a LIST body to the handler, descending into nested
+// LISTs, with the path holding the tag and list type of each open LIST.  The
+// open LISTs are kept on a stack of their unwalked bytes rather than by
+// recursion, so deeply nested input cannot overflow the call stack.
+fn visit_list(
+    body: &[u8],
+    endianness: Endianness,
+    handler: &mut NestedHandler,
+) -> Result<(), Error> {
+    let Some((list_type, rest)) = body.split_first_chunk::<4>() else {
+        return Ok(());
+    };
+
+    let mut path = vec![tag::LIST, *list_type];
+    let mut stack = vec![rest];
+
+    while let Some(rest) = stack.last_mut() {
+        let mut remaining = *rest;
+        let sub_chunk = ChunkHead::parse_with(&mut remaining, endianness)
+            .ok()
+            .and_then(|head| {
+                Some((head, remaining.get(..head.size as usize)?))
+            });
+
+        let Some((head, sub_body)) = sub_chunk else {
+            stack.pop();
+            path.truncate(path.len() - 2);
+            continue;
+        };
+
+        let skip = head.size as usize + pad_len(head.size.into()) as usize;
+        *rest = remaining.get(skip..).unwrap_or(&[]);
+        handler(&path, head, sub_body)?;
+
+        if head.tag == tag::LIST {
+            if let Some((list_type, sub_rest)) =
+                sub_body.split_first_chunk::<4>()
+            {
+                path.extend_from_slice(&[tag::LIST, *list_type]);
+                stack.push(sub_rest);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl<Cursor: Read + Seek> ChunkReader<Cursor> {
+    /// Walk every chunk, descending into LIST chunks, handing each chunk to
+    /// `handler` along with the path of the LISTs holding it: empty for
+    /// top-level chunks, and the tag and list type of each enclosing LIST,
+    /// outermost first, for sub-chunks.  E.g. a "labl" in an "adtl" LIST is
+    /// handed with the path `["LIST", "adtl"]`.  LISTs are handed whole
+    /// before their sub-chunks.  A sub-chunk running past the end of its LIST
+    /// ends the walk of that LIST.  The reader is reset before and after the
+    /// walk.
+    ///
+    /// Only LIST bodies are read into memory.  Other top-level chunks, such
+    /// as "data", are skipped over and handed with an empty body, so their
+    /// size is only known from the head; read them with `read_next_chunk` if
+    /// their contents are needed.
+    ///
+    /// Errors if a LIST is larger than `max_chunk_size`, if underlying cursor
+    /// errors, or if the handler errors
+    pub fn visit_nested(
+        &mut self,
+        handler: &mut NestedHandler,
+    ) -> Result<(), Error> {
+        self.reset()?;
+        while let Some(head) = self.seek_next_chunk(|_| true)? {
+            if head.tag != tag::LIST {
+                self.skip_chunk_body(&head)?;
+                handler(&[], head, &[])?;
+                continue;
+            }
+
+            let body = self.read_chunk_body(&head)?;
+            handler(&[], head, &body)?;
+            visit_list(&body, self.endianness, handler)?;
+        }
+
+        self.reset()?;
+        Ok(())
+    }
+}
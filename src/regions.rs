@@ -0,0 +1,109 @@
+use crate::{ChunkReader, Error, Marker, Region};
+use std::io::{Read, Seek};
+
+/// Suffixes naming the cue point that ends a region, after the name of the
+/// cue point starting it, compared ignoring case; e.g. "Chorus (end)" ends
+/// "Chorus"
+pub const REGION_END_SUFFIXES: [&str; 2] = [" (end)", " end"];
+
+/// Where `regions_from_cue_pairs` finds the end of each region
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RegionStrategy {
+    /// Only labeled-text sample lengths, as `ChunkReader::read_regions`
+    #[default]
+    SampleLength,
+
+    /// Labeled-text sample lengths, or for markers without one, a later cue
+    /// point named as the end of the marker, per `REGION_END_SUFFIXES`, as
+    /// tools that write regions as a pair of cue points do.  Cue points
+    /// ending a region are not regions themselves.
+    CuePairs,
+}
+
+// Name of the region a cue point so named ends
+fn start_name(end_name: &str) -> Option<&str> {
+    REGION_END_SUFFIXES.iter().find_map(|suffix| {
+        let split = end_name.len().checked_sub(suffix.len())?;
+        let (name, end) = end_name.split_at_checked(split)?;
+        end.eq_ignore_ascii_case(suffix).then_some(name)
+    })
+}
+
+/// Get the regions formed by markers, such as those from
+/// `ChunkReader::read_markers`, with region ends found per `strategy`, in
+/// marker order.  With `RegionStrategy::CuePairs`, a marker without a length
+/// pairs with the first end of its name after it that no later marker of
+/// the name has paired with, so repeated names pair in turn.
+pub fn regions_from_cue_pairs(
+    markers: &[Marker],
+    strategy: RegionStrategy,
+) -> Vec<Region> {
+    let is_end = |marker: &Marker| {
+        strategy == RegionStrategy::CuePairs
+            && marker.region_length.is_none()
+            && marker.label.as_deref().and_then(start_name).is_some()
+    };
+
+    // Name of the start, offset, and whether paired, of every end
+    let mut ends = markers
+        .iter()
+        .filter_map(|marker| {
+            let name = marker.label.as_deref().and_then(start_name)?;
+            is_end(marker).then_some((name, marker.cue.sample_offset, false))
+        })
+        .collect::<Vec<_>>();
+    ends.sort_by_key(|&(_, offset, _)| offset);
+
+    let mut starts = markers
+        .iter()
+        .enumerate()
+        .filter(|(_, marker)| !is_end(marker))
+        .collect::<Vec<_>>();
+    starts
+        .sort_by_key(|(_, marker)| std::cmp::Reverse(marker.cue.sample_offset));
+
+    let mut paired = vec![None; markers.len()];
+
+    // Latest starts first, so each takes the nearest end
+    for (index, marker) in starts {
+        if marker.region_length.is_some() {
+            paired[index] = marker.as_region();
+            continue;
+        }
+
+        if strategy != RegionStrategy::CuePairs {
+            continue;
+        }
+
+        let start = marker.cue.sample_offset;
+        let label = marker.label.clone().unwrap_or_default();
+        let end = ends.iter_mut().find(|(name, offset, used)| {
+            !*used && *offset > start && *name == label
+        });
+
+        if let Some((_, offset, used)) = end {
+            *used = true;
+            paired[index] = Some(Region {
+                start,
+                length: *offset - start,
+                name: label,
+            });
+        }
+    }
+
+    paired.into_iter().flatten().collect()
+}
+
+impl<Cursor: Read + Seek> ChunkReader<Cursor> {
+    /// Read the regions formed by the markers of the WAVE, with region ends
+    /// found per `strategy`; see `regions_from_cue_pairs`.  The reader is
+    /// reset before and after reading.
+    ///
+    /// Errors if underlying cursor errors
+    pub fn read_regions_with(
+        &mut self,
+        strategy: RegionStrategy,
+    ) -> Result<Vec<Region>, Error> {
+        Ok(regions_from_cue_pairs(&self.read_markers()?, strategy))
+    }
+}
@@ -0,0 +1,44 @@
+use crate::tag;
+
+/// Chunks written by Pro Tools describing its session and regions.  They are
+/// not parsed, but are kept by `ChunkWriter::strip_metadata` by default since
+/// Pro Tools sessions referencing the file depend on them.
+pub const PRO_TOOLS_CHUNKS: [[u8; 4]; 3] = [*b"minf", *b"elm1", *b"regn"];
+
+const KNOWN_CHUNKS: [([u8; 4], &str); 26] = [
+    (tag::FMT, "Format"),
+    (*b"fact", "Fact"),
+    (tag::DATA, "Samples"),
+    (tag::CUE, "Cue points"),
+    (tag::LIST, "List"),
+    (*b"plst", "Playlist"),
+    (*b"smpl", "Sampler"),
+    (*b"inst", "Instrument"),
+    (*b"bext", "Broadcast extension"),
+    (*b"umid", "Unique material ID"),
+    (*b"iXML", "iXML metadata"),
+    (*b"axml", "XML metadata"),
+    (*b"levl", "Peak envelope"),
+    (*b"cart", "Cart"),
+    (*b"acid", "ACID loop"),
+    (*b"DISP", "Display"),
+    (*b"id3 ", "ID3 tag"),
+    (*b"ID3 ", "ID3 tag"),
+    (*b"JUNK", "Padding"),
+    (*b"PAD ", "Padding"),
+    (*b"FLLR", "Padding"),
+    (*b"ds64", "RF64 sizes"),
+    (*b"chna", "ADM channel assignment"),
+    (*b"minf", "Pro Tools media info"),
+    (*b"elm1", "Pro Tools elements"),
+    (*b"regn", "Pro Tools regions"),
+];
+
+/// Human-readable name of a chunk tag commonly found in WAV files, including
+/// vendor chunks such as Pro Tools', or `None` for unknown tags
+pub fn chunk_name(tag: &[u8; 4]) -> Option<&'static str> {
+    KNOWN_CHUNKS
+        .iter()
+        .find(|(known, _)| known == tag)
+        .map(|(_, name)| *name)
+}
@@ -0,0 +1,258 @@
+use crate::{
+    size_u32, tag, ChunkHead, ChunkWriter, Endianness, Error, CHUNK_HEAD_SZ,
+    DEFAULT_MAX_CHUNK_SIZE,
+};
+use std::io::{Read, Seek, SeekFrom, Write};
+
+/// Smallest "ds64" chunk body, without any table entries
+pub const DS64_MIN_SZ: usize = 28;
+const DS64_ENTRY_SZ: usize = 12;
+
+/// RIFF tags of WAVEs whose sizes may exceed 4 GiB, with 64-bit sizes kept in
+/// a "ds64" chunk
+pub const RF64_TAGS: [[u8; 4]; 2] = [*b"RF64", *b"BW64"];
+
+/// Contents of the "ds64" chunk that must be the first chunk of an RF64 or
+/// BW64 file.  A RIFF size and "data" chunk size of `u32::MAX` in their
+/// headers stand for the 64-bit sizes recorded here.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Ds64Chunk {
+    pub riff_size: u64,
+    pub data_size: u64,
+
+    /// Sample frames, standing in for the count of a "fact" chunk
+    pub sample_count: u64,
+
+    /// 64-bit sizes of any other chunks over 4 GiB, by tag
+    pub table: Vec<([u8; 4], u64)>,
+}
+
+impl Ds64Chunk {
+    /// Parse the body of a "ds64" chunk.  A trailing partial table entry is
+    /// ignored.
+    ///
+    /// Errors if bytes are too short for the fixed-size fields
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < DS64_MIN_SZ {
+            return Err(Error::wave("ds64 chunk too short"));
+        }
+
+        let u64_at = |i: usize| {
+            u64::from_le_bytes(*bytes[i..].first_chunk::<8>().unwrap())
+        };
+        let table_len =
+            u32::from_le_bytes(*bytes[24..].first_chunk::<4>().unwrap());
+
+        let table = bytes[DS64_MIN_SZ..]
+            .chunks_exact(DS64_ENTRY_SZ)
+            .take(table_len as usize)
+            .map(|entry| {
+                let (tag, size) = entry.split_first_chunk::<4>().unwrap();
+                (*tag, u64::from_le_bytes(*size.first_chunk::<8>().unwrap()))
+            })
+            .collect();
+
+        Ok(Ds64Chunk {
+            riff_size: u64_at(0),
+            data_size: u64_at(8),
+            sample_count: u64_at(16),
+            table,
+        })
+    }
+
+    /// Get the bytes for the ds64 chunk, without any header
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes =
+            Vec::with_capacity(DS64_MIN_SZ + self.table.len() * DS64_ENTRY_SZ);
+        bytes.extend_from_slice(&self.riff_size.to_le_bytes());
+        bytes.extend_from_slice(&self.data_size.to_le_bytes());
+        bytes.extend_from_slice(&self.sample_count.to_le_bytes());
+        bytes.extend_from_slice(&(self.table.len() as u32).to_le_bytes());
+
+        for (tag, size) in &self.table {
+            bytes.extend_from_slice(tag);
+            bytes.extend_from_slice(&size.to_le_bytes());
+        }
+
+        bytes
+    }
+}
+
+// Size of the body of the RIFF chunk with the given head, along with the
+// "ds64" chunk of RF64 files.  The cursor must be just after the form id, and
+// is left there.
+pub(crate) fn riff_body_size<Cursor: Read + Seek>(
+    cursor: &mut Cursor,
+    riff_head: &ChunkHead,
+) -> Result<(u64, Option<Ds64Chunk>), Error> {
+    if !RF64_TAGS.contains(&riff_head.tag) {
+        return Ok((riff_head.size.into(), None));
+    }
+
+    let first_chunk_pos = cursor.stream_position()?;
+    let head = ChunkHead::parse(cursor)?;
+
+    if head.tag != *b"ds64" || (head.size as usize) < DS64_MIN_SZ {
+        return Err(Error::wave("RF64 file without ds64 chunk"));
+    }
+
+    // Read only the fixed-size fields and the table they declare, as far as
+    // the chunk, the stream, and the default chunk size limit allow, so a
+    // bogus size in the head cannot demand a huge allocation
+    let mut body = vec![0u8; DS64_MIN_SZ];
+    cursor.read_exact(&mut body)?;
+    let table_len = u32::from_le_bytes(*body[24..].first_chunk().unwrap());
+    let table_sz = (u64::from(table_len) * DS64_ENTRY_SZ as u64)
+        .min(u64::from(head.size) - DS64_MIN_SZ as u64)
+        .min(DEFAULT_MAX_CHUNK_SIZE.into());
+    (&mut *cursor).take(table_sz).read_to_end(&mut body)?;
+    cursor.seek(SeekFrom::Start(first_chunk_pos))?;
+    let ds64 = Ds64Chunk::parse(&body)?;
+
+    let size = if riff_head.size == u32::MAX {
+        ds64.riff_size
+    } else {
+        riff_head.size.into()
+    };
+
+    Ok((size, Some(ds64)))
+}
+
+// Size of the body of a chunk with the given head, from the "ds64" chunk for
+// "data" chunks whose head holds `u32::MAX`
+pub(crate) fn chunk_body_size(
+    head: &ChunkHead,
+    ds64: Option<&Ds64Chunk>,
+) -> u64 {
+    match ds64 {
+        Some(ds64) if head.tag == tag::DATA && head.size == u32::MAX => {
+            ds64.data_size
+        }
+        _ => head.size.into(),
+    }
+}
+
+impl<Cursor: Read + Write + Seek> ChunkWriter<Cursor> {
+    // Find how new sizes will be recorded, before anything is written so
+    // that sizes which cannot be recorded leave the WAVE unchanged.  Returns
+    // the current "ds64" chunk for RF64 files, or one to upgrade to if the
+    // sizes need 64 bits, or `None` to keep 32-bit sizes.
+    //
+    // Upgrading takes over a "JUNK" chunk with room for a ds64 chunk placed
+    // first, as writers reserve for the purpose.
+    pub(crate) fn plan_sizes(
+        &mut self,
+        riff_size: u64,
+        data_size: Option<u64>,
+    ) -> Result<Option<Ds64Chunk>, Error> {
+        if let Some(ds64) = self.read_ds64()? {
+            return Ok(Some(ds64));
+        }
+
+        let max = u64::from(u32::MAX);
+        if riff_size <= max && data_size.is_none_or(|size| size <= max) {
+            return Ok(None);
+        }
+
+        let needed = riff_size.max(data_size.unwrap_or(0));
+
+        if self.endianness == Endianness::Big {
+            return Err(Error::chunk_too_big(needed));
+        }
+
+        let chunks = self.chunk_positions()?;
+        match chunks.first() {
+            Some((_, head))
+                if head.tag == *b"JUNK"
+                    && head.size as usize >= DS64_MIN_SZ =>
+            {
+                Ok(Some(Ds64Chunk {
+                    data_size: chunks
+                        .iter()
+                        .find(|(_, head)| head.tag == tag::DATA)
+                        .map_or(0, |(_, head)| head.size.into()),
+                    ..Ds64Chunk::default()
+                }))
+            }
+            _ => Err(Error::wave(format!(
+                "Size of {} bytes exceeds maximum of {}; reserve a JUNK chunk \
+                 of at least {} bytes first to allow upgrading to RF64",
+                needed,
+                u32::MAX,
+                DS64_MIN_SZ
+            ))),
+        }
+    }
+
+    // The "ds64" chunk of RF64 files
+    pub(crate) fn read_ds64(&mut self) -> Result<Option<Ds64Chunk>, Error> {
+        let cursor = &mut self.base_cursor;
+        cursor.seek(SeekFrom::Start(self.wave_start))?;
+        let riff_head = ChunkHead::parse(cursor)?;
+        cursor.seek(SeekFrom::Current(4))?;
+        Ok(riff_body_size(cursor, &riff_head)?.1)
+    }
+
+    // Record the new RIFF size, and "data" chunk size if given, as planned by
+    // `plan_sizes`.  Upgrading rewrites the RIFF header as "RF64" and the
+    // reserved "JUNK" chunk as "ds64".
+    pub(crate) fn record_sizes(
+        &mut self,
+        plan: Option<Ds64Chunk>,
+        riff_size: u64,
+        data_size: Option<u64>,
+    ) -> Result<(), Error> {
+        let cursor = &mut self.base_cursor;
+
+        let mut ds64 = match plan {
+            Some(ds64) => ds64,
+            None => {
+                cursor.seek(SeekFrom::Start(self.wave_start + 4))?;
+                let riff_size = size_u32(riff_size)?;
+                cursor.write_all(&self.endianness.u32_bytes(riff_size))?;
+                return Ok(());
+            }
+        };
+
+        ds64.riff_size = riff_size;
+        ds64.data_size = data_size.unwrap_or(ds64.data_size);
+
+        cursor.seek(SeekFrom::Start(self.wave_start))?;
+        let riff_head = ChunkHead::parse(cursor)?;
+        let riff_tag = if RF64_TAGS.contains(&riff_head.tag) {
+            riff_head.tag
+        } else {
+            RF64_TAGS[0]
+        };
+
+        let slot_pos = self.wave_start + CHUNK_HEAD_SZ as u64 + 4;
+        cursor.seek(SeekFrom::Start(slot_pos))?;
+        let slot = ChunkHead::parse(cursor)?;
+        let mut body = ds64.as_bytes();
+
+        if body.len() > slot.size as usize {
+            return Err(Error::wave("ds64 table too large for its chunk"));
+        }
+
+        body.resize(slot.size as usize, 0);
+        cursor.seek(SeekFrom::Start(self.wave_start))?;
+        cursor.write_all(
+            &ChunkHead {
+                tag: riff_tag,
+                size: u32::MAX,
+            }
+            .as_bytes(),
+        )?;
+        cursor.seek(SeekFrom::Start(slot_pos))?;
+        cursor.write_all(
+            &ChunkHead {
+                tag: *b"ds64",
+                size: slot.size,
+            }
+            .as_bytes(),
+        )?;
+        cursor.write_all(&body)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,206 @@
+use crate::Error;
+
+pub(crate) const SAMPLER_MIN_SZ: usize = 36;
+const SAMPLE_LOOP_SZ: usize = 24;
+
+/// Manufacturers by their one-byte MMA SysEx ID
+const KNOWN_MANUFACTURERS: [(u8, &str); 10] = [
+    (0x01, "Sequential Circuits"),
+    (0x07, "Kurzweil"),
+    (0x0F, "Ensoniq"),
+    (0x18, "E-mu"),
+    (0x40, "Kawai"),
+    (0x41, "Roland"),
+    (0x42, "Korg"),
+    (0x43, "Yamaha"),
+    (0x44, "Casio"),
+    (0x47, "Akai"),
+];
+
+/// Manufacturer field of a "smpl" chunk, interpreted as an MMA SysEx ID.  The
+/// high byte of the field counts the bytes of the ID held in the low bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Manufacturer {
+    /// One-byte SysEx ID, e.g. 0x41 for Roland
+    SysEx(u8),
+
+    /// Three-byte extended SysEx ID, starting with 0
+    ExtendedSysEx([u8; 3]),
+
+    /// Field following neither layout, e.g. 0 when no manufacturer is set
+    Raw(u32),
+}
+
+impl Manufacturer {
+    /// Interpret a raw manufacturer field
+    pub fn from_field(field: u32) -> Self {
+        let [count, id @ ..] = field.to_be_bytes();
+
+        match (count, id) {
+            (1, [0, 0, id]) if id != 0 => Self::SysEx(id),
+            (3, [0, _, _]) => Self::ExtendedSysEx(id),
+            _ => Self::Raw(field),
+        }
+    }
+
+    /// Name of a well-known manufacturer with a one-byte SysEx ID
+    pub fn name(&self) -> Option<&'static str> {
+        match self {
+            Self::SysEx(id) => KNOWN_MANUFACTURERS
+                .iter()
+                .find(|(known, _)| known == id)
+                .map(|(_, name)| *name),
+            _ => None,
+        }
+    }
+}
+
+/// A loop within the samples of a "smpl" chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SampleLoop {
+    /// ID of a cue point marking the loop, or 0
+    pub cue_point_id: u32,
+
+    /// 0 for forward, 1 for alternating, 2 for backward
+    pub loop_type: u32,
+
+    /// Sample frame of the first sample in the loop
+    pub start: u32,
+
+    /// Sample frame of the last sample in the loop
+    pub end: u32,
+
+    /// Fraction of a sample at which to loop, out of 2^32
+    pub fraction: u32,
+
+    /// Times to play the loop, 0 for forever
+    pub play_count: u32,
+}
+
+/// Contents of a "smpl" chunk, describing how a sampler maps and loops the
+/// samples.  Fields are kept raw, so chunks round-trip; see `manufacturer` for
+/// the interpreted manufacturer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SamplerChunk {
+    /// MMA manufacturer code of the intended sampler, 0 for any
+    pub manufacturer: u32,
+
+    /// Product code of the intended sampler, specific to the manufacturer
+    pub product: u32,
+
+    /// Nanoseconds per sample frame
+    pub sample_period: u32,
+
+    /// MIDI note number at which the samples play at their original pitch
+    pub midi_unity_note: u32,
+
+    /// Fraction of a semitone above the unity note, out of 2^32
+    pub midi_pitch_fraction: u32,
+
+    pub smpte_format: u32,
+    pub smpte_offset: u32,
+
+    pub loops: Vec<SampleLoop>,
+
+    /// Manufacturer-specific data following the loops
+    pub sampler_data: Vec<u8>,
+}
+
+impl SamplerChunk {
+    /// Parse the body of a smpl chunk.  Loops and sampler data declared past
+    /// the end of the chunk are cut short, and sampler data is dropped if any
+    /// loop is.
+    ///
+    /// Errors if bytes are too short for the fixed-size fields
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < SAMPLER_MIN_SZ {
+            return Err(Error::wave("smpl chunk too short"));
+        }
+
+        let u32_at = |bytes: &[u8], i: usize| {
+            u32::from_le_bytes(*bytes[i..].first_chunk::<4>().unwrap())
+        };
+
+        let loop_count = u32_at(bytes, 28) as usize;
+        let data_len = u32_at(bytes, 32) as usize;
+        let rest = &bytes[SAMPLER_MIN_SZ..];
+        let loops_len = loop_count
+            .saturating_mul(SAMPLE_LOOP_SZ)
+            .min(rest.len() / SAMPLE_LOOP_SZ * SAMPLE_LOOP_SZ);
+        let (loop_bytes, mut sampler_data) = rest.split_at(loops_len);
+
+        // Without every loop, there is no telling where the data starts
+        if loops_len < loop_count.saturating_mul(SAMPLE_LOOP_SZ) {
+            sampler_data = &[];
+        }
+
+        let loops = loop_bytes
+            .chunks_exact(SAMPLE_LOOP_SZ)
+            .map(|entry| SampleLoop {
+                cue_point_id: u32_at(entry, 0),
+                loop_type: u32_at(entry, 4),
+                start: u32_at(entry, 8),
+                end: u32_at(entry, 12),
+                fraction: u32_at(entry, 16),
+                play_count: u32_at(entry, 20),
+            })
+            .collect();
+
+        Ok(SamplerChunk {
+            manufacturer: u32_at(bytes, 0),
+            product: u32_at(bytes, 4),
+            sample_period: u32_at(bytes, 8),
+            midi_unity_note: u32_at(bytes, 12),
+            midi_pitch_fraction: u32_at(bytes, 16),
+            smpte_format: u32_at(bytes, 20),
+            smpte_offset: u32_at(bytes, 24),
+            loops,
+            sampler_data: sampler_data[..data_len.min(sampler_data.len())]
+                .to_vec(),
+        })
+    }
+
+    /// Interpreted manufacturer field
+    pub fn manufacturer(&self) -> Manufacturer {
+        Manufacturer::from_field(self.manufacturer)
+    }
+
+    /// Get the bytes for the smpl chunk, without any header
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(
+            SAMPLER_MIN_SZ
+                + self.loops.len() * SAMPLE_LOOP_SZ
+                + self.sampler_data.len(),
+        );
+
+        for field in [
+            self.manufacturer,
+            self.product,
+            self.sample_period,
+            self.midi_unity_note,
+            self.midi_pitch_fraction,
+            self.smpte_format,
+            self.smpte_offset,
+            self.loops.len() as u32,
+            self.sampler_data.len() as u32,
+        ] {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+
+        for sample_loop in &self.loops {
+            for field in [
+                sample_loop.cue_point_id,
+                sample_loop.loop_type,
+                sample_loop.start,
+                sample_loop.end,
+                sample_loop.fraction,
+                sample_loop.play_count,
+            ] {
+                bytes.extend_from_slice(&field.to_le_bytes());
+            }
+        }
+
+        bytes.extend_from_slice(&self.sampler_data);
+        bytes
+    }
+}
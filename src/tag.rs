@@ -0,0 +1,25 @@
+//! Tags of the chunks and sub-chunks this crate reads and writes, and the
+//! list type of their LISTs.  Using these rather than byte string literals
+//! guards against misspellings such as "cue" for "cue ", which compile but
+//! never match.
+
+/// "fmt " chunk, describing the sample format
+pub const FMT: [u8; 4] = *b"fmt ";
+
+/// "data" chunk, holding the samples
+pub const DATA: [u8; 4] = *b"data";
+
+/// "cue " chunk, holding cue points
+pub const CUE: [u8; 4] = *b"cue ";
+
+/// LIST chunk, holding sub-chunks after a 4-byte list type
+pub const LIST: [u8; 4] = *b"LIST";
+
+/// List type of a LIST holding labels and labeled-texts
+pub const ADTL: [u8; 4] = *b"adtl";
+
+/// List type of a LIST holding descriptive text such as title and artist
+pub const INFO: [u8; 4] = *b"INFO";
+
+/// Labeled-text sub-chunk of an "adtl" LIST
+pub const LTXT: [u8; 4] = *b"ltxt";
@@ -0,0 +1,36 @@
+//! Helpers for building WAVE fixtures in tests, available with the
+//! `test-utils` feature.
+
+use crate::{pad_len, ChunkHead, CHUNK_HEAD_SZ};
+
+/// Synthesize a WAVE file from chunk heads and optional payloads, computing
+/// the RIFF size from the heads' declared sizes.  Chunks without a payload are
+/// zero-filled, payloads shorter than the declared size are zero-filled to
+/// fit, and a pad byte is added after odd-sized chunks.  Payloads longer than
+/// the declared size are written whole, for building malformed files.
+pub fn build_wave(chunks: &[(ChunkHead, Option<&[u8]>)]) -> Vec<u8> {
+    let mut v = vec![0u8; CHUNK_HEAD_SZ];
+    v.extend_from_slice(b"WAVE");
+    let mut riff_sz = 4u32;
+
+    for (head, payload) in chunks {
+        v.extend_from_slice(&head.as_bytes()[..]);
+        let rsz = head.size + pad_len(head.size.into()) as u32;
+        let payload = payload.unwrap_or(&[]);
+        v.extend_from_slice(payload);
+
+        if payload.len() < rsz as usize {
+            v.resize(v.len() + rsz as usize - payload.len(), 0u8);
+        }
+
+        riff_sz += rsz + CHUNK_HEAD_SZ as u32;
+    }
+
+    let riff_head = ChunkHead {
+        tag: *b"RIFF",
+        size: riff_sz,
+    };
+    v[..8].copy_from_slice(&riff_head.as_bytes()[..]);
+
+    v
+}
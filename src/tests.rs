@@ -1,47 +1,27 @@
+use crate::testing::build_wave;
 use crate::{
-    extract_labeled_text_from_list, parse_cue_points, ChunkHead, ChunkReader,
-    ChunkWriter, CuePoint, LabeledText, CHUNK_HEAD_SZ, CUE_SZ,
+    aligned_chunk_len, chunk_name, cue_chunk, cue_points_are_sorted,
+    diff_markers, export_regions, extract_labeled_text_from_list,
+    extract_labeled_text_from_list_with_max, extract_labels_from_list,
+    find_overlapping_regions, is_wave, label_chunk, merge_markers_into,
+    needs_pad, normalize_cue_ids, pad_len, pad_size_16, parse_cue_points,
+    parse_cue_points_detect, regions_from_cue_pairs, reindex_positions,
+    resample_cue_points, resample_labeled_texts, resolve_cue_byte_offset,
+    shift_cue_points, shift_labeled_texts, tag, try_parse_cue_points,
+    unlabeled_cue_ids, unlabeled_cue_ids_with_labels, validate_cue_chunk,
+    wrap_pcm, AcidChunk, CartChunk, CartTimer, ChunkAction, ChunkDecoder,
+    ChunkDecoders, ChunkHead, ChunkReader, ChunkWriter, CueCountPolicy,
+    CueIndex, CueLayout, CuePoint, DispChunk, Ds64Chunk, Endianness, Error,
+    ErrorKind, FormatChunk, InfoMetadata, Label, LabeledText, Manufacturer,
+    Marker, PeakEnvelopeChunk, RecoveryPolicy, Region, RegionStrategy,
+    SampleLoop, SamplerChunk, SpeakerPosition, Truncate, Warning, WaveFlavor,
+    WaveList, WaveSegment, CHUNK_HEAD_SZ, CUE_SZ, DEFAULT_MAX_CHUNK_SIZE,
+    DEFAULT_MAX_TEXT_LEN, FORMAT_EXTENSIBLE, PRO_TOOLS_CHUNKS,
 };
 use io::Seek;
 use std::io;
 
-fn riff_head(size: u32) -> ChunkHead {
-    ChunkHead {
-        tag: *b"RIFF",
-        size,
-    }
-}
-
-fn real_size(size: u32) -> u32 {
-    if size & 1 == 1 {
-        size + 1
-    } else {
-        size
-    }
-}
-
-fn wave_bytes(chunk_heads: &[(ChunkHead, Option<&[u8]>)]) -> Vec<u8> {
-    let mut v = vec![0u8; CHUNK_HEAD_SZ];
-    v.extend_from_slice(b"WAVE");
-    let mut riff_sz = 4u32;
-
-    for (head, payload) in chunk_heads {
-        v.extend_from_slice(&head.as_bytes()[..]);
-        let rsz = real_size(head.size);
-
-        match payload {
-            None => v.resize(v.len() + rsz as usize, 0u8),
-            Some(bytes) => v.extend_from_slice(bytes),
-        }
-
-        riff_sz += rsz + CHUNK_HEAD_SZ as u32;
-    }
-
-    let riff_head = riff_head(riff_sz);
-    v[..8].copy_from_slice(&riff_head.as_bytes()[..]);
-
-    v
-}
+mod round_trip;
 
 #[test]
 fn get_cue_points() {
@@ -64,7 +44,7 @@ fn get_cue_points() {
     };
 
     let check_chunks = |chunks: &[(ChunkHead, Option<&[u8]>)]| {
-        let bytes = wave_bytes(chunks);
+        let bytes = build_wave(chunks);
         let mut base_cursor = io::Cursor::new(&bytes[..]);
         let initial_position = base_cursor.stream_position().unwrap();
         let mut cursor = ChunkReader::new(base_cursor).unwrap();
@@ -124,7 +104,7 @@ fn append_cue_points() {
         size: 1,
     };
 
-    let initial_wave_bytes = wave_bytes(&[(fmt_head, None), (data_head, None)]);
+    let initial_wave_bytes = build_wave(&[(fmt_head, None), (data_head, None)]);
     let mut wave_bytes = initial_wave_bytes.clone();
 
     let mut cursor = io::Cursor::new(&mut wave_bytes);
@@ -203,7 +183,7 @@ fn get_labeled_text() {
         (list_head, Some(&list_bytes[..])),
     ];
 
-    let bytes = wave_bytes(&chunks[..]);
+    let bytes = build_wave(&chunks[..]);
     let base_cursor = io::Cursor::new(&bytes[..]);
     let mut cursor = ChunkReader::new(base_cursor).unwrap();
 
@@ -253,7 +233,7 @@ fn append_labeled_text() {
         size: 1,
     };
 
-    let initial_wave_bytes = wave_bytes(&[(fmt_head, None), (data_head, None)]);
+    let initial_wave_bytes = build_wave(&[(fmt_head, None), (data_head, None)]);
     let mut wave_bytes = initial_wave_bytes.clone();
 
     let mut cursor = io::Cursor::new(&mut wave_bytes);
@@ -283,3 +263,4307 @@ fn append_labeled_text() {
 
     assert_eq!(&wave_bytes[list_start + CHUNK_HEAD_SZ..], &list_chunk_bytes,);
 }
+
+#[test]
+fn checked_chunk_head() {
+    let head = ChunkHead::try_new(*b"cue ", 4).unwrap();
+    assert_eq!(head.tag_str(), Some("cue "));
+    assert!(ChunkHead::try_new([b'c', 0, b'e', b' '], 4).is_err());
+    assert!(ChunkHead::try_new(*b"cu\x7f ", 4).is_err());
+
+    let garbage = ChunkHead {
+        tag: [0xFF, b'u', b'e', b' '],
+        size: 0,
+    };
+    assert_eq!(garbage.tag_str(), None);
+}
+
+#[test]
+fn skip_empty_appends() {
+    let fmt_head = ChunkHead {
+        tag: *b"fmt ",
+        size: 16,
+    };
+
+    let initial_wave_bytes = build_wave(&[(fmt_head, None)]);
+    let mut wave_bytes = initial_wave_bytes.clone();
+
+    let cursor = io::Cursor::new(&mut wave_bytes);
+    let mut writer = ChunkWriter::new(cursor).unwrap();
+    writer.append_cue_chunk(&[]).unwrap();
+    writer.append_label_chunk(&[]).unwrap();
+    writer.restore_cursor().unwrap();
+    assert_eq!(wave_bytes, initial_wave_bytes);
+
+    let cursor = io::Cursor::new(&mut wave_bytes);
+    let mut writer = ChunkWriter::new(cursor).unwrap();
+    writer.set_allow_empty(true);
+    writer.append_cue_chunk(&[]).unwrap();
+    writer.append_label_chunk(&[]).unwrap();
+    writer.restore_cursor().unwrap();
+    assert_eq!(
+        wave_bytes.len(),
+        initial_wave_bytes.len() + 2 * (CHUNK_HEAD_SZ + 4)
+    );
+    assert_eq!(&wave_bytes[initial_wave_bytes.len()..][..4], b"cue ");
+}
+
+fn xorshift(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+fn adtl_bytes(sub_chunks: &[(&[u8; 4], u32, &[u8])]) -> Vec<u8> {
+    let mut bytes = Vec::from(&b"adtl"[..]);
+
+    for (tag, size, payload) in sub_chunks {
+        bytes.extend_from_slice(&tag[..]);
+        bytes.extend_from_slice(&size.to_le_bytes());
+        bytes.extend_from_slice(payload);
+    }
+
+    bytes
+}
+
+#[test]
+fn malformed_labeled_text_lists() {
+    let ltxt = LabeledText::from_cue_length(7, 100);
+    let ltxt_bytes = ltxt.as_bytes();
+
+    let zero_len = adtl_bytes(&[
+        (b"ltxt", 0, &[]),
+        (b"labl", 0, &[]),
+        (b"ltxt", ltxt_bytes.len() as u32, &ltxt_bytes),
+    ]);
+    assert_eq!(
+        extract_labeled_text_from_list(&zero_len),
+        vec![ltxt.clone()]
+    );
+
+    let overrun = adtl_bytes(&[
+        (b"ltxt", ltxt_bytes.len() as u32, &ltxt_bytes),
+        (b"ltxt", u32::MAX, &ltxt_bytes),
+    ]);
+    assert_eq!(extract_labeled_text_from_list(&overrun), vec![ltxt.clone()]);
+
+    let truncated_head = adtl_bytes(&[
+        (b"ltxt", ltxt_bytes.len() as u32, &ltxt_bytes),
+        (b"ltxt", 0, &[]),
+    ]);
+    let truncated_head = &truncated_head[..truncated_head.len() - 4];
+    assert_eq!(extract_labeled_text_from_list(truncated_head), vec![ltxt]);
+
+    let mut state = 0x2545_f491_4f6c_dd1d;
+
+    for _ in 0..2000 {
+        let len = (xorshift(&mut state) % 96) as usize;
+        let mut bytes = adtl_bytes(&[]);
+
+        while bytes.len() < len {
+            let word = xorshift(&mut state);
+
+            match word % 4 {
+                0 => bytes.extend_from_slice(b"ltxt"),
+                1 => bytes.extend_from_slice(
+                    &((word >> 8) as u32 % 40).to_le_bytes(),
+                ),
+                _ => bytes.push((word >> 16) as u8),
+            }
+        }
+
+        extract_labeled_text_from_list(&bytes);
+    }
+}
+
+#[test]
+fn count_cues_and_labels() {
+    let cues = [
+        CuePoint::from_sample_offset(1, 10),
+        CuePoint::from_sample_offset(2, 20),
+        CuePoint::from_sample_offset(3, 30),
+    ];
+    let mut ltxt = LabeledText::from_cue_length(1, 5);
+    ltxt.text = String::from("odd");
+    let labeled_texts = [ltxt, LabeledText::from_cue_length(2, 5)];
+
+    let fmt_head = ChunkHead {
+        tag: *b"fmt ",
+        size: 16,
+    };
+    let data_head = ChunkHead {
+        tag: *b"data",
+        size: 7,
+    };
+    let mut bytes = build_wave(&[(fmt_head, None), (data_head, None)]);
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(reader.cue_count().unwrap(), 0);
+    assert_eq!(reader.label_count().unwrap(), 0);
+
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.append_cue_chunk(&cues[..]).unwrap();
+    writer.append_label_chunk(&labeled_texts[..]).unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(reader.cue_count().unwrap(), 3);
+    assert_eq!(reader.label_count().unwrap(), 2);
+    assert!(reader.read_next_chunk(Some(*b"fmt ")).unwrap().is_some());
+}
+
+#[test]
+fn entries_from_bytes() {
+    let cue = CuePoint::from_sample_offset(4, 1000);
+    assert_eq!(CuePoint::try_from_bytes(&cue.as_bytes()).unwrap(), cue);
+    assert!(CuePoint::try_from_bytes(&cue.as_bytes()[1..]).is_err());
+
+    let mut ltxt = LabeledText::from_cue_length(4, 50);
+    ltxt.text = String::from("loop");
+    assert_eq!(LabeledText::try_from_bytes(&ltxt.as_bytes()).unwrap(), ltxt);
+    assert!(LabeledText::try_from_bytes(&ltxt.as_bytes()[..19]).is_err());
+}
+
+#[test]
+fn shift_for_trim() {
+    let cues = vec![
+        CuePoint::from_sample_offset(1, 10),
+        CuePoint::from_sample_offset(2, 100),
+        CuePoint::from_sample_offset(3, 40),
+    ];
+    let labels = vec![
+        LabeledText::from_cue_length(1, 50),
+        LabeledText::from_cue_length(2, 10),
+        LabeledText::from_cue_length(3, 5),
+        LabeledText::from_cue_length(9, 5),
+    ];
+
+    let mut dropped = cues.clone();
+    let mut dropped_labels = labels.clone();
+    shift_labeled_texts(&mut dropped_labels, &dropped, -50, false);
+    shift_cue_points(&mut dropped, -50, false);
+    assert_eq!(dropped, vec![CuePoint::from_sample_offset(2, 50)]);
+    assert_eq!(
+        dropped_labels.iter().map(|l| l.cue_id).collect::<Vec<_>>(),
+        vec![2, 9]
+    );
+
+    let mut clamped = cues.clone();
+    let mut clamped_labels = labels.clone();
+    shift_labeled_texts(&mut clamped_labels, &clamped, -50, true);
+    shift_cue_points(&mut clamped, -50, true);
+    assert_eq!(
+        clamped.iter().map(|c| c.sample_offset).collect::<Vec<_>>(),
+        vec![0, 50, 0]
+    );
+    assert_eq!(
+        clamped_labels
+            .iter()
+            .map(|l| l.sample_length)
+            .collect::<Vec<_>>(),
+        vec![10, 10, 0, 5]
+    );
+
+    let mut saturated = cues;
+    shift_cue_points(&mut saturated, i64::from(u32::MAX), false);
+    assert!(saturated.iter().all(|c| c.sample_offset == u32::MAX));
+}
+
+#[test]
+fn wave_region_accessors() {
+    let fmt_head = ChunkHead {
+        tag: *b"fmt ",
+        size: 16,
+    };
+    let mut bytes = vec![0xAAu8; 5];
+    let wave = build_wave(&[(fmt_head, None)]);
+    bytes.extend_from_slice(&wave);
+    bytes.extend_from_slice(&[0xBB; 3]);
+
+    let mut cursor = io::Cursor::new(&bytes[..]);
+    cursor.set_position(5);
+    let reader = ChunkReader::new(cursor).unwrap();
+    assert_eq!(reader.wave_start(), 5);
+    assert_eq!(reader.first_chunk_pos(), 17);
+    assert_eq!(reader.wave_end(), 5 + wave.len() as u64);
+    assert_eq!(reader.wave_len(), wave.len() as u64);
+}
+
+#[test]
+fn concatenated_waves() {
+    let first = build_wave(&[(
+        ChunkHead {
+            tag: *b"fmt ",
+            size: 16,
+        },
+        None,
+    )]);
+    let second = build_wave(&[(
+        ChunkHead {
+            tag: *b"data",
+            size: 3,
+        },
+        None,
+    )]);
+    let mut bytes = first.clone();
+    bytes.extend_from_slice(&second);
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(reader.read_next_chunk(None).unwrap().unwrap().0, *b"fmt ");
+
+    let mut reader = reader.next_wave().unwrap().unwrap();
+    assert_eq!(reader.wave_start(), first.len() as u64);
+    assert_eq!(reader.read_next_chunk(None).unwrap().unwrap().0, *b"data");
+    assert!(reader.read_next_chunk(None).unwrap().is_none());
+
+    let cursor = reader.restore_cursor().unwrap();
+    assert_eq!(cursor.position(), first.len() as u64);
+    let reader = ChunkReader::new(cursor).unwrap();
+    assert!(reader.next_wave().unwrap().is_none());
+}
+
+#[test]
+fn disp_title() {
+    let disp = DispChunk::from_text("Lava loop");
+    assert_eq!(disp.text().as_deref(), Some("Lava loop"));
+    assert_eq!(DispChunk::parse(&disp.as_bytes()).unwrap(), disp);
+    assert!(DispChunk::parse(&[1, 0]).is_err());
+
+    let bitmap = DispChunk {
+        cf_type: 2,
+        data: vec![1, 2, 3],
+    };
+    assert_eq!(bitmap.text(), None);
+
+    let fmt_head = ChunkHead {
+        tag: *b"fmt ",
+        size: 16,
+    };
+    let mut bytes = build_wave(&[(fmt_head, None)]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.append_disp_chunk(&disp).unwrap();
+    writer.append_chunk(*b"junk", &[9]).unwrap();
+    assert_eq!(bytes.len() % 2, 0);
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let (_, disp_bytes) =
+        reader.read_next_chunk(Some(*b"DISP")).unwrap().unwrap();
+    assert_eq!(DispChunk::parse(&disp_bytes).unwrap(), disp);
+    assert_eq!(
+        reader.read_next_chunk(None).unwrap(),
+        Some((*b"junk", vec![9]))
+    );
+    assert!(reader.read_next_chunk(None).unwrap().is_none());
+}
+
+#[test]
+fn acid_loop_metadata() {
+    let acid = AcidChunk {
+        flags: AcidChunk::ROOT_NOTE_SET | AcidChunk::STRETCH,
+        root_note: 60,
+        reserved_1: 0x8000,
+        reserved_2: 0.0,
+        beats: 8,
+        meter_denominator: 4,
+        meter_numerator: 4,
+        tempo: 120.0,
+    };
+
+    let parsed = AcidChunk::parse(&acid.as_bytes()).unwrap();
+    assert_eq!(parsed, acid);
+    assert!(!parsed.is_one_shot());
+    assert!(parsed.has_root_note());
+    assert!(AcidChunk::parse(&acid.as_bytes()[..20]).is_err());
+
+    let stalled = AcidChunk { tempo: 0.0, ..acid };
+    assert!(AcidChunk::parse(&stalled.as_bytes()).is_err());
+
+    let mut bytes = build_wave(&[]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    assert!(writer.append_acid_chunk(&stalled).is_err());
+    writer.append_acid_chunk(&acid).unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let (_, acid_bytes) =
+        reader.read_next_chunk(Some(*b"acid")).unwrap().unwrap();
+    assert_eq!(AcidChunk::parse(&acid_bytes).unwrap().tempo, 120.0);
+}
+
+#[test]
+fn cart_round_trip() {
+    let mut timers = [CartTimer::default(); 8];
+    timers[0] = CartTimer {
+        usage: *b"SEG1",
+        value: 44100,
+    };
+    timers[3] = CartTimer {
+        usage: *b"INTe",
+        value: 88200,
+    };
+
+    let cart = CartChunk {
+        version: String::from("0101"),
+        title: String::from("Station ID"),
+        artist: String::from("Announcer"),
+        cut_id: String::from("ID-0042"),
+        client_id: String::new(),
+        category: String::from("IDS"),
+        classification: String::new(),
+        out_cue: String::from("...on the air"),
+        start_date: String::from("2024-01-01"),
+        start_time: String::from("00:00:00"),
+        end_date: String::from("2099-12-31"),
+        end_time: String::from("23:59:59"),
+        producer_app_id: String::from("cuet"),
+        producer_app_version: String::from("0.1.0"),
+        user_def: String::new(),
+        level_reference: 32768,
+        timers,
+        reserved: [0u8; 276],
+        url: String::from("https://example.com"),
+        tag_text: String::from("<tag/>"),
+    };
+
+    let bytes = cart.as_bytes();
+    assert_eq!(bytes.len(), 2048 + 6);
+    assert_eq!(CartChunk::parse(&bytes).unwrap(), cart);
+    assert!(CartChunk::parse(&bytes[..2047]).is_err());
+
+    let cues = cart.timer_cue_points(1);
+    assert_eq!(
+        cues,
+        vec![
+            CuePoint::from_sample_offset(1, 44100),
+            CuePoint::from_sample_offset(2, 88200),
+        ]
+    );
+
+    let long_title = CartChunk {
+        version: String::from("0éé"),
+        title: "é".repeat(40),
+        ..cart
+    };
+    let parsed = CartChunk::parse(&long_title.as_bytes()).unwrap();
+    assert_eq!(parsed.version, "0é");
+    assert_eq!(parsed.title, "é".repeat(32));
+}
+
+fn pcm_format_bytes(channels: u16, sample_rate: u32, bits: u16) -> Vec<u8> {
+    let block_align = channels * bits.div_ceil(8);
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&channels.to_le_bytes());
+    bytes.extend_from_slice(&sample_rate.to_le_bytes());
+    bytes.extend_from_slice(
+        &(sample_rate * u32::from(block_align)).to_le_bytes(),
+    );
+    bytes.extend_from_slice(&block_align.to_le_bytes());
+    bytes.extend_from_slice(&bits.to_le_bytes());
+    bytes
+}
+
+#[test]
+fn verify_markers() {
+    let fmt_bytes = pcm_format_bytes(1, 11025, 16);
+    let fmt_head = ChunkHead {
+        tag: *b"fmt ",
+        size: fmt_bytes.len() as u32,
+    };
+    let data_head = ChunkHead {
+        tag: *b"data",
+        size: 20,
+    };
+
+    let mut cue_bytes = vec![4u8, 0, 0, 0];
+    cue_bytes.extend_from_slice(&CuePoint::from_sample_offset(1, 5).as_bytes());
+    cue_bytes.extend_from_slice(&CuePoint::from_sample_offset(1, 6).as_bytes());
+    cue_bytes
+        .extend_from_slice(&CuePoint::from_sample_offset(3, 11).as_bytes());
+    let cue_head = ChunkHead {
+        tag: *b"cue ",
+        size: cue_bytes.len() as u32,
+    };
+
+    let ltxt = LabeledText::from_cue_length(9, 1).as_bytes();
+    let list_bytes = adtl_bytes(&[
+        (b"ltxt", ltxt.len() as u32, &ltxt),
+        (b"labl", 4, &[1, 0, 0, 0]),
+    ]);
+    let list_head = ChunkHead {
+        tag: *b"LIST",
+        size: list_bytes.len() as u32,
+    };
+
+    let bytes = build_wave(&[
+        (fmt_head, Some(&fmt_bytes)),
+        (data_head, None),
+        (cue_head, Some(&cue_bytes)),
+        (list_head, Some(&list_bytes)),
+    ]);
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(
+        reader.verify().unwrap(),
+        vec![
+            Warning::CueCountMismatch {
+                declared: 4,
+                present: 3
+            },
+            Warning::DuplicateCueId { cue_id: 1 },
+            Warning::CueBeyondData {
+                cue_id: 3,
+                sample_offset: 11,
+                sample_count: 10
+            },
+            Warning::OrphanLabel {
+                tag: *b"ltxt",
+                cue_id: 9
+            },
+        ]
+    );
+
+    let clean = build_wave(&[(fmt_head, Some(&fmt_bytes)), (data_head, None)]);
+    let mut reader = ChunkReader::new(io::Cursor::new(&clean[..])).unwrap();
+    assert!(reader.verify().unwrap().is_empty());
+    // A short format chunk, without bits per sample, still bounds the cues
+    let short_head = ChunkHead {
+        tag: *b"fmt ",
+        size: 14,
+    };
+    let short = build_wave(&[
+        (short_head, Some(&fmt_bytes[..14])),
+        (data_head, None),
+        (cue_head, Some(&cue_bytes)),
+    ]);
+    let mut reader = ChunkReader::new(io::Cursor::new(&short[..])).unwrap();
+    assert!(reader.verify().unwrap().contains(&Warning::CueBeyondData {
+        cue_id: 3,
+        sample_offset: 11,
+        sample_count: 10
+    }));
+
+    // RF64, bounding the cues by the data size in the ds64 chunk
+    let mut rf64 = Vec::from(&b"RF64\xff\xff\xff\xffWAVEds64\x1c\0\0\0"[..]);
+    rf64.extend_from_slice(&[0; 28]);
+    rf64.extend_from_slice(&bytes[12..20 + fmt_bytes.len()]);
+    rf64.extend_from_slice(b"data\xff\xff\xff\xff");
+    rf64.extend_from_slice(&[0; 20]);
+    rf64.extend_from_slice(b"cue ");
+    rf64.extend_from_slice(&cue_head.size.to_le_bytes());
+    rf64.extend_from_slice(&cue_bytes);
+    let ds64 = Ds64Chunk {
+        riff_size: rf64.len() as u64 - 8,
+        data_size: 20,
+        ..Ds64Chunk::default()
+    };
+    rf64[20..48].copy_from_slice(&ds64.as_bytes());
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&rf64[..])).unwrap();
+    assert!(reader.verify().unwrap().contains(&Warning::CueBeyondData {
+        cue_id: 3,
+        sample_offset: 11,
+        sample_count: 10
+    }));
+}
+
+#[test]
+fn read_joined_markers() {
+    let cues = [
+        CuePoint::from_sample_offset(1, 100),
+        CuePoint::from_sample_offset(2, 200),
+        CuePoint::from_sample_offset(3, 300),
+    ];
+    let mut named = LabeledText::from_cue_length(1, 0);
+    named.text = String::from("start");
+    let region = LabeledText::from_cue_length(3, 50);
+
+    let mut bytes = build_wave(&[]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.append_cue_chunk(&cues).unwrap();
+    writer
+        .append_label_chunk(&[named.clone(), region.clone()])
+        .unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let markers = reader.read_markers().unwrap();
+
+    assert_eq!(
+        markers,
+        vec![
+            Marker {
+                cue: cues[0],
+                label: Some(String::from("start")),
+                region_length: None,
+                purpose_id: Some(*b"mark"),
+            },
+            Marker {
+                cue: cues[1],
+                label: None,
+                region_length: None,
+                purpose_id: None,
+            },
+            Marker {
+                cue: cues[2],
+                label: None,
+                region_length: Some(50),
+                purpose_id: Some(*b"mark"),
+            },
+        ]
+    );
+
+    assert_eq!(markers[0].split(), (cues[0], Some(named)));
+    assert_eq!(markers[1].split(), (cues[1], None));
+    assert_eq!(markers[2].split(), (cues[2], Some(region)));
+}
+
+#[test]
+fn cue_count_policies() {
+    let cue_chunk = |declared: u32, present: u32| {
+        let mut bytes = Vec::from(declared.to_le_bytes());
+
+        for id in 0..present {
+            bytes.extend_from_slice(
+                &CuePoint::from_sample_offset(id, id * 10).as_bytes(),
+            );
+        }
+
+        bytes
+    };
+
+    let consistent = cue_chunk(2, 2);
+    let (cues, warning) =
+        try_parse_cue_points(&consistent, CueCountPolicy::Strict).unwrap();
+    assert_eq!(cues.len(), 2);
+    assert_eq!(warning, None);
+
+    let overstated = cue_chunk(5, 3);
+    let (cues, warning) =
+        try_parse_cue_points(&overstated, CueCountPolicy::Lenient).unwrap();
+    assert_eq!(cues.len(), 3);
+    assert_eq!(
+        warning,
+        Some(Warning::CueCountMismatch {
+            declared: 5,
+            present: 3
+        })
+    );
+    assert!(try_parse_cue_points(&overstated, CueCountPolicy::Strict).is_err());
+
+    let understated = cue_chunk(2, 5);
+    let (lenient, _) =
+        try_parse_cue_points(&understated, CueCountPolicy::Lenient).unwrap();
+    let (strict, warning) =
+        try_parse_cue_points(&understated, CueCountPolicy::Strict).unwrap();
+    assert_eq!(lenient.len(), 5);
+    assert_eq!(strict.len(), 2);
+    assert!(warning.is_some());
+
+    assert!(try_parse_cue_points(&[1, 0], CueCountPolicy::Lenient).is_err());
+
+    let mut padded = cue_chunk(2, 2);
+    padded.push(0);
+    assert!(try_parse_cue_points(&padded, CueCountPolicy::Lenient).is_err());
+    assert!(try_parse_cue_points(&padded, CueCountPolicy::Strict).is_err());
+    assert_eq!(parse_cue_points(&padded).len(), 2);
+}
+
+#[test]
+fn peak_envelope() {
+    let header = PeakEnvelopeChunk {
+        version: 1,
+        format: PeakEnvelopeChunk::FORMAT_16_BIT,
+        points_per_value: 2,
+        block_size: 256,
+        peak_channels: 1,
+        num_peak_frames: 3,
+        pos_peak_of_peaks: 4,
+        offset_to_peaks: 128,
+        timestamp: *b"2024:01:01:12:00:00:00\0\0\0\0\0\0",
+        reserved: [0u8; 60],
+    };
+    let peaks = [1u8, 0, 2, 0, 3, 0, 4, 0, 5, 0, 6, 0];
+
+    let mut body = Vec::from(header.as_bytes());
+    body.extend_from_slice(&peaks);
+
+    let parsed = PeakEnvelopeChunk::parse(&body).unwrap();
+    assert_eq!(parsed, header);
+    assert_eq!(parsed.peak_frames(&body).unwrap(), &peaks[..]);
+    assert!(parsed.peak_frames(&body[..body.len() - 1]).is_err());
+    assert!(PeakEnvelopeChunk::parse(&body[..119]).is_err());
+
+    let unknown = PeakEnvelopeChunk {
+        format: 3,
+        ..header
+    };
+    assert!(unknown.peak_frames(&body).is_err());
+}
+
+#[test]
+fn borrowed_chunks() {
+    let cue = CuePoint::from_sample_offset(1, 8);
+    let mut cue_bytes = vec![1u8, 0, 0, 0];
+    cue_bytes.extend_from_slice(&cue.as_bytes());
+    let data_head = ChunkHead {
+        tag: *b"data",
+        size: 5,
+    };
+    let cue_head = ChunkHead {
+        tag: *b"cue ",
+        size: cue_bytes.len() as u32,
+    };
+    let bytes = build_wave(&[
+        (data_head, Some(&[1, 2, 3, 4, 5, 0])),
+        (cue_head, Some(&cue_bytes)),
+    ]);
+
+    let mut reader = ChunkReader::from_bytes(&bytes[..]).unwrap();
+    let (head, payload) = reader.read_next_chunk_ref(None).unwrap().unwrap();
+    assert_eq!(head, data_head);
+    assert_eq!(payload, &[1, 2, 3, 4, 5]);
+
+    let (_, payload) = reader.read_next_chunk_ref(None).unwrap().unwrap();
+    assert_eq!(parse_cue_points(payload), vec![cue]);
+    assert!(reader.read_next_chunk_ref(None).unwrap().is_none());
+
+    let truncated = &bytes[..bytes.len() - 1];
+    let mut reader = ChunkReader::from_bytes(truncated).unwrap();
+    assert!(reader.read_next_chunk_ref(Some(*b"cue ")).is_err());
+}
+
+#[test]
+fn chunk_inventory() {
+    let chunk = |tag: &[u8; 4], size| ChunkHead { tag: *tag, size };
+    let bytes = build_wave(&[
+        (chunk(b"fmt ", 16), None),
+        (chunk(b"LIST", 11), None),
+        (chunk(b"data", 3), None),
+        (chunk(b"LIST", 4), None),
+    ]);
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let summary = reader.summary().unwrap();
+    assert_eq!(summary.order, vec![*b"fmt ", *b"LIST", *b"data", *b"LIST"]);
+    assert_eq!(summary.tags[b"LIST"], (2, 15));
+    assert_eq!(summary.tags[b"data"], (1, 3));
+    assert!(summary.has_fmt() && summary.has_data());
+    assert!(summary.fmt_before_data());
+
+    let bytes =
+        build_wave(&[(chunk(b"data", 3), None), (chunk(b"fmt ", 16), None)]);
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert!(!reader.summary().unwrap().fmt_before_data());
+}
+
+#[test]
+fn region_labeled_text() {
+    let ltxt = LabeledText::from_region(5, 1000, 1500, "verse").unwrap();
+    assert_eq!(ltxt.cue_id, 5);
+    assert_eq!(ltxt.sample_length, 500);
+    assert_eq!(ltxt.purpose_id, *b"rgn ");
+    assert_eq!(ltxt.text, "verse");
+    assert!(LabeledText::from_region(5, 1500, 1000, "").is_err());
+}
+
+#[test]
+fn label_sizes_match_encoded_text() {
+    let mut ltxt = LabeledText::from_cue_length(1, 10);
+    ltxt.text = String::from("caf\u{e9}");
+    assert_eq!(ltxt.encoded_text_bytes().len(), 5);
+    assert_eq!(ltxt.as_bytes().len(), 25);
+
+    let mut bytes = build_wave(&[]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.append_label_chunk(&[ltxt.clone()]).unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let (_, list) = reader.read_next_chunk(Some(*b"LIST")).unwrap().unwrap();
+    assert_eq!(list.len(), 4 + CHUNK_HEAD_SZ + 26);
+    assert_eq!(extract_labeled_text_from_list(&list), vec![ltxt]);
+}
+
+#[test]
+fn build_wave_pads_payloads() {
+    let head = ChunkHead {
+        tag: *b"junk",
+        size: 3,
+    };
+    let bytes = build_wave(&[(head, Some(&[1, 2, 3])), (head, None)]);
+    assert_eq!(bytes.len(), 12 + 2 * (CHUNK_HEAD_SZ + 4));
+    assert_eq!(&bytes[20..24], &[1, 2, 3, 0]);
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(reader.wave_len(), bytes.len() as u64);
+    assert_eq!(reader.summary().unwrap().tags[b"junk"], (2, 6));
+}
+
+#[test]
+fn comparable_errors() {
+    use std::error::Error as _;
+
+    let not_wave =
+        ChunkReader::new(io::Cursor::new(&b"FORM\x04\0\0\0WAVE"[..]))
+            .unwrap_err();
+    assert_eq!(
+        not_wave.kind(),
+        ErrorKind::Wave(String::from("Not a WAVE file"))
+    );
+    assert!(not_wave.source().is_none());
+
+    let truncated =
+        ChunkReader::new(io::Cursor::new(&b"RIFF"[..])).unwrap_err();
+    let kinds = [truncated.kind(), truncated.kind()];
+    assert!(matches!(
+        kinds[0],
+        ErrorKind::Io(io::ErrorKind::UnexpectedEof, _)
+    ));
+    assert_eq!(kinds[0], kinds[1]);
+    assert!(truncated.source().is_some());
+    assert!(matches!(truncated, Error::Io(_)));
+}
+
+#[test]
+fn cue_index_lookup() {
+    let cues = [
+        CuePoint::from_sample_offset(1, 300),
+        CuePoint::from_sample_offset(2, 100),
+        CuePoint::from_sample_offset(3, 200),
+        CuePoint::from_sample_offset(4, 200),
+    ];
+    let index = CueIndex::new(&cues);
+
+    assert_eq!(
+        index.cues().iter().map(|c| c.id).collect::<Vec<_>>(),
+        vec![2, 3, 4, 1]
+    );
+    assert_eq!(index.at_or_before(99), None);
+    assert_eq!(index.at_or_before(100).unwrap().id, 2);
+    assert_eq!(index.at_or_before(250).unwrap().id, 4);
+    assert_eq!(index.at_or_before(u32::MAX).unwrap().id, 1);
+
+    assert_eq!(index.range(150..300), &[cues[2], cues[3]]);
+    assert_eq!(index.range(100..101), &[cues[1]]);
+    assert!(index.range(300..300).is_empty());
+    let (start, end) = (300, 100);
+    assert!(index.range(start..end).is_empty());
+}
+
+#[test]
+fn insert_before_data() {
+    let fmt_bytes = pcm_format_bytes(1, 11025, 8);
+    let fmt_head = ChunkHead {
+        tag: *b"fmt ",
+        size: fmt_bytes.len() as u32,
+    };
+    let samples = (0..=255).cycle().take(0x10005).collect::<Vec<u8>>();
+    let data_head = ChunkHead {
+        tag: *b"data",
+        size: samples.len() as u32,
+    };
+    let mut bytes = build_wave(&[
+        (fmt_head, Some(&fmt_bytes)),
+        (data_head, Some(&samples)),
+    ]);
+
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer
+        .insert_chunk_before(*b"data", *b"DISP", b"\x01\0\0\0hi!")
+        .unwrap();
+    assert!(writer.insert_chunk_before(*b"smpl", *b"junk", &[]).is_err());
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(reader.wave_len(), bytes.len() as u64);
+    assert_eq!(
+        reader.summary().unwrap().order,
+        vec![*b"fmt ", *b"DISP", *b"data"]
+    );
+    let (_, disp) = reader.read_next_chunk(Some(*b"DISP")).unwrap().unwrap();
+    assert_eq!(disp, b"\x01\0\0\0hi!");
+    let (_, data) = reader.read_next_chunk(None).unwrap().unwrap();
+    assert_eq!(data, samples);
+}
+
+#[test]
+fn strict_open() {
+    let fmt_head = ChunkHead {
+        tag: *b"fmt ",
+        size: 16,
+    };
+    let data_head = ChunkHead {
+        tag: *b"data",
+        size: 4,
+    };
+    let open_strict = |bytes: &[u8]| {
+        ChunkReader::new_strict(io::Cursor::new(bytes.to_vec())).map(|_| ())
+    };
+
+    assert!(
+        open_strict(&build_wave(&[(fmt_head, None), (data_head, None)]))
+            .is_ok()
+    );
+    assert!(
+        open_strict(&build_wave(&[(data_head, None), (fmt_head, None)]))
+            .is_err()
+    );
+    assert!(open_strict(&build_wave(&[(fmt_head, None)])).is_err());
+    assert!(open_strict(&build_wave(&[(data_head, None)])).is_err());
+
+    let mut overrun = build_wave(&[(fmt_head, None), (data_head, None)]);
+    overrun[40..44].copy_from_slice(&5u32.to_le_bytes());
+    overrun.extend_from_slice(&[0, 0]);
+    assert!(ChunkReader::new(io::Cursor::new(&overrun[..])).is_ok());
+    assert!(open_strict(&overrun).is_err());
+
+    let mut reader = ChunkReader::new_strict(io::Cursor::new(build_wave(&[
+        (fmt_head, None),
+        (data_head, None),
+    ])))
+    .unwrap();
+    assert_eq!(reader.read_next_chunk(None).unwrap().unwrap().0, *b"fmt ");
+}
+
+// Head, body, and padding of every chunk, exactly as stored
+fn raw_chunks(bytes: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+    let mut slice = &bytes[12..];
+    let mut chunks = vec![];
+
+    while !slice.is_empty() {
+        let head = ChunkHead::parse(&mut &slice[..]).unwrap();
+        let len = CHUNK_HEAD_SZ + head.padded_size() as usize;
+        chunks.push((head.tag, Vec::from(&slice[..len])));
+        slice = &slice[len..];
+    }
+
+    chunks
+}
+
+#[test]
+fn rebuild_preserves_unknown_chunks() {
+    let chunk = |tag: &[u8; 4], size| ChunkHead { tag: *tag, size };
+    let mut bext = vec![0x42u8; 601];
+    bext.push(0x7F);
+    let umid = (0..64).collect::<Vec<u8>>();
+    let samples = (0..=255).cycle().take(0x20002).collect::<Vec<u8>>();
+    let mut axml = Vec::from(&b"<axml></axml>"[..]);
+    axml.push(0xAB);
+    let mut cue_bytes = vec![1u8, 0, 0, 0];
+    cue_bytes.extend_from_slice(&CuePoint::from_sample_offset(1, 9).as_bytes());
+
+    let original = build_wave(&[
+        (chunk(b"fmt ", 16), None),
+        (chunk(b"bext", 601), Some(&bext)),
+        (chunk(b"umid", 64), Some(&umid)),
+        (chunk(b"data", 0x20001), Some(&samples)),
+        (chunk(b"cue ", 28), Some(&cue_bytes)),
+        (chunk(b"axml", 13), Some(&axml)),
+        (chunk(b"LIST", 4), Some(b"adtl")),
+    ]);
+    let untouched = raw_chunks(&original)
+        .into_iter()
+        .filter(|(tag, _)| tag != b"cue " && tag != b"LIST")
+        .collect::<Vec<_>>();
+
+    let mut bytes = original.clone();
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+
+    let mut bigger_cues = cue_bytes.clone();
+    bigger_cues[0] = 2;
+    bigger_cues
+        .extend_from_slice(&CuePoint::from_sample_offset(2, 99).as_bytes());
+    assert!(writer.replace_chunk(*b"cue ", &bigger_cues).unwrap());
+    assert!(writer.remove_chunk(*b"LIST").unwrap());
+    assert!(!writer.remove_chunk(*b"LIST").unwrap());
+    assert!(!writer.replace_chunk(*b"smpl", &[]).unwrap());
+    writer.restore_cursor().unwrap();
+
+    let rebuilt = raw_chunks(&bytes);
+    assert_eq!(
+        rebuilt.iter().map(|(tag, _)| *tag).collect::<Vec<_>>(),
+        vec![*b"fmt ", *b"bext", *b"umid", *b"data", *b"cue ", *b"axml"]
+    );
+    assert_eq!(
+        rebuilt
+            .iter()
+            .filter(|(tag, _)| tag != b"cue ")
+            .cloned()
+            .collect::<Vec<_>>(),
+        untouched
+    );
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(reader.wave_len(), bytes.len() as u64);
+    let (_, cues) = reader.read_next_chunk(Some(*b"cue ")).unwrap().unwrap();
+    assert_eq!(parse_cue_points(&cues).len(), 2);
+
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    assert!(writer.remove_chunk(*b"cue ").unwrap());
+    assert!(writer.remove_chunk(*b"fmt ").unwrap());
+    writer.restore_cursor().unwrap();
+    assert_eq!(raw_chunks(&bytes), untouched[1..]);
+}
+
+#[test]
+fn data_digest_survives_appends() {
+    use std::hash::Hasher;
+
+    let digest = |bytes: &[u8]| {
+        let mut reader = ChunkReader::new(io::Cursor::new(bytes)).unwrap();
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        reader.hash_data(&mut hasher).unwrap();
+        hasher.finish()
+    };
+
+    let samples = (0..=255).cycle().take(0x18001).collect::<Vec<u8>>();
+    let data_head = ChunkHead {
+        tag: *b"data",
+        size: samples.len() as u32,
+    };
+    let mut bytes = build_wave(&[(data_head, Some(&samples))]);
+    let before = digest(&bytes);
+
+    let mut expected = std::collections::hash_map::DefaultHasher::new();
+    expected.write(&samples);
+    assert_eq!(before, expected.finish());
+
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer
+        .append_cue_chunk(&[CuePoint::from_sample_offset(1, 5)])
+        .unwrap();
+    assert_eq!(digest(&bytes), before);
+
+    bytes[100] ^= 1;
+    assert_ne!(digest(&bytes), before);
+
+    let no_data = build_wave(&[]);
+    let mut reader = ChunkReader::new(io::Cursor::new(&no_data[..])).unwrap();
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    assert!(reader.hash_data(&mut hasher).is_err());
+
+    // RF64, with the real data size in the ds64 chunk
+    let ds64 = Ds64Chunk {
+        riff_size: 4 + 36 + 12 + 16,
+        data_size: 4,
+        ..Ds64Chunk::default()
+    };
+    let mut rf64 = Vec::from(&b"RF64\xff\xff\xff\xffWAVEds64\x1c\0\0\0"[..]);
+    rf64.extend_from_slice(&ds64.as_bytes());
+    rf64.extend_from_slice(b"data\xff\xff\xff\xff");
+    rf64.extend_from_slice(&samples[..4]);
+    rf64.extend_from_slice(b"note\x08\0\0\0");
+    rf64.extend_from_slice(&[0xEE; 8]);
+
+    let mut expected = std::collections::hash_map::DefaultHasher::new();
+    expected.write(&samples[..4]);
+    assert_eq!(digest(&rf64), expected.finish());
+}
+
+#[test]
+fn cue_offset_sanity() {
+    let sample_count = 1000;
+
+    let in_range = CuePoint::from_sample_offset(1, 1000);
+    assert!(in_range.validate_against(sample_count).is_ok());
+    assert!(!in_range.looks_like_byte_offset(4, sample_count));
+
+    let bytes = CuePoint::from_sample_offset(2, 3000);
+    assert!(bytes.validate_against(sample_count).is_err());
+    assert!(bytes.looks_like_byte_offset(4, sample_count));
+    assert!(!bytes.looks_like_byte_offset(1, sample_count));
+
+    let misaligned = CuePoint::from_sample_offset(3, 3001);
+    assert!(!misaligned.looks_like_byte_offset(4, sample_count));
+
+    let far = CuePoint::from_sample_offset(4, 8000);
+    assert!(!far.looks_like_byte_offset(4, sample_count));
+}
+
+#[test]
+fn strip_all_metadata() {
+    let chunk = |tag: &[u8; 4], size| (ChunkHead { tag: *tag, size }, None);
+    let original = build_wave(&[
+        chunk(b"fmt ", 16),
+        chunk(b"bext", 602),
+        chunk(b"fact", 4),
+        chunk(b"LIST", 31),
+        chunk(b"data", 9),
+        chunk(b"cue ", 28),
+        chunk(b"iXML", 40),
+    ]);
+
+    let mut bytes = original.clone();
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    assert_eq!(writer.strip_metadata(&[]).unwrap(), 4);
+    assert_eq!(writer.strip_metadata(&[]).unwrap(), 0);
+    writer.restore_cursor().unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(reader.wave_len(), bytes.len() as u64);
+    assert_eq!(
+        reader.summary().unwrap().order,
+        vec![*b"fmt ", *b"fact", *b"data"]
+    );
+
+    let mut bytes = original;
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    assert_eq!(writer.strip_metadata(&[*b"data", *b"cue "]).unwrap(), 5);
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(reader.summary().unwrap().order, vec![*b"data", *b"cue "]);
+}
+
+#[test]
+fn read_non_wave_form() {
+    let chunk = |tag: &[u8; 4], size| (ChunkHead { tag: *tag, size }, None);
+    let mut bytes = build_wave(&[chunk(b"data", 6)]);
+    bytes[8..12].copy_from_slice(b"RMID");
+
+    let not_wave = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap_err();
+    assert_eq!(
+        not_wave.kind(),
+        ErrorKind::Wave(String::from("Not a WAVE file"))
+    );
+    assert!(
+        ChunkReader::new_form(io::Cursor::new(&bytes[..]), Some(*b"AVI "))
+            .is_err()
+    );
+
+    let mut writer =
+        ChunkWriter::new_form(io::Cursor::new(&mut bytes), Some(*b"RMID"))
+            .unwrap();
+    assert_eq!(writer.form_id(), *b"RMID");
+    writer.append_chunk(*b"DISP", &[1, 0, 0, 0, b'x']).unwrap();
+    writer.restore_cursor().unwrap();
+
+    let mut reader =
+        ChunkReader::new_form(io::Cursor::new(&bytes[..]), None).unwrap();
+    assert_eq!(reader.form_id(), *b"RMID");
+    assert_eq!(reader.wave_len(), bytes.len() as u64);
+    assert_eq!(reader.summary().unwrap().order, vec![*b"data", *b"DISP"]);
+    let (_, body) = reader.read_next_chunk(Some(*b"DISP")).unwrap().unwrap();
+    assert_eq!(body, [1, 0, 0, 0, b'x']);
+}
+
+#[test]
+fn read_and_write_id3() {
+    let chunk = |tag: &[u8; 4], size| (ChunkHead { tag: *tag, size }, None);
+    let mut bytes = build_wave(&[chunk(b"fmt ", 16), chunk(b"data", 6)]);
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(reader.read_id3().unwrap(), None);
+
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.write_id3(b"ID3\x03\x00tag").unwrap();
+    writer.write_id3(b"ID3\x04\x00").unwrap();
+    writer.restore_cursor().unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(
+        reader.summary().unwrap().order,
+        vec![*b"fmt ", *b"data", *b"id3 "]
+    );
+    assert_eq!(reader.read_id3().unwrap().unwrap(), b"ID3\x04\x00");
+
+    let mut bytes = build_wave(&[
+        chunk(b"ID3 ", 3),
+        (
+            ChunkHead {
+                tag: *b"data",
+                size: 2,
+            },
+            Some(b"ab"),
+        ),
+    ]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.write_id3(b"ID3").unwrap();
+    writer.restore_cursor().unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(reader.summary().unwrap().order, vec![*b"ID3 ", *b"data"]);
+    assert_eq!(reader.read_id3().unwrap().unwrap(), b"ID3");
+    assert_eq!(
+        reader.read_next_chunk(Some(*b"data")).unwrap().unwrap().1,
+        b"ab"
+    );
+}
+
+#[test]
+fn chunk_definitions_match_appends() {
+    let cues = [
+        CuePoint::from_sample_offset(1, 10),
+        CuePoint::from_sample_offset(2, 20),
+    ];
+    let labels = [
+        LabeledText::from_cue_length(1, 5),
+        LabeledText::from_region(2, 20, 40, "odd").unwrap(),
+    ];
+
+    let (tag, payload) = cue_chunk(&cues).unwrap();
+    assert_eq!(tag, *b"cue ");
+    assert_eq!(parse_cue_points(&payload), cues);
+
+    let (tag, payload) = label_chunk(&labels).unwrap();
+    assert_eq!(tag, *b"LIST");
+    assert_eq!(payload.len() & 1, 0);
+    assert_eq!(extract_labeled_text_from_list(&payload), labels);
+
+    let chunk = |tag: &[u8; 4], size| (ChunkHead { tag: *tag, size }, None);
+    let mut appended = build_wave(&[chunk(b"data", 4)]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut appended)).unwrap();
+    writer.append_cue_chunk(&cues).unwrap();
+    writer.append_label_chunk(&labels).unwrap();
+
+    let mut composed = build_wave(&[chunk(b"data", 4)]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut composed)).unwrap();
+    for definition in [cue_chunk(&cues), label_chunk(&labels)] {
+        let (tag, payload) = definition.unwrap();
+        writer.append_chunk(tag, &payload).unwrap();
+    }
+
+    assert_eq!(appended, composed);
+}
+
+#[test]
+fn reindex_cue_positions() {
+    let mut cues = [
+        CuePoint::from_sample_offset(1, 300).with_position(7),
+        CuePoint::from_sample_offset(2, 100),
+        CuePoint::from_sample_offset(3, 300),
+        CuePoint::from_sample_offset(4, 0),
+    ];
+    assert_eq!(cues[0].position, 7);
+    assert_eq!(cues[0].sample_offset, 300);
+
+    reindex_positions(&mut cues);
+    let ids = cues.map(|cue| (cue.id, cue.position));
+    assert_eq!(ids, [(1, 2), (2, 1), (3, 3), (4, 0)]);
+}
+
+#[test]
+fn format_channel_positions() {
+    let mut bytes = pcm_format_bytes(6, 48000, 24);
+    bytes[0..2].copy_from_slice(&FORMAT_EXTENSIBLE.to_le_bytes());
+    bytes.extend_from_slice(&22u16.to_le_bytes());
+    bytes.extend_from_slice(&24u16.to_le_bytes());
+    bytes.extend_from_slice(&0x3Fu32.to_le_bytes());
+    bytes.extend_from_slice(&[1, 0, 0, 0, 0, 0, 0x10, 0]);
+    bytes.extend_from_slice(&[0x80, 0, 0, 0xAA, 0, 0x38, 0x9B, 0x71]);
+
+    let format = FormatChunk::parse(&bytes).unwrap();
+    assert_eq!(format.channels, 6);
+    assert_eq!(format.extensible.unwrap().valid_bits_per_sample, 24);
+    assert_eq!(format.as_bytes(), bytes);
+    assert_eq!(
+        format.channel_positions(),
+        [
+            SpeakerPosition::FrontLeft,
+            SpeakerPosition::FrontRight,
+            SpeakerPosition::FrontCenter,
+            SpeakerPosition::LowFrequency,
+            SpeakerPosition::BackLeft,
+            SpeakerPosition::BackRight,
+        ]
+    );
+
+    assert!(FormatChunk::parse(&bytes[..30]).is_err());
+
+    let stereo = FormatChunk::parse(&pcm_format_bytes(2, 44100, 16)).unwrap();
+    assert_eq!(stereo.extensible, None);
+    assert!(stereo.channel_positions().is_empty());
+    assert_eq!(SpeakerPosition::TopBackRight.mask_bit(), 0x20000);
+}
+
+#[test]
+fn append_markers_together() {
+    let chunk = |tag: &[u8; 4], size| (ChunkHead { tag: *tag, size }, None);
+    let original = build_wave(&[chunk(b"fmt ", 16), chunk(b"data", 40)]);
+    let markers = [
+        Marker {
+            cue: CuePoint::from_sample_offset(1, 2),
+            label: Some(String::from("intro")),
+            region_length: None,
+            purpose_id: None,
+        },
+        Marker {
+            cue: CuePoint::from_sample_offset(2, 8),
+            label: None,
+            region_length: None,
+            purpose_id: None,
+        },
+    ];
+
+    let mut bytes = original.clone();
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    let duplicate = [markers[0].clone(), markers[0].clone()];
+    assert!(writer.append_markers(&duplicate).is_err());
+    writer.restore_cursor().unwrap();
+    assert_eq!(bytes, original);
+
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.append_markers(&markers).unwrap();
+    writer.append_markers(&[]).unwrap();
+    writer.restore_cursor().unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(reader.wave_len(), bytes.len() as u64);
+    assert_eq!(
+        reader.summary().unwrap().order,
+        vec![*b"fmt ", *b"data", *b"cue ", *b"LIST"]
+    );
+    assert_eq!(reader.read_markers().unwrap(), markers);
+}
+
+#[test]
+fn padding_helpers() {
+    assert!(!needs_pad(0));
+    assert!(needs_pad(3));
+    assert_eq!(pad_len(4), 0);
+    assert_eq!(pad_len(u64::from(u32::MAX)), 1);
+    assert_eq!(pad_size_16(5), Some(6));
+    assert_eq!(pad_size_16(6), Some(6));
+    assert_eq!(pad_size_16(usize::MAX), None);
+}
+
+#[test]
+fn read_regions_from_labels() {
+    let cues = [
+        CuePoint::from_sample_offset(1, 100),
+        CuePoint::from_sample_offset(2, 200),
+        CuePoint::from_sample_offset(3, 300),
+    ];
+    let mut point = LabeledText::from_cue_length(1, 0);
+    point.text = String::from("point");
+    let labels = [
+        point,
+        LabeledText::from_region(2, 200, 250, "verse").unwrap(),
+        LabeledText::from_region(9, 0, 10, "orphan").unwrap(),
+    ];
+
+    let mut bytes = build_wave(&[]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.append_cue_chunk(&cues).unwrap();
+    writer.append_label_chunk(&labels).unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(
+        reader.read_regions().unwrap(),
+        [Region {
+            start: 200,
+            length: 50,
+            name: String::from("verse"),
+        }]
+    );
+}
+
+#[test]
+fn visit_chunks() {
+    use io::Read;
+
+    let fmt_bytes = pcm_format_bytes(1, 8000, 8);
+    let bytes = build_wave(&[
+        (
+            ChunkHead {
+                tag: *b"fmt ",
+                size: 16,
+            },
+            Some(&fmt_bytes),
+        ),
+        (
+            ChunkHead {
+                tag: *b"data",
+                size: 5,
+            },
+            Some(b"abcde"),
+        ),
+        (
+            ChunkHead {
+                tag: *b"cue ",
+                size: 4,
+            },
+            None,
+        ),
+        (
+            ChunkHead {
+                tag: *b"LIST",
+                size: 4,
+            },
+            Some(b"adtl"),
+        ),
+    ]);
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+
+    let mut data_prefix = [0u8; 2];
+    let mut tags = vec![];
+    let kept = reader
+        .visit(&mut |head, cursor| {
+            tags.push(head.tag);
+
+            match &head.tag {
+                b"fmt " => {
+                    let mut body = vec![0u8; head.size as usize];
+                    cursor.read_exact(&mut body)?;
+                    Ok(ChunkAction::Read(body))
+                }
+                b"data" => {
+                    cursor.read_exact(&mut data_prefix)?;
+                    Ok(ChunkAction::Skip)
+                }
+                b"cue " => Ok(ChunkAction::Stop),
+                _ => Ok(ChunkAction::Skip),
+            }
+        })
+        .unwrap();
+
+    assert_eq!(kept, vec![(*b"fmt ", fmt_bytes)]);
+    assert_eq!(data_prefix, *b"ab");
+    assert_eq!(tags, vec![*b"fmt ", *b"data", *b"cue "]);
+    assert_eq!(reader.read_next_chunk(None).unwrap().unwrap().0, *b"fmt ");
+
+    let failed = reader.visit(&mut |_, _| Err(Error::wave("handler failed")));
+    assert!(failed.is_err());
+}
+
+#[test]
+fn retag_chunk_in_place() {
+    let cue = [CuePoint::from_sample_offset(4, 2)];
+    let (_, cue_payload) = cue_chunk(&cue).unwrap();
+    let mut bytes = build_wave(&[
+        (
+            ChunkHead {
+                tag: *b"data",
+                size: 3,
+            },
+            None,
+        ),
+        (
+            ChunkHead {
+                tag: *b"CUE ",
+                size: 28,
+            },
+            Some(&cue_payload),
+        ),
+    ]);
+    let len = bytes.len();
+
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    assert!(writer.retag_chunk(*b"CUE ", [b'c', 0, b'e', b' ']).is_err());
+    assert!(writer.retag_chunk(*b"CUE ", *b"cue ").unwrap());
+    assert!(!writer.retag_chunk(*b"CUE ", *b"cue ").unwrap());
+    writer.restore_cursor().unwrap();
+    assert_eq!(bytes.len(), len);
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(reader.summary().unwrap().order, vec![*b"data", *b"cue "]);
+    let (_, body) = reader.read_next_chunk(Some(*b"cue ")).unwrap().unwrap();
+    assert_eq!(parse_cue_points(&body), cue);
+}
+
+#[test]
+fn normalize_zero_based_cue_ids() {
+    let mut cues = vec![
+        CuePoint::from_sample_offset(2, 20),
+        CuePoint::from_sample_offset(0, 0),
+        CuePoint::from_sample_offset(1, 10),
+    ];
+    let mut labels = vec![
+        LabeledText::from_cue_length(2, 5),
+        LabeledText::from_cue_length(0, 0),
+    ];
+
+    normalize_cue_ids(&mut cues, &mut labels, 1);
+    assert_eq!(cues.iter().map(|cue| cue.id).collect::<Vec<_>>(), [3, 1, 2]);
+    assert_eq!(Marker::join(cues[0], &labels).region_length, Some(5));
+    assert_eq!(labels[1].cue_id, 1);
+
+    normalize_cue_ids(&mut cues, &mut labels, 0);
+    assert_eq!(cues.iter().map(|cue| cue.id).collect::<Vec<_>>(), [2, 0, 1]);
+    assert_eq!(labels[0].cue_id, 2);
+
+    let mut labels_only = labels.clone();
+    normalize_cue_ids(&mut [], &mut labels_only, 5);
+    assert_eq!(labels_only, labels);
+}
+
+#[test]
+fn batch_edits_match_incremental_edits() {
+    let chunk = |tag: &[u8; 4], size| (ChunkHead { tag: *tag, size }, None);
+    let original = build_wave(&[
+        chunk(b"fmt ", 16),
+        chunk(b"LIST", 13),
+        chunk(b"iXML", 41),
+        chunk(b"data", 9),
+    ]);
+    let cues = [CuePoint::from_sample_offset(1, 3)];
+    let labels = [LabeledText::from_region(1, 3, 6, "hit").unwrap()];
+
+    let mut incremental = original.clone();
+    let mut writer =
+        ChunkWriter::new(io::Cursor::new(&mut incremental)).unwrap();
+    writer.append_cue_chunk(&cues).unwrap();
+    writer.remove_chunk(*b"LIST").unwrap();
+    writer.replace_chunk(*b"iXML", b"<BWFXML/>").unwrap();
+    writer.append_label_chunk(&labels).unwrap();
+
+    let mut batched = original.clone();
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut batched)).unwrap();
+    writer
+        .edit()
+        .append_cue_chunk(&cues)
+        .remove_chunk(*b"LIST")
+        .replace_chunk(*b"iXML", b"<BWFXML/>")
+        .append_label_chunk(&labels)
+        .append_cue_chunk(&[])
+        .remove_chunk(*b"bext")
+        .commit()
+        .unwrap();
+
+    assert_eq!(batched, incremental);
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&batched[..])).unwrap();
+    assert_eq!(
+        reader.summary().unwrap().order,
+        vec![*b"fmt ", *b"iXML", *b"data", *b"cue ", *b"LIST"]
+    );
+}
+
+#[test]
+fn describe_chunk_tree() {
+    let fmt_bytes = pcm_format_bytes(2, 44100, 16);
+    let (_, cue_payload) =
+        cue_chunk(&[CuePoint::from_sample_offset(1, 7)]).unwrap();
+    let mut ltxt = LabeledText::from_cue_length(1, 3);
+    ltxt.text = String::from("say \"hi\"");
+    let (_, list_payload) = label_chunk(&[ltxt]).unwrap();
+    let list_size = list_payload.len() as u32;
+
+    let bytes = build_wave(&[
+        (
+            ChunkHead {
+                tag: *b"fmt ",
+                size: 16,
+            },
+            Some(&fmt_bytes),
+        ),
+        (
+            ChunkHead {
+                tag: *b"cue ",
+                size: 28,
+            },
+            Some(&cue_payload),
+        ),
+        (
+            ChunkHead {
+                tag: *b"LIST",
+                size: list_size,
+            },
+            Some(&list_payload),
+        ),
+        (
+            ChunkHead {
+                tag: *b"data",
+                size: 3,
+            },
+            None,
+        ),
+    ]);
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let description = reader.describe().unwrap();
+
+    assert!(description.starts_with("{\n  \"form_id\": \"WAVE\",\n"));
+    assert!(description.contains(
+        "{\"tag\": \"fmt \", \"name\": \"Format\", \"offset\": 12, \
+         \"size\": 16, \"fmt\": {\"format_tag\": 1, \"channels\": 2, \
+         \"sample_rate\": 44100, "
+    ));
+    assert!(description.contains(
+        "\"cue_points\": [{\"id\": 1, \"position\": 0, \"data_tag\": \
+         \"data\", \"sample_offset\": 7}]"
+    ));
+    assert!(description.contains("\"text\": \"say \\\"hi\\\"\"}]"));
+    assert!(description.contains(
+        "{\"tag\": \"data\", \"name\": \"Samples\", \"offset\": 120, \
+         \"size\": 3, \"unparsed\": 3}"
+    ));
+    assert_eq!(reader.read_next_chunk(None).unwrap().unwrap().0, *b"fmt ");
+
+    let empty = build_wave(&[]);
+    let mut reader = ChunkReader::new(io::Cursor::new(&empty[..])).unwrap();
+    assert_eq!(
+        reader.describe().unwrap(),
+        "{\n  \"form_id\": \"WAVE\",\n  \"riff_size\": 4,\n  \
+         \"chunks\": []\n}\n"
+    );
+}
+
+#[test]
+fn max_chunk_size_guard() {
+    let mut bytes = build_wave(&[
+        (
+            ChunkHead {
+                tag: *b"iXML",
+                size: 6,
+            },
+            None,
+        ),
+        (
+            ChunkHead {
+                tag: *b"data",
+                size: 2,
+            },
+            Some(b"ab"),
+        ),
+    ]);
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(reader.max_chunk_size(), DEFAULT_MAX_CHUNK_SIZE);
+    reader.set_max_chunk_size(4);
+    assert!(reader.read_next_chunk(None).is_err());
+    reader.reset().unwrap();
+    let (_, body) = reader.read_next_chunk(Some(*b"data")).unwrap().unwrap();
+    assert_eq!(body, b"ab");
+
+    // A hostile size is rejected before allocating
+    bytes[16..20].copy_from_slice(&u32::MAX.to_le_bytes());
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert!(reader.read_next_chunk(None).is_err());
+
+    let reader = ChunkReader::new_unbounded(io::Cursor::new(&bytes[..]));
+    assert_eq!(reader.unwrap().max_chunk_size(), u32::MAX);
+}
+
+#[test]
+fn diff_marker_sets() {
+    let marker = |id, offset, label: Option<&str>| Marker {
+        cue: CuePoint::from_sample_offset(id, offset),
+        label: label.map(String::from),
+        region_length: None,
+        purpose_id: None,
+    };
+
+    let before = [
+        marker(1, 100, Some("intro")),
+        marker(2, 200, None),
+        marker(3, 300, Some("verse")),
+    ];
+    let after = [
+        marker(3, 250, Some("chorus")),
+        marker(1, 100, Some("intro")),
+        marker(4, 400, None),
+    ];
+
+    let diff = diff_markers(&before, &after);
+    assert_eq!(diff.added, [marker(4, 400, None)]);
+    assert_eq!(diff.removed, [marker(2, 200, None)]);
+    assert_eq!(diff.changed.len(), 1);
+    assert_eq!(diff.changed[0].cue_id(), 3);
+    assert_eq!(diff.changed[0].sample_delta(), -50);
+    assert!(diff.changed[0].label_changed());
+    assert!(!diff.changed[0].region_length_changed());
+
+    assert_eq!(
+        diff.to_string(),
+        "- cue 2 at 200\n+ cue 4 at 400\n~ cue 3 moved -50 samples to 250 \
+         label \"verse\" -> \"chorus\"\n"
+    );
+    assert!(diff_markers(&before, &before).is_empty());
+
+    let unnamed = [Marker {
+        region_length: Some(48),
+        ..marker(3, 300, None)
+    }];
+    assert_eq!(
+        diff_markers(&before[2..], &unnamed).to_string(),
+        "~ cue 3 region length (none) -> 48 label \"verse\" -> (none)\n"
+    );
+    assert_eq!(
+        diff_markers(&before[2..], &[]).to_string(),
+        "- cue 3 at 300 \"verse\"\n"
+    );
+}
+
+#[test]
+fn append_named_markers_assigns_ids() {
+    let mut bytes = build_wave(&[(
+        ChunkHead {
+            tag: *b"data",
+            size: 8,
+        },
+        None,
+    )]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer
+        .append_named_markers(&[
+            (40, String::from("drop")),
+            (10, String::new()),
+            (20, String::from("build")),
+        ])
+        .unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let markers = reader
+        .read_markers()
+        .unwrap()
+        .into_iter()
+        .map(|marker| (marker.cue.id, marker.cue.sample_offset, marker.label))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        markers,
+        [
+            (1, 40, Some(String::from("drop"))),
+            (2, 10, None),
+            (3, 20, Some(String::from("build"))),
+        ]
+    );
+}
+
+#[test]
+fn append_labl_names() {
+    let labels = [
+        Label {
+            cue_id: 1,
+            text: String::from("Verse"),
+        },
+        Label {
+            cue_id: 2,
+            text: String::from("Bridge"),
+        },
+    ];
+    assert_eq!(labels[0].as_bytes(), b"\x01\0\0\0Verse\0");
+    assert_eq!(
+        Label::try_from_bytes(b"\x02\0\0\0Bridge\0\0").unwrap(),
+        labels[1]
+    );
+    assert!(Label::try_from_bytes(&[1, 0]).is_err());
+
+    let mut bytes = build_wave(&[(
+        ChunkHead {
+            tag: *b"data",
+            size: 2,
+        },
+        None,
+    )]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.append_labl_chunk(&labels).unwrap();
+    writer.append_labl_chunk(&[]).unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(reader.wave_len(), bytes.len() as u64);
+    assert_eq!(reader.label_count().unwrap(), 2);
+
+    let (_, list) = reader.read_next_chunk(Some(*b"LIST")).unwrap().unwrap();
+    // raw_chunks skips a RIFF header, which the list type completes
+    let tags = raw_chunks(&[&[0u8; 8][..], &list].concat())
+        .into_iter()
+        .map(|(tag, _)| tag)
+        .collect::<Vec<_>>();
+    assert_eq!(tags, [*b"labl", *b"labl"]);
+    assert_eq!(extract_labels_from_list(&list), labels);
+    assert!(extract_labeled_text_from_list(&list).is_empty());
+}
+
+#[test]
+fn coalesce_duplicate_cue_chunks() {
+    let mut bytes = build_wave(&[
+        (
+            ChunkHead {
+                tag: *b"fmt ",
+                size: 16,
+            },
+            None,
+        ),
+        (
+            ChunkHead {
+                tag: *b"data",
+                size: 5,
+            },
+            None,
+        ),
+    ]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    assert_eq!(writer.coalesce_cue_chunks().unwrap(), 0);
+
+    writer
+        .append_cue_chunk(&[
+            CuePoint::from_sample_offset(1, 10),
+            CuePoint::from_sample_offset(2, 20),
+        ])
+        .unwrap();
+    writer.append_chunk(*b"iXML", b"<x/>").unwrap();
+    writer
+        .append_cue_chunk(&[CuePoint::from_sample_offset(2, 30)])
+        .unwrap();
+    writer
+        .append_cue_chunk(&[CuePoint::from_sample_offset(7, 40)])
+        .unwrap();
+    assert_eq!(writer.coalesce_cue_chunks().unwrap(), 2);
+    assert_eq!(writer.coalesce_cue_chunks().unwrap(), 0);
+    writer.restore_cursor().unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(reader.wave_len(), bytes.len() as u64);
+    assert_eq!(
+        reader.summary().unwrap().order,
+        vec![*b"fmt ", *b"data", *b"cue ", *b"iXML"]
+    );
+    let cues = reader
+        .read_markers()
+        .unwrap()
+        .iter()
+        .map(|marker| (marker.cue.id, marker.cue.sample_offset))
+        .collect::<Vec<_>>();
+    assert_eq!(cues, [(1, 10), (2, 20), (8, 30), (7, 40)]);
+}
+
+#[test]
+fn seek_to_indexed_chunk() {
+    let bytes = build_wave(&[
+        (
+            ChunkHead {
+                tag: *b"fmt ",
+                size: 16,
+            },
+            None,
+        ),
+        (
+            ChunkHead {
+                tag: *b"iXML",
+                size: 3,
+            },
+            Some(b"<x>"),
+        ),
+        (
+            ChunkHead {
+                tag: *b"data",
+                size: 2,
+            },
+            Some(b"ab"),
+        ),
+    ]);
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let index = reader.chunk_index().unwrap();
+    let offsets = index.iter().map(|(offset, _)| *offset).collect::<Vec<_>>();
+    assert_eq!(offsets, [20, 44, 56]);
+
+    let (data_offset, data_head) = index[2];
+    assert_eq!(reader.seek_to_chunk(data_offset).unwrap(), data_head);
+    assert_eq!(
+        reader.read_next_chunk(None).unwrap(),
+        Some((*b"data", b"ab".to_vec()))
+    );
+
+    assert_eq!(reader.seek_to_chunk(index[1].0).unwrap().tag, *b"iXML");
+    assert_eq!(reader.read_next_chunk(None).unwrap().unwrap().1, b"<x>");
+    assert_eq!(reader.read_next_chunk(None).unwrap().unwrap().0, *b"data");
+
+    assert!(reader.seek_to_chunk(4).is_err());
+    assert!(reader.seek_to_chunk(bytes.len() as u64 + 1).is_err());
+}
+
+#[test]
+fn wave_list_segments() {
+    let mut list = b"wavl".to_vec();
+    list.extend_from_slice(b"data\x06\0\0\0abcdef");
+    list.extend_from_slice(b"slnt\x04\0\0\0\xe8\x03\0\0");
+    list.extend_from_slice(b"data\x03\0\0\0ghi\0");
+    list.extend_from_slice(b"slnt\x02\0\0\0\x01\0");
+    let list_size = list.len() as u32;
+
+    let bytes = build_wave(&[
+        (
+            ChunkHead {
+                tag: *b"fmt ",
+                size: 16,
+            },
+            None,
+        ),
+        (
+            ChunkHead {
+                tag: *b"LIST",
+                size: 4,
+            },
+            Some(b"adtl"),
+        ),
+        (
+            ChunkHead {
+                tag: *b"LIST",
+                size: list_size,
+            },
+            Some(&list),
+        ),
+    ]);
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let wave_list = reader.read_wave_list().unwrap().unwrap();
+
+    assert_eq!(
+        wave_list.segments,
+        [
+            WaveSegment::Data {
+                chunk_start: 0,
+                len: 6
+            },
+            WaveSegment::Silence {
+                chunk_start: 14,
+                sample_frames: 1000
+            },
+            WaveSegment::Data {
+                chunk_start: 26,
+                len: 3
+            },
+        ]
+    );
+    assert_eq!(wave_list.data_len(), 9);
+    assert_eq!(wave_list.sample_frames(3), 1003);
+    assert_eq!(wave_list.duration_seconds(3, 1003), 1.0);
+
+    let mut cue = CuePoint::from_sample_offset(1, 1);
+    cue.chunk_start = 26;
+    assert_eq!(
+        wave_list.segment_for_cue(&cue),
+        Some(&wave_list.segments[2])
+    );
+    assert_eq!(cue, CuePoint::in_data_chunk(1, 1, 26, 0));
+    assert_eq!(resolve_cue_byte_offset(&cue, 3, Some(&wave_list)), Some(37));
+    assert_eq!(resolve_cue_byte_offset(&cue, 3, None), Some(3));
+
+    let second_block = CuePoint::in_data_chunk(2, 1, 0, 3);
+    assert_eq!(
+        resolve_cue_byte_offset(&second_block, 1, Some(&wave_list)),
+        Some(12)
+    );
+    let silent = CuePoint {
+        data_tag: *b"slnt",
+        ..CuePoint::in_data_chunk(3, 0, 14, 0)
+    };
+    assert_eq!(resolve_cue_byte_offset(&silent, 3, Some(&wave_list)), None);
+
+    cue.data_tag = *b"slnt";
+    assert_eq!(wave_list.segment_for_cue(&cue), None);
+
+    assert!(WaveList::parse(b"adtl").is_err());
+    let plain = build_wave(&[(
+        ChunkHead {
+            tag: *b"data",
+            size: 2,
+        },
+        None,
+    )]);
+    let mut reader = ChunkReader::new(io::Cursor::new(&plain[..])).unwrap();
+    assert_eq!(reader.read_wave_list().unwrap(), None);
+}
+
+#[test]
+fn remaining_wave_bytes() {
+    let mut bytes = build_wave(&[
+        (
+            ChunkHead {
+                tag: *b"fmt ",
+                size: 16,
+            },
+            None,
+        ),
+        (
+            ChunkHead {
+                tag: *b"data",
+                size: 3,
+            },
+            None,
+        ),
+    ]);
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(reader.remaining().unwrap(), 36);
+    reader.read_next_chunk(None).unwrap();
+    assert_eq!(reader.remaining().unwrap(), 12);
+    assert!(!reader.at_end().unwrap());
+    reader.read_next_chunk(None).unwrap();
+    assert!(reader.at_end().unwrap());
+
+    // Trailing bytes declared by the RIFF size but not by any chunk
+    bytes.extend_from_slice(&[0; 2]);
+    let riff_size = bytes.len() as u32 - 8;
+    bytes[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    while reader.remaining().unwrap() >= 8 {
+        reader.read_next_chunk(None).unwrap();
+    }
+    assert_eq!(reader.remaining().unwrap(), 2);
+}
+
+#[test]
+fn preserve_non_zero_pad_byte() {
+    let mut bytes = build_wave(&[
+        (
+            ChunkHead {
+                tag: *b"iXML",
+                size: 4,
+            },
+            None,
+        ),
+        (
+            ChunkHead {
+                tag: *b"data",
+                size: 3,
+            },
+            Some(b"abc"),
+        ),
+    ]);
+    let pad_position = bytes.len() - 1;
+    bytes[pad_position] = 0xAB;
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(
+        reader.read_next_chunk_with_pad(None).unwrap(),
+        Some(((*b"iXML", vec![0; 4]), None))
+    );
+    let ((tag, body), pad) =
+        reader.read_next_chunk_with_pad(None).unwrap().unwrap();
+    assert_eq!(pad, Some(0xAB));
+    assert!(reader.at_end().unwrap());
+
+    let mut copy = build_wave(&[(
+        ChunkHead {
+            tag: *b"iXML",
+            size: 4,
+        },
+        None,
+    )]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut copy)).unwrap();
+    writer
+        .append_chunk_with_pad(tag, &body, pad.unwrap())
+        .unwrap();
+    assert_eq!(copy, bytes);
+
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.remove_chunk(*b"iXML").unwrap();
+    assert_eq!(bytes.last(), Some(&0xAB));
+
+    // Truncated before the pad byte
+    bytes.pop();
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let (_, pad) = reader.read_next_chunk_with_pad(None).unwrap().unwrap();
+    assert_eq!(pad, None);
+}
+
+#[test]
+fn stream_cue_points() {
+    let cues = (0..100)
+        .map(|id| CuePoint::from_sample_offset(id, id * 7))
+        .collect::<Vec<_>>();
+    let mut bytes = build_wave(&[(
+        ChunkHead {
+            tag: *b"data",
+            size: 4,
+        },
+        None,
+    )]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.append_cue_chunk(&cues).unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let streamed = reader
+        .cue_points_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(streamed, cues);
+
+    let late = reader
+        .cue_points_iter()
+        .filter_map(Result::ok)
+        .filter(|cue| cue.sample_offset > 600)
+        .take(2)
+        .map(|cue| cue.id)
+        .collect::<Vec<_>>();
+    assert_eq!(late, [86, 87]);
+
+    // Truncated partway through the cue chunk
+    let truncated = &bytes[..bytes.len() - 30];
+    let mut reader = ChunkReader::new(io::Cursor::new(truncated)).unwrap();
+    let results = reader.cue_points_iter().collect::<Vec<_>>();
+    assert_eq!(results.len(), 99);
+    assert!(results[..98].iter().all(Result::is_ok));
+    assert!(results[98].is_err());
+
+    let empty = build_wave(&[]);
+    let mut reader = ChunkReader::new(io::Cursor::new(&empty[..])).unwrap();
+    assert_eq!(reader.cue_points_iter().count(), 0);
+}
+
+#[test]
+fn rewind_to_last_chunk() {
+    let bytes = build_wave(&[
+        (
+            ChunkHead {
+                tag: *b"fmt ",
+                size: 16,
+            },
+            None,
+        ),
+        (
+            ChunkHead {
+                tag: *b"data",
+                size: 3,
+            },
+            Some(b"abc"),
+        ),
+    ]);
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert!(!reader.rewind_chunk().unwrap());
+
+    assert_eq!(reader.read_next_chunk(None).unwrap().unwrap().0, *b"fmt ");
+    assert!(reader.rewind_chunk().unwrap());
+    assert_eq!(reader.read_next_chunk(None).unwrap().unwrap().0, *b"fmt ");
+
+    let (_, data) = reader.read_next_chunk(None).unwrap().unwrap();
+    assert!(reader.rewind_chunk().unwrap());
+    assert!(reader.rewind_chunk().unwrap());
+    assert_eq!(reader.read_next_chunk(None).unwrap().unwrap().1, data);
+    assert_eq!(reader.read_next_chunk(None).unwrap(), None);
+
+    reader.summary().unwrap();
+    assert!(!reader.rewind_chunk().unwrap());
+}
+
+#[test]
+fn sort_cue_points_when_writing() {
+    let cues = [
+        CuePoint::from_sample_offset(1, 30),
+        CuePoint::from_sample_offset(2, 10),
+        CuePoint::from_sample_offset(3, 10),
+    ];
+    assert!(!cue_points_are_sorted(&cues));
+    assert!(cue_points_are_sorted(&[]));
+
+    let mut bytes = build_wave(&[(
+        ChunkHead {
+            tag: *b"data",
+            size: 4,
+        },
+        None,
+    )]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.set_sort_cue_points(true);
+    writer.append_cue_chunk(&cues).unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let (_, body) = reader.read_next_chunk(Some(*b"cue ")).unwrap().unwrap();
+    let written = parse_cue_points(&body);
+    assert!(cue_points_are_sorted(&written));
+    let ids = written.iter().map(|cue| cue.id).collect::<Vec<_>>();
+    assert_eq!(ids, [2, 3, 1]);
+}
+
+#[test]
+fn find_adtl_list_after_info() {
+    let (_, adtl) = label_chunk(&[LabeledText::from_cue_length(1, 9)]).unwrap();
+    let bytes = build_wave(&[
+        (
+            ChunkHead {
+                tag: *b"LIST",
+                size: 12,
+            },
+            Some(b"INFOINAM\0\0\0\0"),
+        ),
+        (
+            ChunkHead {
+                tag: *b"data",
+                size: 2,
+            },
+            None,
+        ),
+        (
+            ChunkHead {
+                tag: *b"LIST",
+                size: adtl.len() as u32,
+            },
+            Some(&adtl),
+        ),
+    ]);
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(reader.find_adtl_list().unwrap(), Some(adtl));
+    assert_eq!(
+        reader.read_next_chunk(None).unwrap().unwrap().1[..4],
+        *b"INFO"
+    );
+
+    let bare = build_wave(&[(
+        ChunkHead {
+            tag: *b"LIST",
+            size: 4,
+        },
+        Some(b"INFO"),
+    )]);
+    let mut reader = ChunkReader::new(io::Cursor::new(&bare[..])).unwrap();
+    assert_eq!(reader.find_adtl_list().unwrap(), None);
+}
+
+#[test]
+fn labeled_text_max_len() {
+    let short = LabeledText {
+        text: String::from("intro"),
+        ..LabeledText::from_cue_length(1, 0)
+    };
+    let long = LabeledText {
+        text: "x".repeat(DEFAULT_MAX_TEXT_LEN + 1),
+        ..LabeledText::from_cue_length(2, 0)
+    };
+    let (_, list) = label_chunk(&[short.clone(), long]).unwrap();
+
+    let ltxts = extract_labeled_text_from_list(&list);
+    assert_eq!(ltxts[0], short);
+    assert_eq!(ltxts[1].text.len(), DEFAULT_MAX_TEXT_LEN);
+
+    let (ltxts, warnings) = extract_labeled_text_from_list_with_max(&list, 3);
+    assert_eq!(ltxts[0].text, "int");
+    assert_eq!(ltxts[1].text, "xxx");
+    assert_eq!(
+        warnings,
+        vec![
+            Warning::TextTruncated { cue_id: 1, len: 5 },
+            Warning::TextTruncated {
+                cue_id: 2,
+                len: DEFAULT_MAX_TEXT_LEN + 1
+            },
+        ]
+    );
+
+    let (_, warnings) = extract_labeled_text_from_list_with_max(&list, 1 << 20);
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn wrap_pcm_then_mark() {
+    let format = FormatChunk::parse(&pcm_format_bytes(1, 8000, 8)).unwrap();
+    let samples = [0x80u8; 7];
+    let mut bytes = vec![];
+    wrap_pcm(&samples, &format, &mut bytes).unwrap();
+
+    assert_eq!(bytes.len(), 12 + 8 + 16 + 8 + 8);
+    assert_eq!(
+        u32::from_le_bytes(*bytes[4..].first_chunk::<4>().unwrap()) as usize,
+        bytes.len() - 8
+    );
+
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer
+        .append_named_markers(&[(3, String::from("hit"))])
+        .unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let (_, fmt) = reader.read_next_chunk(Some(*b"fmt ")).unwrap().unwrap();
+    assert_eq!(FormatChunk::parse(&fmt).unwrap(), format);
+    let (_, data) = reader.read_next_chunk(Some(*b"data")).unwrap().unwrap();
+    assert_eq!(data, samples);
+    let markers = reader.read_markers().unwrap();
+    assert_eq!(markers[0].cue.sample_offset, 3);
+    assert_eq!(markers[0].label.as_deref(), Some("hit"));
+}
+
+#[test]
+fn marker_prefers_labl_name() {
+    let cues = [
+        CuePoint::from_sample_offset(1, 10),
+        CuePoint::from_sample_offset(2, 20),
+    ];
+    let region = LabeledText {
+        purpose_id: *b"rgn ",
+        text: String::from("old name"),
+        ..LabeledText::from_cue_length(1, 100)
+    };
+    let fallback = LabeledText {
+        text: String::from("ltxt only"),
+        ..LabeledText::from_cue_length(2, 0)
+    };
+    let labels = [
+        Label {
+            cue_id: 1,
+            text: String::from("Verse"),
+        },
+        Label {
+            cue_id: 2,
+            text: String::new(),
+        },
+    ];
+
+    let mut bytes = build_wave(&[]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.append_cue_chunk(&cues).unwrap();
+    writer.append_labl_chunk(&labels).unwrap();
+    writer.append_label_chunk(&[region, fallback]).unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let markers = reader.read_markers().unwrap();
+    assert_eq!(
+        markers[0],
+        Marker {
+            cue: cues[0],
+            label: Some(String::from("Verse")),
+            region_length: Some(100),
+            purpose_id: Some(*b"rgn "),
+        }
+    );
+    assert_eq!(markers[1].label.as_deref(), Some("ltxt only"));
+
+    // Written back, names go to labels and regions to text-less labeled-texts
+    let mut copy = build_wave(&[]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut copy)).unwrap();
+    writer.append_markers(&markers).unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&copy[..])).unwrap();
+    assert_eq!(reader.read_markers().unwrap(), markers);
+    let list = reader.find_adtl_list().unwrap().unwrap();
+    assert_eq!(extract_labels_from_list(&list).len(), 2);
+    assert!(extract_labeled_text_from_list(&list)
+        .iter()
+        .all(|ltxt| ltxt.text.is_empty()));
+}
+
+#[test]
+fn format_sample_frames() {
+    let stereo = FormatChunk::parse(&pcm_format_bytes(2, 44100, 16)).unwrap();
+    assert_eq!(stereo.sample_frames(4 * 100 + 3), 100);
+
+    let broken = FormatChunk {
+        block_align: 0,
+        ..stereo
+    };
+    assert_eq!(broken.sample_frames(400), 0);
+}
+
+#[test]
+fn resync_past_lying_chunk() {
+    let (_, cue) = cue_chunk(&[CuePoint::from_sample_offset(1, 5)]).unwrap();
+    let mut bytes = build_wave(&[]);
+    bytes.extend_from_slice(b"JUNK");
+    bytes.extend_from_slice(&4u32.to_le_bytes());
+    bytes.extend_from_slice(&[1u8; 10]);
+    bytes.extend_from_slice(b"cue ");
+    bytes.extend_from_slice(&(cue.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&cue);
+    let riff_size = bytes.len() as u32 - 8;
+    bytes[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(reader.recovery_policy(), RecoveryPolicy::Strict);
+    assert_eq!(reader.read_next_chunk(Some(*b"cue ")).unwrap(), None);
+    assert!(reader.skipped_regions().is_empty());
+
+    reader.set_recovery_policy(RecoveryPolicy::Resync);
+    reader.reset().unwrap();
+    let (_, body) = reader.read_next_chunk(Some(*b"cue ")).unwrap().unwrap();
+    assert_eq!(body, cue);
+    assert_eq!(
+        reader.skipped_regions(),
+        [std::ops::Range { start: 24, end: 30 }]
+    );
+
+    reader.reset().unwrap();
+    assert_eq!(reader.read_markers().unwrap().len(), 1);
+    assert_eq!(
+        reader.skipped_regions(),
+        [std::ops::Range { start: 24, end: 30 }]
+    );
+
+    // Garbage longer than a scanned block, with the head straddling two
+    let mut bytes = build_wave(&[]);
+    bytes.extend_from_slice(b"JUNK");
+    bytes.extend_from_slice(&2u32.to_le_bytes());
+    bytes.resize(bytes.len() + 0x10000 - 4, 0);
+    bytes.extend_from_slice(b"cue ");
+    bytes.extend_from_slice(&(cue.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&cue);
+    let riff_size = bytes.len() as u32 - 8;
+    bytes[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    reader.set_recovery_policy(RecoveryPolicy::Resync);
+    let (_, body) = reader.read_next_chunk(Some(*b"cue ")).unwrap().unwrap();
+    assert_eq!(body, cue);
+    assert_eq!(
+        reader.skipped_regions(),
+        [std::ops::Range {
+            start: 22,
+            end: 0x10010
+        }]
+    );
+}
+
+#[test]
+fn chunk_alignment_helpers() {
+    let head = |size| ChunkHead {
+        tag: *b"note",
+        size,
+    };
+    assert_eq!(head(7).padded_size(), 8);
+    assert_eq!(head(8).padded_size(), 8);
+    assert_eq!(head(u32::MAX).padded_size(), 1 << 32);
+
+    let mut bytes = build_wave(&[]);
+    let before = bytes.len() as u64;
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.append_chunk(*b"note", b"odd").unwrap();
+    assert_eq!(Some(bytes.len() as u64 - before), aligned_chunk_len(3));
+
+    assert_eq!(aligned_chunk_len(0), Some(8));
+    assert_eq!(aligned_chunk_len(u32::MAX as usize), Some(8 + (1 << 32)));
+}
+
+#[test]
+fn open_paths() {
+    let path = std::env::temp_dir()
+        .join(format!("cuet-open-paths-{}.wav", std::process::id()));
+    std::fs::write(&path, build_wave(&[])).unwrap();
+
+    let mut writer = ChunkWriter::open(&path).unwrap();
+    writer
+        .append_cue_chunk(&[CuePoint::from_sample_offset(1, 0)])
+        .unwrap();
+    drop(writer);
+
+    let mut reader = ChunkReader::open(&path).unwrap();
+    assert_eq!(reader.read_markers().unwrap().len(), 1);
+
+    std::fs::write(&path, b"RIFF\x04\x00\x00\x00AVI ").unwrap();
+    assert!(ChunkWriter::open(&path).is_err());
+    std::fs::remove_file(&path).unwrap();
+    assert!(ChunkReader::open(&path).is_err());
+}
+
+#[test]
+fn pro_tools_chunks_kept() {
+    let chunk = |tag: &[u8; 4], size| (ChunkHead { tag: *tag, size }, None);
+    let mut bytes = build_wave(&[
+        chunk(b"fmt ", 16),
+        chunk(b"minf", 16),
+        chunk(b"elm1", 3),
+        chunk(b"iXML", 4),
+        chunk(b"data", 2),
+        chunk(b"regn", 92),
+    ]);
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let named = reader.summary().unwrap().named_order();
+    assert_eq!(named[1], (*b"minf", Some("Pro Tools media info")));
+    assert_eq!(chunk_name(b"zzzz"), None);
+    assert!(reader
+        .describe()
+        .unwrap()
+        .contains("\"tag\": \"regn\", \"name\": \"Pro Tools regions\""));
+
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    assert_eq!(writer.strip_metadata(&[]).unwrap(), 1);
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let order = reader.summary().unwrap().order;
+    assert_eq!(order, [*b"fmt ", *b"minf", *b"elm1", *b"data", *b"regn"]);
+    assert!(PRO_TOOLS_CHUNKS.iter().all(|tag| chunk_name(tag).is_some()));
+}
+
+#[test]
+fn overlapping_regions() {
+    let marker = |id, offset, length| Marker {
+        cue: CuePoint::from_sample_offset(id, offset),
+        label: None,
+        region_length: Some(length).filter(|&len| len > 0),
+        purpose_id: None,
+    };
+
+    let markers = [
+        marker(1, 100, 50),
+        marker(2, 0, 100),
+        marker(3, 120, 0),
+        marker(4, 140, 20),
+        marker(5, 10, 10),
+        marker(6, u32::MAX, u32::MAX),
+    ];
+
+    assert_eq!(find_overlapping_regions(&markers), [(0, 3), (1, 4)]);
+    assert!(find_overlapping_regions(&markers[..3]).is_empty());
+}
+
+#[test]
+fn grow_data_chunk_after_recording() {
+    let chunk = |tag: &[u8; 4], size| (ChunkHead { tag: *tag, size }, None);
+    let mut bytes = build_wave(&[chunk(b"fmt ", 16), chunk(b"data", 3)]);
+
+    // Overwrite the pad byte with more samples
+    bytes.pop();
+    bytes.extend_from_slice(&[7u8; 4]);
+
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.grow_data_chunk(4).unwrap();
+    assert_eq!(bytes.len(), 12 + 24 + 8 + 8);
+
+    let mut reader =
+        ChunkReader::new_strict(io::Cursor::new(&bytes[..])).unwrap();
+    let (_, data) = reader.read_next_chunk(Some(*b"data")).unwrap().unwrap();
+    assert_eq!(data, [0, 0, 0, 7, 7, 7, 7]);
+    assert!(reader.at_end().unwrap());
+
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    assert!(writer.grow_data_chunk(u32::MAX).is_err());
+    writer.append_chunk(*b"note", b"x").unwrap();
+    assert!(writer.grow_data_chunk(2).is_err());
+
+    let mut empty = build_wave(&[]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut empty)).unwrap();
+    assert!(writer.grow_data_chunk(2).is_err());
+}
+
+#[test]
+fn read_sample_rate_and_channels() {
+    let fmt = pcm_format_bytes(2, 48000, 24);
+    let bytes = build_wave(&[
+        (
+            ChunkHead {
+                tag: *b"JUNK",
+                size: 4,
+            },
+            None,
+        ),
+        (
+            ChunkHead {
+                tag: *b"fmt ",
+                size: 16,
+            },
+            Some(&fmt),
+        ),
+    ]);
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(reader.sample_rate().unwrap(), 48000);
+    assert_eq!(reader.channels().unwrap(), 2);
+    assert_eq!(reader.read_next_chunk(None).unwrap().unwrap().0, *b"JUNK");
+
+    let short = build_wave(&[(
+        ChunkHead {
+            tag: *b"fmt ",
+            size: 8,
+        },
+        None,
+    )]);
+    let mut reader = ChunkReader::new(io::Cursor::new(&short[..])).unwrap();
+    assert!(reader.sample_rate().is_err());
+
+    let empty = build_wave(&[]);
+    let mut reader = ChunkReader::new(io::Cursor::new(&empty[..])).unwrap();
+    assert!(reader.channels().is_err());
+
+    // RIFX, with the fields big-endian and two frames of samples
+    let mut rifx = Vec::from(&b"RIFX\0\0\0\x30WAVEfmt \0\0\0\x10"[..]);
+    rifx.extend_from_slice(&1u16.to_be_bytes());
+    rifx.extend_from_slice(&2u16.to_be_bytes());
+    rifx.extend_from_slice(&48000u32.to_be_bytes());
+    rifx.extend_from_slice(&288000u32.to_be_bytes());
+    rifx.extend_from_slice(&6u16.to_be_bytes());
+    rifx.extend_from_slice(&24u16.to_be_bytes());
+    rifx.extend_from_slice(b"data\0\0\0\x0c");
+    rifx.extend_from_slice(&[0; 12]);
+
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut rifx)).unwrap();
+    writer
+        .append_cue_chunk(&[CuePoint::from_sample_offset(1, 3)])
+        .unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&rifx[..])).unwrap();
+    assert_eq!(reader.sample_rate().unwrap(), 48000);
+    assert_eq!(reader.channels().unwrap(), 2);
+    assert_eq!(
+        reader.verify().unwrap(),
+        [Warning::CueBeyondData {
+            cue_id: 1,
+            sample_offset: 3,
+            sample_count: 2
+        }]
+    );
+}
+
+#[test]
+fn append_to_wave_without_chunks() {
+    let cues = [CuePoint::from_sample_offset(1, 0)];
+    let ltxts = [LabeledText::from_cue_length(1, 4)];
+    let mut bytes = b"RIFF\x04\x00\x00\x00WAVE".to_vec();
+
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.append_cue_chunk(&cues).unwrap();
+    writer.append_label_chunk(&ltxts).unwrap();
+
+    let (_, cue) = cue_chunk(&cues).unwrap();
+    let (_, list) = label_chunk(&ltxts).unwrap();
+    let expected = build_wave(&[
+        (
+            ChunkHead {
+                tag: *b"cue ",
+                size: cue.len() as u32,
+            },
+            Some(&cue),
+        ),
+        (
+            ChunkHead {
+                tag: *b"LIST",
+                size: list.len() as u32,
+            },
+            Some(&list),
+        ),
+    ]);
+    assert_eq!(bytes, expected);
+
+    for riff_size in [0u8, 2] {
+        let mut bytes = b"RIFF\x00\x00\x00\x00WAVE".to_vec();
+        bytes[4] = riff_size;
+        assert!(ChunkWriter::new(io::Cursor::new(&mut bytes)).is_err());
+        assert!(ChunkReader::new(io::Cursor::new(&bytes[..])).is_err());
+    }
+}
+
+#[test]
+fn resample_markers() {
+    let mut cues = [
+        CuePoint::from_sample_offset(1, 44100),
+        CuePoint::from_sample_offset(2, 1),
+        CuePoint::from_sample_offset(3, u32::MAX),
+    ];
+    let mut ltxts = [LabeledText::from_cue_length(1, 441)];
+
+    resample_cue_points(&mut cues, 44100, 48000);
+    resample_labeled_texts(&mut ltxts, 44100, 48000);
+    assert_eq!(cues.map(|cue| cue.sample_offset), [48000, 1, u32::MAX]);
+    assert_eq!(ltxts[0].sample_length, 480);
+
+    resample_cue_points(&mut cues, 48000, 16000);
+    assert_eq!(cues.map(|cue| cue.sample_offset)[..2], [16000, 0]);
+
+    resample_cue_points(&mut cues, 0, 16000);
+    assert_eq!(cues[0].sample_offset, 16000);
+}
+
+#[test]
+fn tag_constants() {
+    let (cue_tag, cue) =
+        cue_chunk(&[CuePoint::from_sample_offset(1, 0)]).unwrap();
+    assert_eq!(cue_tag, tag::CUE);
+
+    let mut bytes = build_wave(&[]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.append_chunk(tag::CUE, &cue).unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert!(reader.read_next_chunk(Some(tag::CUE)).unwrap().is_some());
+    assert_eq!(label_chunk(&[]).unwrap().1, tag::ADTL);
+}
+
+#[test]
+fn read_chunks_in_tag_set() {
+    let chunk = |tag: &[u8; 4], size| (ChunkHead { tag: *tag, size }, None);
+    let bytes = build_wave(&[
+        chunk(b"fmt ", 16),
+        chunk(b"data", 64),
+        chunk(b"cue ", 4),
+        chunk(b"JUNK", 2),
+        chunk(b"LIST", 4),
+    ]);
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    // Too small to read the data chunk, which is only ever skipped
+    reader.set_max_chunk_size(16);
+
+    let wanted = [tag::FMT, tag::CUE, tag::LIST];
+    let mut found = vec![];
+    while let Some((tag, _)) = reader.read_next_chunk_in(&wanted).unwrap() {
+        found.push(tag);
+    }
+    assert_eq!(found, wanted);
+    assert_eq!(reader.read_next_chunk_in(&[]).unwrap(), None);
+}
+
+// Stream reading as zeros wherever nothing was written, storing only written
+// blocks, for WAVEs of several GiB
+#[derive(Default)]
+struct SparseCursor {
+    blocks: std::collections::BTreeMap<u64, Vec<u8>>,
+    len: u64,
+    position: u64,
+}
+
+const SPARSE_BLOCK_SZ: u64 = 4096;
+
+impl Truncate for SparseCursor {
+    fn truncate(&mut self, len: u64) -> io::Result<()> {
+        self.len = len;
+        Ok(())
+    }
+}
+
+impl io::Read for SparseCursor {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let offset = self.position % SPARSE_BLOCK_SZ;
+        let n = (buf.len() as u64)
+            .min(self.len.saturating_sub(self.position))
+            .min(SPARSE_BLOCK_SZ - offset) as usize;
+
+        match self.blocks.get(&(self.position / SPARSE_BLOCK_SZ)) {
+            Some(block) => {
+                buf[..n].copy_from_slice(&block[offset as usize..][..n])
+            }
+            None => buf[..n].fill(0),
+        }
+
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+impl io::Write for SparseCursor {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let offset = self.position % SPARSE_BLOCK_SZ;
+        let n = (buf.len() as u64).min(SPARSE_BLOCK_SZ - offset) as usize;
+        let block = self
+            .blocks
+            .entry(self.position / SPARSE_BLOCK_SZ)
+            .or_insert_with(|| vec![0; SPARSE_BLOCK_SZ as usize]);
+        block[offset as usize..][..n].copy_from_slice(&buf[..n]);
+
+        self.position += n as u64;
+        self.len = self.len.max(self.position);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl io::Seek for SparseCursor {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.position = match pos {
+            io::SeekFrom::Start(position) => position,
+            io::SeekFrom::End(delta) => self.len.saturating_add_signed(delta),
+            io::SeekFrom::Current(delta) => {
+                self.position.saturating_add_signed(delta)
+            }
+        };
+        Ok(self.position)
+    }
+}
+
+// WAVE of a JUNK chunk, if reserved, a "fmt " chunk, and a "data" chunk of
+// data_size zeros
+fn large_wave(data_size: u32, reserve: bool) -> SparseCursor {
+    use io::Write as _;
+
+    let mut head = vec![];
+    if reserve {
+        head.extend_from_slice(b"JUNK\x1c\0\0\0");
+        head.extend_from_slice(&[0; 28]);
+    }
+    head.extend_from_slice(b"fmt \x10\0\0\0");
+    head.extend_from_slice(&pcm_format_bytes(2, 48000, 24));
+    head.extend_from_slice(b"data");
+    head.extend_from_slice(&data_size.to_le_bytes());
+
+    let riff_size = 4
+        + head.len() as u64
+        + ChunkHead {
+            tag: *b"data",
+            size: data_size,
+        }
+        .padded_size();
+
+    let mut file = SparseCursor::default();
+    file.write_all(b"RIFF").unwrap();
+    file.write_all(&(riff_size as u32).to_le_bytes()).unwrap();
+    file.write_all(b"WAVE").unwrap();
+    file.write_all(&head).unwrap();
+    file.truncate(8 + riff_size).unwrap();
+    file.rewind().unwrap();
+    file
+}
+
+#[test]
+fn append_past_4_gib_upgrades_to_rf64() {
+    let data_size = u32::MAX - 200;
+    let note = [0x55u8; 200];
+
+    let mut file = large_wave(data_size, false);
+    file.rewind().unwrap();
+    let mut writer = ChunkWriter::new(&mut file).unwrap();
+    writer.append_chunk(*b"note", &[0; 64]).unwrap();
+    let err = writer.append_chunk(*b"note", &note).unwrap_err();
+    let needed = 4 + 24 + 8 + u64::from(data_size) + 1 + 72 + 208;
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "Wave: Size of {} bytes exceeds maximum of {}; reserve a JUNK \
+             chunk of at least 28 bytes first to allow upgrading to RF64\n",
+            needed,
+            u32::MAX
+        )
+    );
+    file.rewind().unwrap();
+    let mut reader = ChunkReader::new(&mut file).unwrap();
+    assert_eq!(reader.ds64(), None);
+    assert_eq!(reader.summary().unwrap().order.len(), 3);
+
+    let mut file = large_wave(data_size, true);
+    file.rewind().unwrap();
+    let mut writer = ChunkWriter::new(&mut file).unwrap();
+    writer.append_chunk(*b"note", &note).unwrap();
+
+    file.rewind().unwrap();
+    let mut reader = ChunkReader::new(&mut file).unwrap();
+    let expected_size = 4 + 36 + 24 + 8 + u64::from(data_size) + 1 + 208;
+    assert_eq!(
+        reader.ds64(),
+        Some(&Ds64Chunk {
+            riff_size: expected_size,
+            data_size: data_size.into(),
+            ..Ds64Chunk::default()
+        })
+    );
+    assert_eq!(reader.wave_end(), 8 + expected_size);
+    let (_, body) = reader.read_next_chunk(Some(*b"note")).unwrap().unwrap();
+    assert_eq!(body, note);
+    drop(reader);
+
+    let mut head = [0u8; 8];
+    file.rewind().unwrap();
+    io::Read::read_exact(&mut file, &mut head).unwrap();
+    assert_eq!(head, *b"RF64\xff\xff\xff\xff");
+
+    // Later appends update the ds64 chunk
+    file.rewind().unwrap();
+    let mut writer = ChunkWriter::new(&mut file).unwrap();
+    writer.append_chunk(*b"note", b"abc").unwrap();
+    file.rewind().unwrap();
+    let reader = ChunkReader::new(&mut file).unwrap();
+    assert_eq!(reader.ds64().unwrap().riff_size, expected_size + 12);
+
+    file.rewind().unwrap();
+    let mut writer = ChunkWriter::new(&mut file).unwrap();
+    assert!(writer.remove_chunk(*b"note").is_err());
+}
+
+#[test]
+fn grow_data_past_4_gib_upgrades_to_rf64() {
+    let data_size = u32::MAX - 100;
+    let mut file = large_wave(data_size, true);
+    file.rewind().unwrap();
+    let mut writer = ChunkWriter::new(&mut file).unwrap();
+    writer.grow_data_chunk(1001).unwrap();
+    writer.append_chunk(*b"note", b"after").unwrap();
+
+    let grown = u64::from(data_size) + 1001;
+    file.rewind().unwrap();
+    let mut reader = ChunkReader::new(&mut file).unwrap();
+    assert_eq!(reader.ds64().unwrap().data_size, grown);
+    assert_eq!(
+        reader.ds64().unwrap().riff_size,
+        4 + 36 + 24 + 8 + grown + 14
+    );
+
+    let (_, body) = reader.read_next_chunk(Some(*b"note")).unwrap().unwrap();
+    assert_eq!(body, b"after");
+}
+
+#[test]
+fn grow_data_again_after_rf64_upgrade() {
+    let data_size = u32::MAX - 100;
+    let mut file = large_wave(data_size, true);
+    file.rewind().unwrap();
+    let mut writer = ChunkWriter::new(&mut file).unwrap();
+    writer.grow_data_chunk(1001).unwrap();
+    writer.grow_data_chunk(10).unwrap();
+    writer.grow_data_chunk(3).unwrap();
+
+    let grown = u64::from(data_size) + 1014;
+    file.rewind().unwrap();
+    let mut reader = ChunkReader::new(&mut file).unwrap();
+    assert_eq!(reader.ds64().unwrap().data_size, grown);
+    assert_eq!(
+        reader.ds64().unwrap().riff_size,
+        4 + 36 + 24 + 8 + grown + 1
+    );
+    assert_eq!(
+        reader.summary().unwrap().order,
+        [*b"ds64", *b"fmt ", *b"data"]
+    );
+}
+
+#[test]
+fn iterate_markers_lazily() {
+    let cues = [
+        CuePoint::from_sample_offset(1, 10),
+        CuePoint::from_sample_offset(2, 20),
+        CuePoint::from_sample_offset(3, 30),
+    ];
+    let region = LabeledText::from_cue_length(2, 500).as_bytes();
+    let list = adtl_bytes(&[
+        (b"ltxt", region.len() as u32, &region),
+        (b"labl", 6, b"\x01\0\0\0A\0"),
+        (b"labl", 2, b"\x09\0"),
+        (b"labl", 6, b"\x02\0\0\0B\0"),
+    ]);
+
+    let mut bytes = build_wave(&[]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.append_cue_chunk(&cues).unwrap();
+    writer.append_chunk(*b"LIST", &list).unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let mut markers = reader.markers_iter().unwrap();
+
+    let first = markers.next().unwrap().unwrap();
+    assert_eq!(first.cue.id, 2);
+    assert_eq!(first.label.as_deref(), Some("B"));
+    assert_eq!(first.region_length, Some(500));
+
+    assert_eq!(markers.next().unwrap().unwrap().label.as_deref(), Some("A"));
+    assert!(markers.next().unwrap().is_err());
+    assert_eq!(markers.next().unwrap().unwrap(), Marker::join(cues[2], &[]));
+    assert!(markers.next().is_none());
+    assert!(markers.next().is_none());
+
+    let mut lazy = reader
+        .markers_iter()
+        .unwrap()
+        .filter_map(Result::ok)
+        .collect::<Vec<_>>();
+    lazy.sort_by_key(|marker| marker.cue.id);
+    assert_eq!(lazy, reader.read_markers().unwrap());
+}
+
+#[test]
+fn truncate_garbage_after_wave() {
+    let wave = build_wave(&[(
+        ChunkHead {
+            tag: *b"data",
+            size: 3,
+        },
+        Some(b"abc"),
+    )]);
+    let mut bytes = wave.clone();
+    bytes.extend_from_slice(b"<html>not found</html>");
+
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    assert_eq!(writer.truncate_to_wave_end().unwrap(), 22);
+    assert_eq!(writer.truncate_to_wave_end().unwrap(), 0);
+    assert_eq!(bytes, wave);
+
+    // Cut short, nothing is removed
+    let mut bytes = wave[..wave.len() - 2].to_vec();
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    assert_eq!(writer.truncate_to_wave_end().unwrap(), 0);
+    assert_eq!(bytes.len(), wave.len() - 2);
+}
+
+#[test]
+fn probe_wave_headers() {
+    let wave = build_wave(&[]);
+    assert!(is_wave(&mut &wave[..]));
+    assert!(is_wave(&mut &b"RIFX\0\0\0\x04WAVE"[..]));
+    assert!(is_wave(&mut &b"RF64\xff\xff\xff\xffWAVEds64"[..]));
+
+    assert!(!is_wave(&mut &b"RIFF\x04\0\0\0AVI "[..]));
+    assert!(!is_wave(&mut &b"FORM\0\0\0\x04AIFF"[..]));
+    assert!(!is_wave(&mut &wave[..11]));
+}
+
+#[test]
+fn report_wave_flavor() {
+    let wave = build_wave(&[]);
+    let reader = ChunkReader::new(io::Cursor::new(&wave[..])).unwrap();
+    assert_eq!(reader.format_flavor(), WaveFlavor::Riff);
+
+    let rifx = b"RIFX\0\0\0\x04WAVE";
+    let reader = ChunkReader::new(io::Cursor::new(&rifx[..])).unwrap();
+    assert_eq!(reader.format_flavor(), WaveFlavor::Rifx);
+    assert_eq!(reader.format_flavor().endianness(), Endianness::Big);
+
+    for (riff_tag, flavor) in
+        [(*b"RF64", WaveFlavor::Rf64), (*b"BW64", WaveFlavor::Bw64)]
+    {
+        let ds64 = Ds64Chunk {
+            riff_size: 4 + 36,
+            ..Ds64Chunk::default()
+        };
+        let mut bytes = riff_tag.to_vec();
+        bytes.extend_from_slice(b"\xff\xff\xff\xffWAVEds64\x1c\0\0\0");
+        bytes.extend_from_slice(&ds64.as_bytes());
+
+        let reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+        assert_eq!(reader.format_flavor(), flavor);
+        assert_eq!(flavor.riff_tag(), riff_tag);
+        assert_eq!(WaveFlavor::of_riff_tag(&riff_tag), Some(flavor));
+    }
+
+    assert_eq!(WaveFlavor::of_riff_tag(b"FORM"), None);
+}
+
+#[test]
+fn labels_split_across_adtl_lists() {
+    let first = [LabeledText::from_cue_length(1, 9)];
+    let second = [
+        LabeledText::from_cue_length(2, 10),
+        LabeledText::from_cue_length(3, 11),
+    ];
+
+    let mut bytes = build_wave(&[]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.append_label_chunk(&first).unwrap();
+    writer.append_chunk(*b"LIST", b"INFOINAM\0\0\0\0").unwrap();
+    writer.append_label_chunk(&second).unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let lists = reader.find_adtl_lists().unwrap();
+    assert_eq!(lists.len(), 2);
+    assert_eq!(extract_labeled_text_from_list(&lists[1]), second);
+    assert_eq!(
+        reader.read_labeled_texts().unwrap(),
+        [&first[..], &second[..]].concat()
+    );
+}
+
+#[test]
+fn append_to_rifx_big_endian() {
+    let mut bytes = Vec::from(&b"RIFX\0\0\0\x26WAVEfmt \0\0\0\x10"[..]);
+    bytes.extend_from_slice(&[0; 16]);
+    bytes.extend_from_slice(b"data\0\0\0\x02\x12\x34");
+
+    let cue = CuePoint::from_sample_offset(7, 0x0102);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    assert_eq!(writer.endianness(), Endianness::Big);
+    writer.append_cue_chunk(&[cue]).unwrap();
+    writer
+        .append_label_chunk(&[LabeledText::from_cue_length(7, 3)])
+        .unwrap();
+
+    assert_eq!(bytes[4..8], (0x26u32 + 36 + 40).to_be_bytes());
+    assert_eq!(bytes[46..58], *b"cue \0\0\0\x1c\0\0\0\x01");
+    assert_eq!(bytes[58..82], cue.as_bytes_with(Endianness::Big));
+    assert_eq!(bytes[58..62], [0, 0, 0, 7]);
+    assert_eq!(bytes[94..106], *b"ltxt\0\0\0\x14\0\0\0\x07");
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(reader.endianness(), Endianness::Big);
+    let (_, body) = reader.read_next_chunk(Some(*b"LIST")).unwrap().unwrap();
+    assert_eq!(body.len(), 32);
+
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    assert!(writer.remove_chunk(*b"cue ").is_err());
+    assert_eq!(
+        ChunkHead::parse_with(&mut &bytes[36..44], Endianness::Big).unwrap(),
+        ChunkHead {
+            tag: *b"data",
+            size: 2
+        }
+    );
+}
+
+#[test]
+fn export_regions_to_files() {
+    let format = FormatChunk::parse(&pcm_format_bytes(1, 8000, 16)).unwrap();
+    let samples = (0..20u8).collect::<Vec<_>>();
+    let mut bytes = vec![];
+    wrap_pcm(&samples, &format, &mut bytes).unwrap();
+
+    let region =
+        |id, offset, length: Option<u32>, label: Option<&str>| Marker {
+            cue: CuePoint::from_sample_offset(id, offset),
+            label: label.map(String::from),
+            region_length: length,
+            purpose_id: None,
+        };
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer
+        .append_markers(&[
+            region(1, 1, Some(2), Some("Kick")),
+            region(2, 3, None, Some("Point")),
+            region(3, 8, Some(5), None),
+        ])
+        .unwrap();
+
+    let out_dir = std::env::temp_dir()
+        .join(format!("cuet-export-regions-{}", std::process::id()));
+    std::fs::create_dir_all(&out_dir).unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let written = export_regions(&mut reader, &out_dir, |marker| {
+        format!("{}.wav", marker.cue.id)
+    })
+    .unwrap();
+    assert_eq!(written, 2);
+
+    let mut kick = ChunkReader::open(out_dir.join("1.wav")).unwrap();
+    assert_eq!(kick.read_markers().unwrap(), []);
+    let (_, data) = kick.read_next_chunk(Some(*b"data")).unwrap().unwrap();
+    assert_eq!(data, [2, 3, 4, 5]);
+    let (_, info) = kick.read_next_chunk(Some(*b"LIST")).unwrap().unwrap();
+    assert_eq!(info, *b"INFOINAM\x05\0\0\0Kick\0\0");
+
+    // Cut short at the end of the samples
+    let mut tail = ChunkReader::open(out_dir.join("3.wav")).unwrap();
+    assert_eq!(tail.sample_rate().unwrap(), 8000);
+    let (_, data) = tail.read_next_chunk(Some(*b"data")).unwrap().unwrap();
+    assert_eq!(data, samples[16..]);
+    assert!(tail.read_next_chunk(Some(*b"LIST")).unwrap().is_none());
+
+    std::fs::remove_dir_all(&out_dir).unwrap();
+}
+
+#[test]
+fn format_extension_round_trips() {
+    let pcm = pcm_format_bytes(1, 8000, 8);
+    let plain = FormatChunk::parse(&pcm).unwrap();
+    assert_eq!((plain.cb_size, plain.extra.len()), (None, 0));
+    assert_eq!(plain.as_bytes(), pcm);
+
+    // A-law, with an empty extension
+    let mut alaw = pcm.clone();
+    alaw[0] = 6;
+    alaw.extend_from_slice(&[0, 0]);
+    let format = FormatChunk::parse(&alaw).unwrap();
+    assert_eq!(format.cb_size, Some(0));
+    assert_eq!(format.as_bytes(), alaw);
+
+    // Extension the crate has no fields for, and a stray trailing byte
+    let mut adpcm = pcm.clone();
+    adpcm[0] = 2;
+    adpcm.extend_from_slice(&[2, 0, 0xF4, 0x07, 0xEE]);
+    let format = FormatChunk::parse(&adpcm).unwrap();
+    assert_eq!(format.cb_size, Some(2));
+    assert_eq!(format.extra, [0xF4, 0x07, 0xEE]);
+    assert_eq!(format.as_bytes(), adpcm);
+
+    let built = FormatChunk {
+        cb_size: None,
+        extra: vec![0xF4, 0x07],
+        ..format
+    };
+    assert_eq!(built.as_bytes(), adpcm[..20]);
+
+    // A stray byte without room for the extension size
+    let mut odd = pcm.clone();
+    odd.push(0xEE);
+    let format = FormatChunk::parse(&odd).unwrap();
+    assert_eq!((format.cb_size, &format.extra[..]), (None, &[0xEE][..]));
+    assert_eq!(format.as_bytes(), odd);
+}
+
+#[test]
+fn find_unlabeled_cues() {
+    let cues = [1, 2, 3, 4].map(|id| CuePoint::from_sample_offset(id, id * 10));
+    let labels = [
+        LabeledText {
+            text: String::from("Intro"),
+            ..LabeledText::from_cue_length(1, 0)
+        },
+        LabeledText::from_cue_length(3, 100),
+        LabeledText {
+            text: String::from("Orphan"),
+            ..LabeledText::from_cue_length(9, 0)
+        },
+        LabeledText {
+            text: String::from("Outro"),
+            ..LabeledText::from_cue_length(4, 0)
+        },
+    ];
+
+    assert_eq!(unlabeled_cue_ids(&cues, &labels), [2, 3]);
+    assert_eq!(unlabeled_cue_ids(&cues, &[]), [1, 2, 3, 4]);
+    assert_eq!(unlabeled_cue_ids(&[], &labels), []);
+
+    let labl = [
+        Label {
+            cue_id: 2,
+            text: String::from("Verse"),
+        },
+        Label {
+            cue_id: 3,
+            text: String::new(),
+        },
+    ];
+    assert_eq!(unlabeled_cue_ids_with_labels(&cues, &labels, &labl), [3]);
+    assert_eq!(unlabeled_cue_ids_with_labels(&cues, &[], &labl), [1, 3, 4]);
+}
+
+#[test]
+fn buffered_appends() {
+    let mut bytes = build_wave(&[]);
+    let mut expected = bytes.clone();
+
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut expected)).unwrap();
+    for i in 0..50u8 {
+        writer.append_chunk(*b"stmp", &[i]).unwrap();
+    }
+    writer
+        .append_cue_chunk(&[CuePoint::from_sample_offset(1, 0)])
+        .unwrap();
+
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    let mut buffer = writer.buffered();
+    for i in 0..50u8 {
+        buffer.append_chunk(*b"stmp", &[i]);
+    }
+    buffer
+        .append_cue_chunk(&[CuePoint::from_sample_offset(1, 0)])
+        .unwrap();
+    buffer.append_label_chunk(&[]).unwrap();
+    assert_eq!(buffer.pending(), 51);
+    buffer.flush().unwrap();
+    assert_eq!(buffer.pending(), 0);
+    drop(buffer);
+    assert_eq!(writer.restore_cursor().unwrap().into_inner(), &expected);
+
+    // Flushed when dropped
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.buffered().append_chunk(*b"last", b"!");
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let (_, body) = reader.read_next_chunk(Some(*b"last")).unwrap().unwrap();
+    assert_eq!(body, b"!");
+}
+
+#[test]
+fn read_chunks_into_buffer() {
+    let mut bytes = build_wave(&[]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.append_chunk(*b"long", &[7; 40]).unwrap();
+    writer.append_chunk(*b"odd ", b"abc").unwrap();
+    writer.append_chunk(*b"even", b"de").unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let mut buffer = vec![];
+    let head = reader.read_next_chunk_into(None, &mut buffer).unwrap();
+    assert_eq!(head.unwrap().size, 40);
+    let capacity = buffer.capacity();
+
+    let head = reader.read_next_chunk_into(None, &mut buffer).unwrap();
+    assert_eq!(head.unwrap().tag, *b"odd ");
+    assert_eq!(buffer, b"abc");
+    assert_eq!(buffer.capacity(), capacity);
+
+    // Past the pad byte
+    let head = reader.read_next_chunk_into(Some(*b"even"), &mut buffer);
+    assert_eq!(head.unwrap().unwrap().size, 2);
+    assert_eq!(buffer, b"de");
+    assert_eq!(
+        reader.read_next_chunk_into(None, &mut buffer).unwrap(),
+        None
+    );
+}
+
+#[test]
+fn sampler_chunk_manufacturer() {
+    let sampler = SamplerChunk {
+        manufacturer: 0x0100_0047,
+        product: 0x5E,
+        sample_period: 22675,
+        midi_unity_note: 60,
+        midi_pitch_fraction: 0,
+        smpte_format: 0,
+        smpte_offset: 0,
+        loops: vec![SampleLoop {
+            cue_point_id: 1,
+            loop_type: 0,
+            start: 100,
+            end: 199,
+            fraction: 0,
+            play_count: 0,
+        }],
+        sampler_data: vec![1, 2, 3],
+    };
+    let bytes = sampler.as_bytes();
+    assert_eq!(bytes.len(), 36 + 24 + 3);
+    assert_eq!(SamplerChunk::parse(&bytes).unwrap(), sampler);
+
+    assert_eq!(sampler.manufacturer(), Manufacturer::SysEx(0x47));
+    assert_eq!(sampler.manufacturer().name(), Some("Akai"));
+    assert_eq!(
+        Manufacturer::from_field(0x0300_2029),
+        Manufacturer::ExtendedSysEx([0, 0x20, 0x29])
+    );
+    assert_eq!(Manufacturer::from_field(0), Manufacturer::Raw(0));
+    assert_eq!(Manufacturer::from_field(0x47), Manufacturer::Raw(0x47));
+    assert_eq!(Manufacturer::SysEx(0x7D).name(), None);
+
+    // Loops and data declared past the end are cut short
+    let short = SamplerChunk::parse(&bytes[..50]).unwrap();
+    assert!(short.loops.is_empty());
+    assert!(short.sampler_data.is_empty());
+    let no_data = SamplerChunk::parse(&bytes[..61]).unwrap();
+    assert_eq!(no_data.loops, sampler.loops);
+    assert_eq!(no_data.sampler_data, [1]);
+    assert!(SamplerChunk::parse(&bytes[..35]).is_err());
+
+    let mut wave = build_wave(&[]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut wave)).unwrap();
+    writer.append_sampler_chunk(&sampler).unwrap();
+    let mut reader = ChunkReader::new(io::Cursor::new(&wave[..])).unwrap();
+    let (_, body) = reader.read_next_chunk(Some(*b"smpl")).unwrap().unwrap();
+    assert_eq!(body, bytes);
+}
+
+// Stream whose writes fail once, after `budget` more bytes are written, for
+// injecting an error partway through a write
+struct FailingCursor<T> {
+    inner: T,
+    budget: Option<usize>,
+}
+
+impl<T: io::Read> io::Read for FailingCursor<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl<T: io::Write> io::Write for FailingCursor<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.budget {
+            Some(0) => {
+                self.budget = None;
+                Err(io::Error::other("injected write error"))
+            }
+            Some(budget) => {
+                let n = self.inner.write(&buf[..budget.min(buf.len())])?;
+                self.budget = Some(budget - n);
+                Ok(n)
+            }
+            None => self.inner.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<T: io::Seek> io::Seek for FailingCursor<T> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+#[test]
+fn failed_append_leaves_wave_unchanged() {
+    use io::Read as _;
+
+    let wave = build_wave(&[
+        (
+            ChunkHead {
+                tag: *b"fmt ",
+                size: 16,
+            },
+            Some(&pcm_format_bytes(1, 8000, 8)),
+        ),
+        (
+            ChunkHead {
+                tag: *b"data",
+                size: 3,
+            },
+            Some(b"abc"),
+        ),
+    ]);
+
+    // Failing within the chunk head, body, pad byte, and RIFF size
+    for budget in 0..18 {
+        let mut bytes = wave.clone();
+        let cursor = FailingCursor {
+            inner: io::Cursor::new(&mut bytes),
+            budget: Some(budget),
+        };
+        let mut writer = ChunkWriter::new(cursor).unwrap();
+        assert!(writer.append_chunk(*b"note", b"hello").is_err());
+        writer.restore_cursor().unwrap();
+        assert_eq!(bytes[..wave.len()], wave[..], "failing after {budget}");
+
+        let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+        writer.truncate_to_wave_end().unwrap();
+        assert_eq!(bytes, wave);
+    }
+
+    let mut bytes = wave.clone();
+    let cursor = FailingCursor {
+        inner: io::Cursor::new(&mut bytes),
+        budget: Some(18),
+    };
+    let mut writer = ChunkWriter::new(cursor).unwrap();
+    writer.append_chunk(*b"note", b"hello").unwrap();
+    assert_eq!(bytes.len(), wave.len() + 14);
+
+    // Failing while upgrading to RF64, in the RIFF header and ds64 chunk
+    let note = [0x55u8; 200];
+    let mut original = large_wave(u32::MAX - 200, true);
+    let mut header = [0; 64];
+    original.read_exact(&mut header).unwrap();
+
+    for budget in [208 + 4, 208 + 8 + 8 + 14] {
+        let mut file = large_wave(u32::MAX - 200, true);
+        let cursor = FailingCursor {
+            inner: &mut file,
+            budget: Some(budget),
+        };
+        let mut writer = ChunkWriter::new(cursor).unwrap();
+        assert!(writer.append_chunk(*b"note", &note).is_err());
+        writer.restore_cursor().unwrap();
+
+        let mut restored = [0; 64];
+        file.read_exact(&mut restored).unwrap();
+        assert_eq!(restored, header);
+        file.rewind().unwrap();
+        let mut reader = ChunkReader::new(&mut file).unwrap();
+        assert_eq!(reader.ds64(), None);
+        assert_eq!(reader.summary().unwrap().order.len(), 3);
+    }
+}
+
+#[test]
+fn detect_missing_cue_count() {
+    let cues = [
+        CuePoint::from_sample_offset(7, 100),
+        CuePoint::from_sample_offset(8, 200),
+    ];
+    let entries = cues.iter().flat_map(CuePoint::as_bytes).collect::<Vec<_>>();
+
+    let mut counted = 2u32.to_le_bytes().to_vec();
+    counted.extend_from_slice(&entries);
+    assert_eq!(
+        parse_cue_points_detect(&counted),
+        (cues.to_vec(), CueLayout::Counted)
+    );
+
+    assert_eq!(
+        parse_cue_points_detect(&entries),
+        (cues.to_vec(), CueLayout::Uncounted)
+    );
+    // Read with a count, the first cue point is lost
+    assert_eq!(parse_cue_points(&entries).len(), 1);
+
+    assert_eq!(
+        parse_cue_points_detect(&counted[..30]),
+        (cues[..1].to_vec(), CueLayout::Counted)
+    );
+    assert_eq!(parse_cue_points_detect(&[]), (vec![], CueLayout::Counted));
+}
+
+#[test]
+fn visit_chunks_within_lists() {
+    let mut nested = Vec::from(&b"INFO"[..]);
+    nested.extend_from_slice(b"INAM\x03\0\0\0ab\0\0");
+    let list = adtl_bytes(&[
+        (b"labl", 6, b"\x01\0\0\0a\0"),
+        (b"LIST", nested.len() as u32, &nested),
+        (b"note", 99, b"cut short"),
+    ]);
+    let wave = build_wave(&[
+        (
+            ChunkHead {
+                tag: *b"LIST",
+                size: list.len() as u32,
+            },
+            Some(&list),
+        ),
+        (
+            ChunkHead {
+                tag: *b"data",
+                size: 2,
+            },
+            Some(b"ab"),
+        ),
+    ]);
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&wave)).unwrap();
+    let mut found = vec![];
+    reader
+        .visit_nested(&mut |path, head, body| {
+            found.push((path.concat(), head.tag, body.len()));
+            Ok(())
+        })
+        .unwrap();
+
+    let found = found
+        .iter()
+        .map(|(path, tag, len)| (&path[..], tag, *len))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        found,
+        [
+            (&b""[..], b"LIST", list.len()),
+            (b"LISTadtl", b"labl", 6),
+            (b"LISTadtl", b"LIST", nested.len()),
+            (b"LISTadtlLISTINFO", b"INAM", 3),
+            (b"", b"data", 0),
+        ]
+    );
+    assert_eq!(reader.read_next_chunk(None).unwrap().unwrap().0, tag::LIST);
+
+    // Chunks other than LISTs are skipped, however large
+    reader.set_max_chunk_size(list.len() as u32);
+    let mut sizes = vec![];
+    reader
+        .visit_nested(&mut |path, head, _| {
+            if path.is_empty() {
+                sizes.push(head.size);
+            }
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(sizes, [list.len() as u32, 2]);
+
+    reader.set_max_chunk_size(1);
+    assert!(reader
+        .visit_nested(&mut |_, head, _| {
+            assert_ne!(head.tag, *b"data");
+            Ok(())
+        })
+        .is_err());
+}
+
+#[test]
+fn read_wave_embedded_in_container() {
+    let cues = [
+        CuePoint::from_sample_offset(1, 100),
+        CuePoint::from_sample_offset(2, 200),
+    ];
+    let mut named = LabeledText::from_cue_length(2, 50);
+    named.text = String::from("chorus");
+
+    let mut wave = build_wave(&[]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut wave)).unwrap();
+    writer.append_cue_chunk(&cues).unwrap();
+    writer.append_label_chunk(&[named.clone()]).unwrap();
+
+    let mut standalone = ChunkReader::new(io::Cursor::new(&wave[..])).unwrap();
+    let markers = standalone.read_markers().unwrap();
+
+    // Odd offset, with more bytes of the container following the WAVE
+    let mut container = b"ASSET\0\0\0\x01\0\0".repeat(3);
+    let offset = container.len() as u64;
+    container.extend_from_slice(&wave);
+    container.extend_from_slice(b"NEXT ASSET");
+
+    let mut reader =
+        ChunkReader::new_at(io::Cursor::new(&container[..]), offset).unwrap();
+    assert_eq!(reader.wave_start(), offset);
+    assert_eq!(reader.wave_end(), offset + wave.len() as u64);
+    assert_eq!(reader.read_markers().unwrap(), markers);
+    assert_eq!(reader.read_labeled_texts().unwrap(), vec![named]);
+    assert_eq!(
+        reader
+            .markers_iter()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+            .len(),
+        2
+    );
+
+    let (_, body) = reader.read_next_chunk(Some(tag::CUE)).unwrap().unwrap();
+    assert_eq!(parse_cue_points(&body), cues);
+    assert_eq!(reader.read_next_chunk(None).unwrap().unwrap().0, tag::LIST);
+    assert_eq!(reader.read_next_chunk(None).unwrap(), None);
+
+    let mut cursor = reader.restore_cursor().unwrap();
+    assert_eq!(cursor.stream_position().unwrap(), offset);
+    assert!(is_wave(&mut cursor));
+
+    let junk = ChunkReader::new_at(io::Cursor::new(&container[..]), 1);
+    assert!(junk.is_err());
+}
+
+#[test]
+fn classify_regions_and_points() {
+    let mut region = LabeledText::from_cue_length(1, 480);
+    region.purpose_id = *b"rgn ";
+    region.text = String::from("verse");
+    let quake_loop = LabeledText::from_cue_length(2, 96);
+    let mut point = LabeledText::from_cue_length(3, 0);
+    point.purpose_id = *b"rgn ";
+
+    assert_eq!(
+        region.as_region(1000),
+        Some(Region {
+            start: 1000,
+            length: 480,
+            name: String::from("verse"),
+        })
+    );
+    assert_eq!(
+        quake_loop.as_region(0).map(|region| region.length),
+        Some(96)
+    );
+    assert_eq!(point.as_region(1000), None);
+
+    let cues = [
+        CuePoint::from_sample_offset(1, 1000),
+        CuePoint::from_sample_offset(2, 2000),
+        CuePoint::from_sample_offset(3, 3000),
+    ];
+    let mut bytes = build_wave(&[]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.append_cue_chunk(&cues).unwrap();
+    writer
+        .append_label_chunk(&[region, quake_loop, point])
+        .unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let regions = reader
+        .read_markers()
+        .unwrap()
+        .iter()
+        .map(Marker::as_region)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        regions,
+        [
+            Some(Region {
+                start: 1000,
+                length: 480,
+                name: String::from("verse"),
+            }),
+            Some(Region {
+                start: 2000,
+                length: 96,
+                name: String::new(),
+            }),
+            None,
+        ]
+    );
+    assert_eq!(
+        reader.read_regions().unwrap(),
+        regions.into_iter().flatten().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn pad_labeled_text_bytes() {
+    let mut ltxt = LabeledText::from_cue_length(1, 0);
+    ltxt.text = String::from("even");
+    assert_eq!(ltxt.as_bytes().len(), 24);
+    assert_eq!(ltxt.as_bytes_padded(), ltxt.as_bytes());
+
+    ltxt.text = String::from("odd");
+    let padded = ltxt.as_bytes_padded();
+    assert_eq!(padded.len(), 24);
+    assert_eq!(padded[..23], ltxt.as_bytes()[..]);
+    assert_eq!(padded[23], 0);
+
+    // A LIST composed by hand from padded bytes reads back
+    let mut list = Vec::from(&b"adtl"[..]);
+    for text in ["odd", "even"] {
+        ltxt.text = String::from(text);
+        list.extend_from_slice(b"ltxt");
+        list.extend_from_slice(&(ltxt.as_bytes().len() as u32).to_le_bytes());
+        list.extend_from_slice(&ltxt.as_bytes_padded());
+    }
+    let texts = extract_labeled_text_from_list(&list)
+        .into_iter()
+        .map(|ltxt| ltxt.text)
+        .collect::<Vec<_>>();
+    assert_eq!(texts, ["odd", "even"]);
+}
+
+#[test]
+fn pair_cue_points_into_regions() {
+    let marker = |id, offset, label: &str, length| Marker {
+        cue: CuePoint::from_sample_offset(id, offset),
+        label: Some(String::from(label)),
+        region_length: length,
+        purpose_id: None,
+    };
+    let region = |start, length, name: &str| Region {
+        start,
+        length,
+        name: String::from(name),
+    };
+    let markers = [
+        marker(1, 0, "Verse", None),
+        marker(2, 100, "Verse (end)", None),
+        marker(3, 150, "Bridge", Some(30)),
+        marker(4, 200, "Verse", None),
+        marker(5, 300, "Verse END", None),
+        marker(6, 400, "Hit", None),
+        marker(7, 500, "Coda (End)", None),
+    ];
+
+    assert_eq!(
+        regions_from_cue_pairs(&markers, RegionStrategy::CuePairs),
+        [
+            region(0, 100, "Verse"),
+            region(150, 30, "Bridge"),
+            region(200, 100, "Verse"),
+        ]
+    );
+    assert_eq!(
+        regions_from_cue_pairs(&markers, RegionStrategy::SampleLength),
+        [region(150, 30, "Bridge")]
+    );
+
+    let mut bytes = build_wave(&[]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.append_markers(&markers).unwrap();
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(
+        reader
+            .read_regions_with(RegionStrategy::CuePairs)
+            .unwrap()
+            .len(),
+        3
+    );
+    assert_eq!(
+        reader.read_regions_with(RegionStrategy::default()).unwrap(),
+        [region(150, 30, "Bridge")]
+    );
+}
+
+// Decoder of a made-up vendor chunk holding a u16 tempo
+struct TempoDecoder;
+
+impl ChunkDecoder for TempoDecoder {
+    fn tag(&self) -> [u8; 4] {
+        *b"tmpo"
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<Box<dyn std::any::Any>, Error> {
+        let tempo = bytes
+            .first_chunk::<2>()
+            .ok_or(io::Error::from(io::ErrorKind::UnexpectedEof))?;
+        Ok(Box::new(u16::from_le_bytes(*tempo)))
+    }
+
+    fn describe(&self, value: &dyn std::any::Any) -> Option<String> {
+        value.downcast_ref::<u16>().map(u16::to_string)
+    }
+}
+
+// Decoder of "cue " chunks standing in for the built-in one, giving the size
+struct CueSizeDecoder;
+
+impl ChunkDecoder for CueSizeDecoder {
+    fn tag(&self) -> [u8; 4] {
+        *b"cue "
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<Box<dyn std::any::Any>, Error> {
+        Ok(Box::new(bytes.len()))
+    }
+
+    fn describe(&self, value: &dyn std::any::Any) -> Option<String> {
+        value.downcast_ref::<usize>().map(usize::to_string)
+    }
+}
+
+struct InfoDecoder;
+
+impl ChunkDecoder for InfoDecoder {
+    fn tag(&self) -> [u8; 4] {
+        *b"LIST"
+    }
+
+    fn list_type(&self) -> Option<[u8; 4]> {
+        Some(*b"INFO")
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<Box<dyn std::any::Any>, Error> {
+        Ok(Box::new(InfoMetadata::parse(bytes)?))
+    }
+}
+
+#[test]
+fn decode_registered_chunks() {
+    let cues = [CuePoint::from_sample_offset(1, 10)];
+    let mut bytes = build_wave(&[
+        (
+            ChunkHead {
+                tag: *b"fmt ",
+                size: 16,
+            },
+            Some(&pcm_format_bytes(2, 44100, 16)),
+        ),
+        (
+            ChunkHead {
+                tag: *b"tmpo",
+                size: 2,
+            },
+            Some(&120u16.to_le_bytes()),
+        ),
+        (
+            ChunkHead {
+                tag: *b"tmpo",
+                size: 1,
+            },
+            Some(&[0]),
+        ),
+    ]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.append_cue_chunk(&cues).unwrap();
+    writer
+        .append_label_chunk(&[LabeledText::from_cue_length(1, 5)])
+        .unwrap();
+    let info = InfoMetadata {
+        title: Some(String::from("Take 1")),
+        ..InfoMetadata::default()
+    };
+    writer.append_info_chunk(&info).unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let builtin = reader.decode_chunks(&ChunkDecoders::new()).unwrap();
+    assert_eq!(builtin.len(), 3);
+    let format = builtin[0].value.as_ref().unwrap();
+    assert_eq!(format.downcast_ref::<FormatChunk>().unwrap().channels, 2);
+    let decoded_cues = builtin[1].value.as_ref().unwrap();
+    assert_eq!(
+        decoded_cues.downcast_ref::<Vec<CuePoint>>().unwrap(),
+        &cues.to_vec()
+    );
+    let ltxts = builtin[2].value.as_ref().unwrap();
+    assert_eq!(ltxts.downcast_ref::<Vec<LabeledText>>().unwrap().len(), 1);
+
+    // LIST decoders are kept apart by list type
+    let mut decoders = ChunkDecoders::new();
+    decoders.register(InfoDecoder);
+    let lists = reader.decode_chunks(&decoders).unwrap();
+    assert_eq!(lists.len(), 4);
+    let ltxts = lists[2].value.as_ref().unwrap();
+    assert_eq!(ltxts.downcast_ref::<Vec<LabeledText>>().unwrap().len(), 1);
+    let decoded_info = lists[3].value.as_ref().unwrap();
+    assert_eq!(decoded_info.downcast_ref::<InfoMetadata>(), Some(&info));
+    assert_eq!(
+        format!("{:?}", decoders),
+        "ChunkDecoders { tags: [\"cue \", \"fmt \", \"LIST/adtl\", \
+         \"LIST/INFO\"] }"
+    );
+
+    let mut decoders = ChunkDecoders::empty();
+    decoders.register(TempoDecoder);
+    assert!(decoders.get(&tag::CUE, None).is_none());
+    let tempos = reader.decode_chunks(&decoders).unwrap();
+    assert_eq!(tempos.len(), 2);
+    let tempo = tempos[0].value.as_ref().unwrap();
+    assert_eq!(tempo.downcast_ref::<u16>(), Some(&120));
+    assert!(tempos[1].value.is_err());
+    assert_eq!(
+        format!("{:?}", decoders),
+        "ChunkDecoders { tags: [\"tmpo\"] }"
+    );
+
+    let description = reader.describe_with(&decoders).unwrap();
+    assert!(description.contains("\"size\": 2, \"decoded\": 120}"));
+    assert!(description.contains("\"size\": 1, \"error\": "));
+    assert!(reader
+        .describe()
+        .unwrap()
+        .contains("\"size\": 2, \"unparsed\": 2}"));
+
+    // Registered decoders take the place of the built-in ones
+    let mut decoders = ChunkDecoders::new();
+    decoders.register(CueSizeDecoder);
+    let description = reader.describe_with(&decoders).unwrap();
+    assert!(description.contains("\"size\": 28, \"decoded\": 28}"));
+    assert!(description.contains("\"fmt\": {\"format_tag\": 1, "));
+    assert!(description.contains("\"list_type\": \"adtl\", \"labels\": "));
+    assert!(!description.contains("\"cue_points\""));
+}
+
+#[test]
+fn validate_cue_chunk_sizes() {
+    let cues = [
+        CuePoint::from_sample_offset(1, 10),
+        CuePoint::from_sample_offset(2, 20),
+    ];
+    let (_, payload) = cue_chunk(&cues).unwrap();
+    let head = |size| ChunkHead {
+        tag: tag::CUE,
+        size,
+    };
+    assert!(validate_cue_chunk(&head(52), &payload).is_ok());
+
+    // Head disagreeing with the count, then a count disagreeing with both
+    assert!(validate_cue_chunk(&head(28), &payload[..28]).is_err());
+    let mut overcounted = payload.clone();
+    overcounted[0] = 3;
+    assert!(validate_cue_chunk(&head(52), &overcounted).is_err());
+    assert!(validate_cue_chunk(&head(76), &overcounted).is_err());
+
+    assert!(validate_cue_chunk(&head(52), &payload[..40]).is_err());
+    assert!(validate_cue_chunk(&head(2), &payload[..2]).is_err());
+    let list = ChunkHead {
+        tag: tag::LIST,
+        size: 52,
+    };
+    assert!(validate_cue_chunk(&list, &payload).is_err());
+
+    let mut bytes = build_wave(&[]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.append_cue_chunk(&cues).unwrap();
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let mut payload = vec![];
+    let head = reader
+        .read_next_chunk_into(Some(tag::CUE), &mut payload)
+        .unwrap()
+        .unwrap();
+    assert!(validate_cue_chunk(&head, &payload).is_ok());
+}
+
+#[test]
+fn merge_markers_of_two_takes() {
+    let marker = |id, offset, label: &str, length| Marker {
+        cue: CuePoint::from_sample_offset(id, offset),
+        label: Some(String::from(label)),
+        region_length: length,
+        purpose_id: length.map(|_| *b"rgn "),
+    };
+
+    let mut dest = build_wave(&[]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut dest)).unwrap();
+    writer
+        .append_markers(&[
+            marker(1, 10, "take 1", None),
+            marker(2, 50, "a", None),
+        ])
+        .unwrap();
+
+    let mut source = build_wave(&[]);
+    let mut source_writer =
+        ChunkWriter::new(io::Cursor::new(&mut source)).unwrap();
+    source_writer
+        .append_markers(&[
+            marker(1, 0, "take 2", None),
+            marker(3, 20, "solo", Some(40)),
+        ])
+        .unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&source[..])).unwrap();
+    merge_markers_into(&mut writer, &mut reader, 1000).unwrap();
+
+    let mut merged = ChunkReader::new(io::Cursor::new(&dest[..])).unwrap();
+    assert_eq!(merged.summary().unwrap().tags[&tag::CUE].0, 1);
+    assert_eq!(
+        merged.read_markers().unwrap(),
+        [
+            marker(1, 10, "take 1", None),
+            marker(2, 50, "a", None),
+            marker(4, 1000, "take 2", None),
+            marker(3, 1020, "solo", Some(40)),
+        ]
+    );
+
+    // Shifted past the last sample offset
+    let before = dest.clone();
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut dest)).unwrap();
+    assert!(merge_markers_into(&mut writer, &mut reader, u32::MAX).is_err());
+    assert_eq!(dest, before);
+}
+
+#[test]
+fn read_all_but_the_samples() {
+    let data_size = u32::MAX - 200;
+    let mut file = large_wave(data_size, false);
+    let mut writer = ChunkWriter::new(&mut file).unwrap();
+    writer.append_chunk(*b"note", b"after").unwrap();
+    file.rewind().unwrap();
+
+    let mut reader = ChunkReader::new_unbounded(&mut file).unwrap();
+    let chunks = reader.read_all_except(&[tag::DATA]).unwrap();
+    let tags = chunks.iter().map(|(tag, _)| *tag).collect::<Vec<_>>();
+    assert_eq!(tags, [tag::FMT, *b"note"]);
+    assert_eq!(chunks[1].1, b"after");
+    assert_eq!(reader.read_next_chunk(None).unwrap().unwrap().0, tag::FMT);
+
+    let small = build_wave(&[(
+        ChunkHead {
+            tag: *b"data",
+            size: 3,
+        },
+        Some(b"abc"),
+    )]);
+    let mut reader = ChunkReader::new(io::Cursor::new(&small[..])).unwrap();
+    assert_eq!(
+        reader.read_all_except(&[]).unwrap(),
+        [(tag::DATA, b"abc".to_vec())]
+    );
+}
+
+#[test]
+fn align_cues_to_bext_time_reference() {
+    // One hour into the day at 48 kHz
+    let time_reference = 3600u64 * 48000;
+    let mut bext = vec![b' '; 602];
+    bext[338..346].copy_from_slice(&time_reference.to_le_bytes());
+
+    let wave = build_wave(&[(
+        ChunkHead {
+            tag: *b"bext",
+            size: 602,
+        },
+        Some(&bext),
+    )]);
+    let mut reader = ChunkReader::new(io::Cursor::new(&wave[..])).unwrap();
+    assert_eq!(
+        reader.read_bext_time_reference().unwrap(),
+        Some(time_reference)
+    );
+
+    let cue = CuePoint::from_sample_offset(1, 480);
+    assert_eq!(cue.absolute_sample(time_reference), time_reference + 480);
+    assert_eq!(cue.absolute_sample(u64::MAX), u64::MAX);
+
+    let mut reader =
+        ChunkReader::new(io::Cursor::new(build_wave(&[]))).unwrap();
+    assert_eq!(reader.read_bext_time_reference().unwrap(), None);
+
+    let short = build_wave(&[(
+        ChunkHead {
+            tag: *b"bext",
+            size: 340,
+        },
+        Some(&bext[..340]),
+    )]);
+    let mut reader = ChunkReader::new(io::Cursor::new(&short[..])).unwrap();
+    assert!(reader.read_bext_time_reference().is_err());
+
+    // RIFX, with each half of the time reference big-endian
+    let mut rifx = Vec::from(&b"RIFX\0\0\x02\x66WAVEbext\0\0\x02\x5a"[..]);
+    rifx.extend_from_slice(&bext);
+    rifx[12 + 8 + 338..12 + 8 + 346].copy_from_slice(&[0, 0, 0, 2, 0, 0, 0, 1]);
+    let mut reader = ChunkReader::new(io::Cursor::new(&rifx[..])).unwrap();
+    assert_eq!(
+        reader.read_bext_time_reference().unwrap(),
+        Some(0x1_0000_0002)
+    );
+}
+
+#[test]
+fn write_info_list() {
+    let info = InfoMetadata {
+        title: Some(String::from("Take 3")),
+        artist: Some(String::from("The Band")),
+        software: Some(String::from("cuet")),
+        ..InfoMetadata::default()
+    };
+
+    let mut bytes = build_wave(&[]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.append_info_chunk(&info).unwrap();
+    writer.append_info_chunk(&InfoMetadata::default()).unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let (_, body) = reader.read_next_chunk(Some(tag::LIST)).unwrap().unwrap();
+    assert_eq!(reader.read_next_chunk(None).unwrap(), None);
+    assert_eq!(
+        body,
+        b"INFO\
+          INAM\x07\0\0\0Take 3\0\0\
+          IART\x09\0\0\0The Band\0\0\
+          ISFT\x05\0\0\0cuet\0\0"
+    );
+    assert_eq!(InfoMetadata::parse(&body).unwrap(), info);
+    assert!(InfoMetadata::parse(b"adtl").is_err());
+
+    let mut bytes = build_wave(&[]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.set_allow_empty(true);
+    writer.append_info_chunk(&InfoMetadata::default()).unwrap();
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let (_, body) = reader.read_next_chunk(None).unwrap().unwrap();
+    assert_eq!(body, b"INFO");
+}
+
+#[test]
+fn replace_every_cue_chunk() {
+    let mut bytes = build_wave(&[(
+        ChunkHead {
+            tag: *b"data",
+            size: 5,
+        },
+        None,
+    )]);
+    let cue_ids = |bytes: &[u8]| {
+        let mut reader = ChunkReader::new(io::Cursor::new(bytes)).unwrap();
+        reader
+            .read_markers()
+            .unwrap()
+            .iter()
+            .map(|marker| (marker.cue.id, marker.cue.sample_offset))
+            .collect::<Vec<_>>()
+    };
+
+    // Without a cue chunk, one is appended
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer
+        .set_cue_points(&[CuePoint::from_sample_offset(1, 10)])
+        .unwrap();
+    writer.append_chunk(*b"iXML", b"<x/>").unwrap();
+    writer
+        .append_cue_chunk(&[
+            CuePoint::from_sample_offset(2, 20),
+            CuePoint::from_sample_offset(3, 30),
+        ])
+        .unwrap();
+
+    let cues = [
+        CuePoint::from_sample_offset(4, 40),
+        CuePoint::from_sample_offset(5, 50),
+    ];
+    writer.set_cue_points(&cues).unwrap();
+    writer.restore_cursor().unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(reader.wave_len(), bytes.len() as u64);
+    assert_eq!(
+        reader.summary().unwrap().order,
+        vec![*b"data", *b"cue ", *b"iXML"]
+    );
+    assert_eq!(cue_ids(&bytes), [(4, 40), (5, 50)]);
+
+    // The same size overwrites in place
+    let before = bytes.clone();
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer
+        .set_cue_points(&[cues[1], CuePoint::from_sample_offset(6, 60)])
+        .unwrap();
+    assert_eq!(bytes.len(), before.len());
+    assert_eq!(bytes[..38], before[..38]);
+    assert_eq!(bytes[86..], before[86..]);
+    assert_eq!(cue_ids(&bytes), [(5, 50), (6, 60)]);
+
+    // Sorted when asked to
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.set_sort_cue_points(true);
+    writer
+        .set_cue_points(&[CuePoint::from_sample_offset(6, 60), cues[1]])
+        .unwrap();
+    assert_eq!(cue_ids(&bytes), [(5, 50), (6, 60)]);
+
+    // Without cue points, an empty chunk is kept only if allowed
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.set_allow_empty(true);
+    writer.set_cue_points(&[]).unwrap();
+    assert_eq!(bytes.len(), before.len() - 48);
+    assert_eq!(cue_ids(&bytes), []);
+
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.set_cue_points(&[]).unwrap();
+    writer.set_cue_points(&[]).unwrap();
+    assert_eq!(bytes.len(), before.len() - 60);
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(reader.summary().unwrap().order, vec![*b"data", *b"iXML"]);
+}
+
+#[test]
+fn visit_deeply_nested_lists() {
+    let depth = 200_000;
+    let mut list = Vec::with_capacity(depth * 12 + 12);
+
+    for level in 0..depth {
+        let size = ((depth - level) * 12 + 4) as u32;
+        list.extend_from_slice(b"LIST");
+        list.extend_from_slice(&size.to_le_bytes());
+        list.extend_from_slice(b"deep");
+    }
+
+    list.extend_from_slice(b"note\x04\0\0\0abcd");
+    let list = &list[8..];
+    let wave = build_wave(&[(
+        ChunkHead {
+            tag: *b"LIST",
+            size: list.len() as u32,
+        },
+        Some(list),
+    )]);
+
+    let mut reader =
+        ChunkReader::new_unbounded(io::Cursor::new(&wave)).unwrap();
+    let mut lists = 0;
+    let mut deepest = None;
+    reader
+        .visit_nested(&mut |path, head, body| {
+            match &head.tag {
+                b"LIST" => lists += 1,
+                _ => deepest = Some((path.len(), body.to_vec())),
+            }
+            Ok(())
+        })
+        .unwrap();
+
+    assert_eq!(lists, depth);
+    assert_eq!(deepest, Some((2 * depth, b"abcd".to_vec())));
+}
+
+#[test]
+fn read_ds64_without_trusting_its_size() {
+    let ds64 = Ds64Chunk {
+        riff_size: 4 + 36,
+        table: vec![(*b"big1", 1 << 33)],
+        ..Ds64Chunk::default()
+    };
+    let mut body = ds64.as_bytes();
+    body[24..28].copy_from_slice(&u32::MAX.to_le_bytes());
+
+    let mut bytes =
+        Vec::from(&b"RF64\xff\xff\xff\xffWAVEds64\xf0\xff\xff\xff"[..]);
+    bytes.extend_from_slice(&body);
+
+    let reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(reader.ds64(), Some(&ds64));
+
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    assert_eq!(writer.read_ds64().unwrap(), Some(ds64));
+}
+
+#[test]
+fn read_back_rifx_cues_and_labels() {
+    let mut bytes = Vec::from(&b"RIFX\0\0\0\x26WAVEfmt \0\0\0\x10"[..]);
+    bytes.extend_from_slice(&[0; 16]);
+    bytes.extend_from_slice(b"data\0\0\0\x02\x12\x34");
+
+    let cues = [
+        CuePoint::from_sample_offset(0x0102, 0x0304),
+        CuePoint::from_sample_offset(7, 1),
+    ];
+    let mut ltxt = LabeledText::from_cue_length(0x0102, 0x0506);
+    ltxt.code_page = 0x0708;
+    ltxt.text = String::from("loop");
+    let label = Label {
+        cue_id: 7,
+        text: String::from("hit"),
+    };
+
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.append_cue_chunk(&cues).unwrap();
+    writer.append_label_chunk(&[ltxt.clone()]).unwrap();
+    writer.append_labl_chunk(&[label]).unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(reader.cue_count().unwrap(), 2);
+    assert_eq!(
+        reader
+            .cue_points_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap(),
+        cues
+    );
+    assert_eq!(reader.read_labeled_texts().unwrap(), [ltxt]);
+
+    let markers = reader.read_markers().unwrap();
+    assert_eq!(markers.len(), 2);
+    assert_eq!(markers[0].cue, cues[0]);
+    assert_eq!(markers[0].region_length, Some(0x0506));
+    assert_eq!(markers[0].label.as_deref(), Some("loop"));
+    assert_eq!(markers[1].label.as_deref(), Some("hit"));
+    assert_eq!(
+        reader
+            .markers_iter()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap(),
+        markers
+    );
+    assert!(reader
+        .verify()
+        .unwrap()
+        .iter()
+        .all(|warning| !matches!(warning, Warning::OrphanLabel { .. })));
+    assert!(reader.describe().unwrap().contains("\"cue_id\": 258"));
+}
+
+#[test]
+fn iterate_many_markers() {
+    let cues = (0..3000)
+        .map(|id| CuePoint::from_sample_offset(id, id * 10))
+        .collect::<Vec<_>>();
+    let labels = cues
+        .iter()
+        .rev()
+        .map(|cue| Label {
+            cue_id: cue.id,
+            text: format!("marker {}", cue.id),
+        })
+        .collect::<Vec<_>>();
+    let ltxts = cues
+        .iter()
+        .step_by(2)
+        .map(|cue| LabeledText::from_cue_length(cue.id, 5))
+        .collect::<Vec<_>>();
+
+    let mut bytes = build_wave(&[]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer.append_cue_chunk(&cues).unwrap();
+    writer.append_label_chunk(&ltxts).unwrap();
+    writer.append_labl_chunk(&labels).unwrap();
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let mut lazy = reader
+        .markers_iter()
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    lazy.sort_by_key(|marker| marker.cue.id);
+    assert_eq!(lazy, reader.read_markers().unwrap());
+    assert_eq!(lazy[2].region_length, Some(5));
+    assert_eq!(lazy[2].label.as_deref(), Some("marker 2"));
+}
+
+#[test]
+fn append_past_largest_seekable_size() {
+    let riff_size = i64::MAX as u64 - 16;
+    let ds64 = Ds64Chunk {
+        riff_size,
+        ..Ds64Chunk::default()
+    };
+    let mut bytes = Vec::from(&b"RF64\xff\xff\xff\xffWAVEds64\x1c\0\0\0"[..]);
+    bytes.extend_from_slice(&ds64.as_bytes());
+
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    let err = writer.append_chunk(*b"note", b"abcd").unwrap_err();
+    assert_eq!(
+        err.kind(),
+        ErrorKind::ChunkTooBig {
+            needed: riff_size + 12,
+            limit: i64::MAX as u64 - 8,
+        }
+    );
+    assert_eq!(bytes.len(), 48);
+}
+
+#[test]
+fn coalesce_cue_chunks_near_id_limits() {
+    let mut bytes = build_wave(&[]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer
+        .append_cue_chunk(&[
+            CuePoint::from_sample_offset(u32::MAX, 10),
+            CuePoint::from_sample_offset(0, 20),
+        ])
+        .unwrap();
+    writer
+        .append_cue_chunk(&[CuePoint::from_sample_offset(u32::MAX, 30)])
+        .unwrap();
+    assert_eq!(writer.coalesce_cue_chunks().unwrap(), 1);
+
+    let mut reader = ChunkReader::new(io::Cursor::new(&bytes[..])).unwrap();
+    let cues = reader
+        .read_markers()
+        .unwrap()
+        .iter()
+        .map(|marker| (marker.cue.id, marker.cue.sample_offset))
+        .collect::<Vec<_>>();
+    assert_eq!(cues, [(u32::MAX, 10), (0, 20), (1, 30)]);
+
+    // A cue chunk claiming more than the maximum chunk size is not read
+    let mut bytes = build_wave(&[]);
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    writer
+        .append_cue_chunk(&[CuePoint::from_sample_offset(1, 10)])
+        .unwrap();
+    let claimed = DEFAULT_MAX_CHUNK_SIZE + 2;
+    bytes.extend_from_slice(b"cue ");
+    bytes.extend_from_slice(&claimed.to_le_bytes());
+    let riff_size = bytes.len() as u32 - 8 + claimed;
+    bytes[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+    let mut writer = ChunkWriter::new(io::Cursor::new(&mut bytes)).unwrap();
+    let err = writer.coalesce_cue_chunks().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "Wave: Chunk size {} exceeds maximum of {}\n",
+            claimed, DEFAULT_MAX_CHUNK_SIZE
+        )
+    );
+}
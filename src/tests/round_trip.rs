@@ -0,0 +1,182 @@
+use super::{adtl_bytes, xorshift};
+use crate::testing::build_wave;
+use crate::{
+    extract_labeled_text_from_list, extract_labels_from_list, parse_cue_points,
+    parse_cue_points_detect, try_parse_cue_points, ChunkHead, ChunkReader,
+    CueCountPolicy, CuePoint, Label, LabeledText,
+};
+use std::io;
+
+fn arbitrary_text(state: &mut u64) -> String {
+    let len = xorshift(state) % 12;
+
+    (0..len)
+        .filter_map(|_| match xorshift(state) % 3 {
+            0 => char::from_u32((xorshift(state) % 0x80) as u32),
+            1 => char::from_u32((xorshift(state) % 0x800) as u32),
+            _ => char::from_u32((xorshift(state) % 0x11_0000) as u32),
+        })
+        .collect()
+}
+
+fn arbitrary_cue_point(state: &mut u64) -> CuePoint {
+    let mut word = || xorshift(state) as u32;
+
+    CuePoint {
+        id: word(),
+        position: word(),
+        data_tag: word().to_le_bytes(),
+        chunk_start: word(),
+        block_start: word(),
+        sample_offset: word(),
+    }
+}
+
+fn arbitrary_labeled_text(state: &mut u64) -> LabeledText {
+    let mut word = || xorshift(state) as u32;
+    let [country @ .., _, _] = word().to_le_bytes();
+    let [language @ .., _, _] = word().to_le_bytes();
+    let [dialect @ .., _, _] = word().to_le_bytes();
+
+    LabeledText {
+        cue_id: word(),
+        sample_length: word(),
+        purpose_id: word().to_le_bytes(),
+        country,
+        language,
+        dialect,
+        code_page: word() as u16,
+        text: arbitrary_text(state),
+    }
+}
+
+// Body of an "adtl" LIST of sub-chunks with the tags labeled text is found
+// under, and sizes that are often wrong: zero, past the end of the list, or
+// cutting the fixed-size fields short
+fn arbitrary_adtl_list(state: &mut u64) -> Vec<u8> {
+    let mut bytes = adtl_bytes(&[]);
+
+    for _ in 0..xorshift(state) % 6 {
+        let word = xorshift(state);
+        let tag = match word % 4 {
+            0 | 1 => *b"ltxt",
+            2 => *b"labl",
+            _ => *b"note",
+        };
+        let body_len = ((word >> 8) % 40) as usize;
+        let size = match (word >> 16) % 4 {
+            0 => body_len as u32,
+            1 => (word >> 24) as u32,
+            2 => (word >> 24) as u32 % 24,
+            _ => 0,
+        };
+
+        bytes.extend_from_slice(&tag);
+        bytes.extend_from_slice(&size.to_le_bytes());
+        bytes.extend((0..body_len).map(|_| xorshift(state) as u8));
+    }
+
+    bytes
+}
+
+#[test]
+fn cue_points_and_labels_round_trip() {
+    let mut state = 0x9e37_79b9_7f4a_7c15;
+
+    for _ in 0..500 {
+        let cue = arbitrary_cue_point(&mut state);
+        assert_eq!(CuePoint::try_from_bytes(&cue.as_bytes()).unwrap(), cue);
+
+        let ltxt = arbitrary_labeled_text(&mut state);
+        assert_eq!(
+            LabeledText::try_from_bytes(&ltxt.as_bytes()).unwrap(),
+            ltxt
+        );
+
+        let label = Label {
+            cue_id: cue.id,
+            text: arbitrary_text(&mut state).replace('\0', ""),
+        };
+        assert_eq!(Label::try_from_bytes(&label.as_bytes()).unwrap(), label);
+
+        let ltxt_bytes = ltxt.as_bytes();
+        let mut list = adtl_bytes(&[(
+            b"ltxt",
+            ltxt_bytes.len() as u32,
+            &ltxt.as_bytes_padded(),
+        )]);
+        list.extend_from_slice(b"ltxt\0\0\0\0");
+        assert_eq!(extract_labeled_text_from_list(&list), [ltxt]);
+
+        let mut cue_bytes = 2u32.to_le_bytes().to_vec();
+        cue_bytes.extend_from_slice(&cue.as_bytes());
+        cue_bytes.extend_from_slice(&cue.as_bytes());
+        assert_eq!(parse_cue_points(&cue_bytes), [cue, cue]);
+        assert_eq!(
+            try_parse_cue_points(&cue_bytes, CueCountPolicy::Strict)
+                .unwrap()
+                .0,
+            [cue, cue]
+        );
+    }
+}
+
+#[test]
+fn arbitrary_bytes_parse_without_panic() {
+    let mut state = 0xd1b5_4a32_d192_ed03;
+
+    for _ in 0..2000 {
+        let len = (xorshift(&mut state) % 120) as usize;
+        let bytes = (0..len)
+            .map(|_| xorshift(&mut state) as u8)
+            .collect::<Vec<_>>();
+
+        parse_cue_points(&bytes);
+        parse_cue_points_detect(&bytes);
+        let _ = try_parse_cue_points(&bytes, CueCountPolicy::Strict);
+        let _ = CuePoint::try_from_bytes(&bytes);
+        let _ = LabeledText::try_from_bytes(&bytes);
+        let _ = Label::try_from_bytes(&bytes);
+    }
+}
+
+#[test]
+fn arbitrary_adtl_lists_parse_without_panic() {
+    let mut state = 0x2545_f491_4f6c_dd1d;
+
+    for _ in 0..2000 {
+        let list = arbitrary_adtl_list(&mut state);
+        extract_labeled_text_from_list(&list);
+        extract_labels_from_list(&list);
+
+        // And through the reader, joined with cue points
+        let mut cue_bytes =
+            ((xorshift(&mut state) % 4) as u32).to_le_bytes().to_vec();
+        for _ in 0..xorshift(&mut state) % 3 {
+            let mut cue = arbitrary_cue_point(&mut state);
+            cue.id %= 8;
+            cue_bytes.extend_from_slice(&cue.as_bytes());
+        }
+
+        let wave = build_wave(&[
+            (
+                ChunkHead {
+                    tag: *b"cue ",
+                    size: cue_bytes.len() as u32,
+                },
+                Some(&cue_bytes),
+            ),
+            (
+                ChunkHead {
+                    tag: *b"LIST",
+                    size: list.len() as u32,
+                },
+                Some(&list),
+            ),
+        ]);
+        let mut reader = ChunkReader::new(io::Cursor::new(&wave[..])).unwrap();
+        reader.read_markers().unwrap();
+        reader.markers_iter().unwrap().for_each(drop);
+        reader.verify().unwrap();
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{adtl_sub_chunks, pad_len, tag, CuePoint, Error, CHUNK_HEAD_SZ};
+
+/// A sub-chunk of a "wavl" LIST, which some files use in place of a single
+/// "data" chunk.  `chunk_start` is the offset of the sub-chunk's header from
+/// the start of the list's data section, following the "wavl" list type, and
+/// is what cue points in such files store in `CuePoint::chunk_start`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaveSegment {
+    /// "data" sub-chunk of `len` bytes of samples
+    Data { chunk_start: u32, len: u32 },
+
+    /// "slnt" sub-chunk, standing for a number of silent sample frames
+    Silence {
+        chunk_start: u32,
+        sample_frames: u32,
+    },
+}
+
+impl WaveSegment {
+    pub fn chunk_start(&self) -> u32 {
+        match self {
+            Self::Data { chunk_start, .. } => *chunk_start,
+            Self::Silence { chunk_start, .. } => *chunk_start,
+        }
+    }
+
+    /// Tag of the sub-chunk, "data" or "slnt"
+    pub fn tag(&self) -> [u8; 4] {
+        match self {
+            Self::Data { .. } => tag::DATA,
+            Self::Silence { .. } => *b"slnt",
+        }
+    }
+
+    /// Number of sample frames the segment plays for
+    pub fn sample_frames(&self, block_align: u16) -> u64 {
+        match self {
+            Self::Data { len, .. } => {
+                u64::from(*len) / u64::from(block_align.max(1))
+            }
+            Self::Silence { sample_frames, .. } => u64::from(*sample_frames),
+        }
+    }
+}
+
+/// Contents of a "wavl" LIST of alternating "data" and "slnt" sub-chunks
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct WaveList {
+    /// "data" and "slnt" sub-chunks in file order; other sub-chunks are left
+    /// out
+    pub segments: Vec<WaveSegment>,
+}
+
+impl WaveList {
+    /// Parse the body of a LIST chunk with list type "wavl".  Sub-chunks that
+    /// run past the end of the list are dropped, along with all remaining
+    /// bytes, and "slnt" sub-chunks too short for a sample count are skipped.
+    ///
+    /// Errors if the list type is not "wavl"
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        if !bytes.starts_with(b"wavl") {
+            return Err(Error::wave("Not a wavl LIST"));
+        }
+
+        // Every sub-chunk yielded directly follows the previous one
+        let mut next_start = 0u64;
+        let segments = adtl_sub_chunks(bytes)
+            .filter_map(|(head, body)| {
+                let chunk_start = u32::try_from(next_start).ok()?;
+                next_start += CHUNK_HEAD_SZ as u64
+                    + u64::from(head.size)
+                    + pad_len(head.size.into());
+
+                match &head.tag {
+                    &tag::DATA => Some(WaveSegment::Data {
+                        chunk_start,
+                        len: head.size,
+                    }),
+                    b"slnt" => Some(WaveSegment::Silence {
+                        chunk_start,
+                        sample_frames: u32::from_le_bytes(
+                            *body.first_chunk::<4>()?,
+                        ),
+                    }),
+                    _ => None,
+                }
+            })
+            .collect();
+
+        Ok(WaveList { segments })
+    }
+
+    /// Total bytes of samples across every "data" sub-chunk
+    pub fn data_len(&self) -> u64 {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                WaveSegment::Data { len, .. } => u64::from(*len),
+                WaveSegment::Silence { .. } => 0,
+            })
+            .sum()
+    }
+
+    /// Total sample frames played, counting the samples of "data" sub-chunks
+    /// and the silence of "slnt" sub-chunks
+    pub fn sample_frames(&self, block_align: u16) -> u64 {
+        self.segments
+            .iter()
+            .map(|segment| segment.sample_frames(block_align))
+            .sum()
+    }
+
+    /// Seconds played at the given sample rate
+    pub fn duration_seconds(&self, block_align: u16, sample_rate: u32) -> f64 {
+        self.sample_frames(block_align) as f64 / f64::from(sample_rate.max(1))
+    }
+
+    /// Find the segment a cue point lies in, by its `chunk_start` and
+    /// `data_tag`.  The cue point's `sample_offset` is relative to the start
+    /// of that segment.
+    pub fn segment_for_cue(&self, cue: &CuePoint) -> Option<&WaveSegment> {
+        self.segments.iter().find(|segment| {
+            segment.chunk_start() == cue.chunk_start
+                && segment.tag() == cue.data_tag
+        })
+    }
+}
+
+/// Byte offset of the sample frame a PCM cue point marks.  The cue point's
+/// `block_start` is the byte offset, within the samples of its chunk, of the
+/// block holding the frame, and `sample_offset` counts frames of
+/// `block_align` bytes from there.
+///
+/// Without a wave list the offset is from the start of the samples of the
+/// single "data" chunk, as `chunk_start` is then 0.  With a wave list the
+/// offset is from the start of the list's data section, like `chunk_start`,
+/// so counts the headers of the list's sub-chunks; `None` is returned if the
+/// cue point's `chunk_start` and `data_tag` name no "data" sub-chunk.
+pub fn resolve_cue_byte_offset(
+    cue: &CuePoint,
+    block_align: u16,
+    wave_list: Option<&WaveList>,
+) -> Option<u64> {
+    let within_chunk = u64::from(cue.block_start)
+        + u64::from(cue.sample_offset) * u64::from(block_align);
+
+    match wave_list {
+        None => Some(within_chunk),
+        Some(wave_list) => match wave_list.segment_for_cue(cue)? {
+            WaveSegment::Data { chunk_start, .. } => Some(
+                u64::from(*chunk_start) + CHUNK_HEAD_SZ as u64 + within_chunk,
+            ),
+            WaveSegment::Silence { .. } => None,
+        },
+    }
+}
+
+impl<Cursor: std::io::Read + std::io::Seek> crate::ChunkReader<Cursor> {
+    /// Read the first "wavl" LIST, for files storing samples in alternating
+    /// "data" and "slnt" sub-chunks rather than a single "data" chunk.  The
+    /// reader is reset before and after reading.
+    ///
+    /// Errors if chunk is too large or if underlying cursor errors
+    pub fn read_wave_list(&mut self) -> Result<Option<WaveList>, Error> {
+        self.reset()?;
+        let mut wave_list = None;
+
+        while let Some(head) = self.seek_next_chunk(|h| h.tag == tag::LIST)? {
+            let body = self.read_chunk_body(&head)?;
+
+            if body.starts_with(b"wavl") {
+                wave_list = Some(WaveList::parse(&body)?);
+                break;
+            }
+        }
+
+        self.reset()?;
+        Ok(wave_list)
+    }
+}